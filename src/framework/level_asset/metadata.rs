@@ -0,0 +1,104 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Everything about a level that isn't geometry or objects.
+/// Inserted as a resource while the level is loaded.
+#[derive(Debug, Clone, PartialEq, Resource, Reflect, Serialize, Deserialize)]
+pub struct LevelMetadata {
+    pub name: String,
+    pub description: String,
+    pub author: String,
+    /// Seconds. `0.0` if the level has no par time
+    pub par_time: f32,
+    pub objective: LevelObjective,
+    pub ambient_light: AmbientLightDef,
+    pub directional_light: DirectionalLightDef,
+    /// Asset path of the looping background music. Empty for silence
+    pub bgm_path: String,
+}
+
+impl Default for LevelMetadata {
+    /// Matches what levels without metadata were hardcoded to
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            description: String::new(),
+            author: String::new(),
+            par_time: 0.0,
+            objective: LevelObjective::default(),
+            ambient_light: AmbientLightDef::default(),
+            directional_light: DirectionalLightDef::default(),
+            bgm_path: "Gamejam - Theme - 1.ogg".to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Reflect, Serialize, Deserialize)]
+pub struct LevelObjective {
+    /// Number of loot objects that have to be stolen to finish the level
+    pub required_loot: u32,
+    /// Whether the player has to make it back to their spawn point with the loot
+    pub return_to_spawn: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Reflect, Serialize, Deserialize)]
+pub struct AmbientLightDef {
+    /// sRGB
+    pub color: [f32; 3],
+    pub brightness: f32,
+}
+
+impl Default for AmbientLightDef {
+    fn default() -> Self {
+        Self {
+            color: [1.0, 1.0, 1.0],
+            brightness: 50.0,
+        }
+    }
+}
+
+impl AmbientLightDef {
+    pub fn to_ambient_light(&self) -> AmbientLight {
+        AmbientLight {
+            color: Color::srgb_from_array(self.color),
+            brightness: self.brightness,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Reflect, Serialize, Deserialize)]
+pub struct DirectionalLightDef {
+    /// sRGB
+    pub color: [f32; 3],
+    pub illuminance: f32,
+    pub shadows_enabled: bool,
+    pub position: Vec3,
+    pub look_at: Vec3,
+}
+
+impl Default for DirectionalLightDef {
+    fn default() -> Self {
+        Self {
+            color: [1.0, 1.0, 1.0],
+            illuminance: 500.0,
+            shadows_enabled: true,
+            position: Vec3::new(5.0, 10.0, -5.0),
+            look_at: Vec3::ZERO,
+        }
+    }
+}
+
+impl DirectionalLightDef {
+    pub fn to_bundle(&self) -> DirectionalLightBundle {
+        DirectionalLightBundle {
+            directional_light: DirectionalLight {
+                color: Color::srgb_from_array(self.color),
+                illuminance: self.illuminance,
+                shadows_enabled: self.shadows_enabled,
+                ..Default::default()
+            },
+            transform: Transform::from_translation(self.position).looking_at(self.look_at, Vec3::Y),
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! Version handling for `.level` files.
+//!
+//! The binary payload is bincode, which isn't self-describing: a file can only be read
+//! back with the exact `LevelAssetData` shape it was written with. Whenever that shape
+//! changes, bump [`LevelAsset::CURRENT_VERSION`], freeze the previous shape in a `vN`
+//! module next to this one with a `From` impl into the next version, and add an arm
+//! to [`read_data`] that deserializes it and walks the chain up to current.
+//!
+//! Versions before 4 never made it into the repository, so the chain starts there.
+
+use super::{v4, v5, v6, LevelAsset, LevelAssetData};
+use anyhow::{bail, Context};
+use serde::de::DeserializeOwned;
+
+/// An encoded `LevelAssetData` payload that can be read as any version's shape
+pub trait Payload {
+    fn deserialize<T: DeserializeOwned>(&self, version: u32) -> anyhow::Result<T>;
+}
+
+/// Deserializes a payload written with `version` and migrates it to the current layout
+pub fn read_data(version: u32, payload: impl Payload) -> anyhow::Result<LevelAssetData> {
+    match version {
+        LevelAsset::CURRENT_VERSION => payload.deserialize(version),
+        6 => Ok(payload.deserialize::<v6::LevelAssetData>(version)?.into()),
+        5 => {
+            let v6: v6::LevelAssetData = payload.deserialize::<v5::LevelAssetData>(version)?.into();
+            Ok(v6.into())
+        }
+        4 => {
+            let v5: v5::LevelAssetData = payload.deserialize::<v4::LevelAssetData>(version)?.into();
+            let v6: v6::LevelAssetData = v5.into();
+            Ok(v6.into())
+        }
+        v if v > LevelAsset::CURRENT_VERSION => bail!(
+            "Level format version {v} is newer than the newest supported version {}",
+            LevelAsset::CURRENT_VERSION
+        ),
+        v => bail!(
+            "Level format version {v} is too old, the oldest supported version is {}",
+            LevelAsset::OLDEST_SUPPORTED_VERSION
+        ),
+    }
+}
+
+/// The shipping format: bincode behind the version header
+pub struct Bincode<'a>(pub &'a [u8]);
+
+impl Payload for Bincode<'_> {
+    fn deserialize<T: DeserializeOwned>(&self, version: u32) -> anyhow::Result<T> {
+        bincode::deserialize(self.0)
+            .with_context(|| format!("Malformed version {version} level data"))
+    }
+}
+
+/// The text format: a whole `.level.ron` document
+#[cfg(not(target_family = "wasm"))]
+pub struct Ron<'a>(pub &'a [u8]);
+
+#[cfg(not(target_family = "wasm"))]
+impl Payload for Ron<'_> {
+    fn deserialize<T: DeserializeOwned>(&self, version: u32) -> anyhow::Result<T> {
+        #[derive(serde::Deserialize)]
+        struct Document<T> {
+            data: T,
+        }
+        let document: Document<T> = ron::de::from_bytes(self.0)
+            .with_context(|| format!("Malformed version {version} level data"))?;
+        Ok(document.data)
+    }
+}
@@ -0,0 +1,271 @@
+use crate::{
+    framework::{raw_mesh::RawMesh, tilemap::Tilemap},
+    game::objects::definitions::ObjectDef,
+};
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    reflect::TypePath,
+};
+use bevy_rapier3d::prelude::*;
+use metadata::LevelMetadata;
+use serde::{Deserialize, Serialize};
+
+pub mod metadata;
+pub mod migration;
+mod v4;
+mod v5;
+mod v6;
+
+#[cfg(not(target_family = "wasm"))]
+use {
+    std::fs::File,
+    std::io::{BufReader, BufWriter, Read, Write},
+};
+
+#[derive(Asset, TypePath, Serialize, Deserialize)]
+pub struct LevelAsset {
+    version: u32,
+    data: LevelAssetData,
+}
+impl LevelAsset {
+    pub const CURRENT_VERSION: u32 = 7;
+    /// Oldest version [`migration::read_data`] can still upgrade
+    pub const OLDEST_SUPPORTED_VERSION: u32 = 4;
+    /// Extension of the human-readable format, for review and hand-patching.
+    /// `.level` stays the shipping format.
+    pub const RON_EXTENSION: &'static str = "level.ron";
+
+    pub fn new(data: LevelAssetData) -> Self {
+        Self {
+            version: Self::CURRENT_VERSION,
+            data,
+        }
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    pub fn read(path: &str) -> anyhow::Result<LevelAsset> {
+        let file = File::open(path)?;
+        let mut reader = BufReader::new(file);
+
+        let mut data = vec![];
+        reader.read_to_end(&mut data)?;
+        if path.ends_with(Self::RON_EXTENSION) {
+            Self::from_ron_bytes(&data)
+        } else {
+            Self::from_bytes(&data)
+        }
+    }
+
+    pub fn from_bytes(data: &[u8]) -> anyhow::Result<Self> {
+        if data.len() < 4 {
+            anyhow::bail!("Not big enough");
+        }
+
+        let data = lz4_flex::decompress_size_prepended(data)?;
+        if data.len() < 4 {
+            anyhow::bail!("Missing version header");
+        }
+        let version = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        let data = migration::read_data(version, migration::Bincode(&data[4..]))?;
+
+        Ok(Self::new(data))
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    pub fn from_ron_bytes(data: &[u8]) -> anyhow::Result<Self> {
+        #[derive(Deserialize)]
+        struct VersionProbe {
+            version: u32,
+        }
+        let probe: VersionProbe = ron::de::from_bytes(data)?;
+        let data = migration::read_data(probe.version, migration::Ron(data))?;
+
+        Ok(Self::new(data))
+    }
+
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        let mut data = vec![];
+        data.extend_from_slice(&self.version.to_le_bytes());
+        data.extend_from_slice(&bincode::serialize(&self.data)?);
+
+        Ok(lz4_flex::compress_prepend_size(&data))
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    pub fn to_ron_string(&self) -> anyhow::Result<String> {
+        Ok(ron::ser::to_string_pretty(
+            self,
+            ron::ser::PrettyConfig::new(),
+        )?)
+    }
+
+    /// Writes the binary format, or the text format if `path` ends in `.level.ron`
+    #[cfg(not(target_family = "wasm"))]
+    pub fn save(&self, path: &str) -> anyhow::Result<()> {
+        let data = if path.ends_with(Self::RON_EXTENSION) {
+            self.to_ron_string()?.into_bytes()
+        } else {
+            self.to_bytes()?
+        };
+
+        let file = File::create(path)?;
+        let mut writer = BufWriter::new(file);
+        writer.write_all(&data)?;
+        Ok(())
+    }
+
+    pub fn data(&self) -> &LevelAssetData {
+        &self.data
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LevelAssetData {
+    pub metadata: LevelMetadata,
+    pub tilemap: Tilemap,
+    pub objects: Vec<ObjectDef>,
+    pub meshes: Vec<OrnamentalMesh>,
+    pub baked_ground_mesh: RawMesh,
+    pub baked_ground_collider: Collider,
+    pub baked_walls: Vec<BakedWallData>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BakedWallData {
+    pub collider: Collider,
+    pub mesh: RawMesh,
+}
+
+#[derive(Default)]
+pub struct LevelAssetLoader;
+
+impl AssetLoader for LevelAssetLoader {
+    type Asset = LevelAsset;
+    type Settings = ();
+    type Error = anyhow::Error;
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        cfg_if::cfg_if! {
+            if #[cfg(not(target_family = "wasm"))] {
+                let path = load_context.path().to_string_lossy();
+                if path.ends_with(LevelAsset::RON_EXTENSION) {
+                    return LevelAsset::from_ron_bytes(&bytes);
+                }
+            } else {
+                let _ = load_context;
+            }
+        }
+        LevelAsset::from_bytes(&bytes)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        cfg_if::cfg_if! {
+            if #[cfg(not(target_family = "wasm"))] {
+                &["level", LevelAsset::RON_EXTENSION]
+            } else {
+                &["level"]
+            }
+        }
+    }
+}
+
+/// A decorative glTF scene placed in the level
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrnamentalMesh {
+    /// Scene asset path, e.g. `props.glb#Scene0`
+    pub asset_path: String,
+    pub position: Vec3,
+    /// Euler angles in radians: yaw around y, then pitch around x, then roll around z
+    pub rotation: Vec3,
+    /// Whether to generate a collider from the scene's meshes once it's loaded
+    pub collider: bool,
+}
+
+impl OrnamentalMesh {
+    pub fn transform(&self) -> Transform {
+        let r = self.rotation;
+        Transform::from_translation(self.position).with_rotation(Quat::from_euler(
+            EulerRot::YXZ,
+            r.y,
+            r.x,
+            r.z,
+        ))
+    }
+}
+
+#[test]
+fn test_level_versions() {
+    use crate::framework::tilemap::TileFlags;
+
+    let fixture = include_bytes!("../../../tests/fixtures/level_v4.level");
+    let asset = LevelAsset::from_bytes(fixture).unwrap();
+    assert_eq!(asset.version, LevelAsset::CURRENT_VERSION);
+    assert_eq!(asset.data.metadata, LevelMetadata::default());
+
+    let fixture = include_bytes!("../../../tests/fixtures/level_v5.level");
+    let v5 = LevelAsset::from_bytes(fixture).unwrap();
+    assert_eq!(v5.version, LevelAsset::CURRENT_VERSION);
+    assert_eq!(v5.data.objects.len(), 12);
+    assert!(v5.data.meshes.is_empty());
+
+    let fixture = include_bytes!("../../../tests/fixtures/level_v6.level");
+    let v6 = LevelAsset::from_bytes(fixture).unwrap();
+    assert_eq!(v6.version, LevelAsset::CURRENT_VERSION);
+    assert!(v6.data.tilemap.face_data() == v5.data.tilemap.face_data());
+    assert!(v6.data.tilemap.vert_data() == v5.data.tilemap.vert_data());
+    assert!(v6
+        .data
+        .tilemap
+        .face_data()
+        .iter()
+        .all(|f| f.flags == TileFlags::NONE));
+
+    let reloaded = LevelAsset::from_bytes(&asset.to_bytes().unwrap()).unwrap();
+    assert_eq!(reloaded.data.tilemap.dims(), asset.data.tilemap.dims());
+    assert_eq!(reloaded.data.objects.len(), asset.data.objects.len());
+    assert_eq!(
+        reloaded.data.baked_walls.len(),
+        asset.data.baked_walls.len()
+    );
+
+    let with_version = |version: u32| {
+        let mut bytes = version.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&bincode::serialize(&asset.data).unwrap());
+        lz4_flex::compress_prepend_size(&bytes)
+    };
+    assert!(LevelAsset::from_bytes(&with_version(LevelAsset::CURRENT_VERSION)).is_ok());
+    assert!(LevelAsset::from_bytes(&with_version(LevelAsset::CURRENT_VERSION + 1)).is_err());
+    assert!(
+        LevelAsset::from_bytes(&with_version(LevelAsset::OLDEST_SUPPORTED_VERSION - 1)).is_err()
+    );
+    assert!(LevelAsset::from_bytes(&lz4_flex::compress_prepend_size(&[1, 2])).is_err());
+}
+
+#[test]
+fn test_level_ron() {
+    let fixture = include_bytes!("../../../tests/fixtures/level_v4.level");
+    let asset = LevelAsset::from_bytes(fixture).unwrap();
+
+    let text = asset.to_ron_string().unwrap();
+    let reloaded = LevelAsset::from_ron_bytes(text.as_bytes()).unwrap();
+    assert_eq!(
+        bincode::serialize(&reloaded.data).unwrap(),
+        bincode::serialize(&asset.data).unwrap()
+    );
+
+    let future = text.replacen(
+        &format!("version: {}", LevelAsset::CURRENT_VERSION),
+        &format!("version: {}", LevelAsset::CURRENT_VERSION + 1),
+        1,
+    );
+    assert_ne!(future, text);
+    assert!(LevelAsset::from_ron_bytes(future.as_bytes()).is_err());
+}
@@ -0,0 +1,31 @@
+//! Frozen `LevelAssetData` layout of format version 4, before the metadata block.
+//! The nested types are shared with later versions until one of them changes.
+
+use super::{metadata::LevelMetadata, v5, v5::OrnamentalMesh, v6::Tilemap, BakedWallData};
+use crate::{framework::raw_mesh::RawMesh, game::objects::definitions::ObjectDef};
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct LevelAssetData {
+    pub tilemap: Tilemap,
+    pub objects: Vec<ObjectDef>,
+    pub meshes: Vec<OrnamentalMesh>,
+    pub baked_ground_mesh: RawMesh,
+    pub baked_ground_collider: Collider,
+    pub baked_walls: Vec<BakedWallData>,
+}
+
+impl From<LevelAssetData> for v5::LevelAssetData {
+    fn from(data: LevelAssetData) -> Self {
+        Self {
+            metadata: LevelMetadata::default(),
+            tilemap: data.tilemap,
+            objects: data.objects,
+            meshes: data.meshes,
+            baked_ground_mesh: data.baked_ground_mesh,
+            baked_ground_collider: data.baked_ground_collider,
+            baked_walls: data.baked_walls,
+        }
+    }
+}
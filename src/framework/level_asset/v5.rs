@@ -0,0 +1,53 @@
+//! Frozen `LevelAssetData` layout of format version 5, before ornamental meshes had
+//! the collider flag.
+
+use super::{
+    metadata::LevelMetadata,
+    v6::{self, Tilemap},
+    BakedWallData,
+};
+use crate::{framework::raw_mesh::RawMesh, game::objects::definitions::ObjectDef};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct LevelAssetData {
+    pub metadata: LevelMetadata,
+    pub tilemap: Tilemap,
+    pub objects: Vec<ObjectDef>,
+    pub meshes: Vec<OrnamentalMesh>,
+    pub baked_ground_mesh: RawMesh,
+    pub baked_ground_collider: Collider,
+    pub baked_walls: Vec<BakedWallData>,
+}
+
+#[derive(Deserialize)]
+pub struct OrnamentalMesh {
+    pub asset_path: String,
+    pub position: Vec3,
+    pub rotation: Vec3,
+}
+
+impl From<LevelAssetData> for v6::LevelAssetData {
+    fn from(data: LevelAssetData) -> Self {
+        Self {
+            metadata: data.metadata,
+            tilemap: data.tilemap,
+            objects: data.objects,
+            meshes: data
+                .meshes
+                .into_iter()
+                .map(|mesh| super::OrnamentalMesh {
+                    asset_path: mesh.asset_path,
+                    position: mesh.position,
+                    rotation: mesh.rotation,
+                    collider: false,
+                })
+                .collect(),
+            baked_ground_mesh: data.baked_ground_mesh,
+            baked_ground_collider: data.baked_ground_collider,
+            baked_walls: data.baked_walls,
+        }
+    }
+}
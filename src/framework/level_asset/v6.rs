@@ -0,0 +1,73 @@
+//! Frozen `LevelAssetData` layout of format version 6, before faces had tile flags.
+//! Versions 4 and 5 share its tilemap.
+
+use super::{metadata::LevelMetadata, BakedWallData, OrnamentalMesh};
+use crate::{
+    framework::{
+        grid::Grid,
+        raw_mesh::RawMesh,
+        tilemap::{self, VertData, MAX_NUM_WALLS},
+    },
+    game::objects::definitions::ObjectDef,
+};
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct LevelAssetData {
+    pub metadata: LevelMetadata,
+    pub tilemap: Tilemap,
+    pub objects: Vec<ObjectDef>,
+    pub meshes: Vec<OrnamentalMesh>,
+    pub baked_ground_mesh: RawMesh,
+    pub baked_ground_collider: Collider,
+    pub baked_walls: Vec<BakedWallData>,
+}
+
+#[derive(Deserialize)]
+pub struct Tilemap {
+    face_grid: Grid,
+    #[allow(dead_code)]
+    vert_grid: Grid,
+    face_data: Vec<FaceData>,
+    vert_data: Vec<VertData>,
+}
+
+#[derive(Deserialize)]
+pub struct FaceData {
+    pub wall_height: u32,
+    pub wall_top_tile_id: u32,
+    pub wall_side_tile_ids: [u32; 4 * MAX_NUM_WALLS],
+    pub tile_id: u32,
+}
+
+impl From<Tilemap> for tilemap::Tilemap {
+    fn from(old: Tilemap) -> Self {
+        let mut tilemap = tilemap::Tilemap::new(old.face_grid.dims(), 0).unwrap();
+        for (face, old) in tilemap.face_data_mut().iter_mut().zip(old.face_data) {
+            *face = tilemap::FaceData {
+                wall_height: old.wall_height,
+                wall_top_tile_id: old.wall_top_tile_id,
+                wall_side_tile_ids: old.wall_side_tile_ids,
+                tile_id: old.tile_id,
+                flags: tilemap::TileFlags::NONE,
+            };
+        }
+        tilemap.vert_data_mut().clone_from_slice(&old.vert_data);
+        tilemap
+    }
+}
+
+impl From<LevelAssetData> for super::LevelAssetData {
+    fn from(data: LevelAssetData) -> Self {
+        Self {
+            metadata: data.metadata,
+            tilemap: data.tilemap.into(),
+            objects: data.objects,
+            meshes: data.meshes,
+            baked_ground_mesh: data.baked_ground_mesh,
+            baked_ground_collider: data.baked_ground_collider,
+            baked_walls: data.baked_walls,
+        }
+    }
+}
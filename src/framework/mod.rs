@@ -55,4 +55,14 @@ impl Pnormal2 {
             _ => None?,
         })
     }
+
+    /// Direction on the ground plane, with the second axis mapped to z
+    pub fn to_vec3(self) -> Vec3 {
+        match self {
+            Pnormal2::NO => Vec3::new(-1.0, 0.0,  0.0),
+            Pnormal2::ON => Vec3::new( 0.0, 0.0, -1.0),
+            Pnormal2::PO => Vec3::new( 1.0, 0.0,  0.0),
+            Pnormal2::OP => Vec3::new( 0.0, 0.0,  1.0),
+        }
+    }
 }
@@ -1,13 +1,138 @@
-use bevy::prelude::*;
+use crate::framework::tilemap::{TileFlags, Tilemap, SLOPE_HEIGHT, TILE_DIMS};
+use bevy::{prelude::*, utils::HashMap};
 use itertools::Itertools;
-use polyanya::{Polygon, Vertex};
+use polyanya::{MeshError, Polygon, Vertex};
+use vleue_navigator::NavMesh;
 
+/// Largest distance grid vertices are moved away from walls, more than half a tile would
+/// close the walkable area of single tile corridors
+const MAX_VERTEX_PADDING: f32 = 0.45;
+
+/// Which level navmesh an entity paths on, by its size and the faces it can't walk on
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Component)]
+pub enum NavAgent {
+    /// Squeezes through gaps the others can't
+    #[default]
+    Minion,
+    /// For auto-walking the player
+    Player,
+    Large,
+}
+
+impl NavAgent {
+    pub const VARIANTS: [NavAgent; 3] = [NavAgent::Minion, NavAgent::Player, NavAgent::Large];
+
+    /// Distance the walkable area keeps from walls
+    pub fn radius(self) -> f32 {
+        match self {
+            NavAgent::Minion => 0.3,
+            NavAgent::Player => 0.35,
+            NavAgent::Large => 1.2,
+        }
+    }
+
+    pub fn blocking_flags(self) -> TileFlags {
+        match self {
+            NavAgent::Minion | NavAgent::Large => MINION_BLOCKING_FLAGS,
+            NavAgent::Player => PLAYER_BLOCKING_FLAGS,
+        }
+    }
+
+    /// Splits the radius into whole faces cut out around walls and the padding of the
+    /// remaining vertices. Rounds up to whole faces where the padding would be too large.
+    fn wall_clearance(self) -> (u32, f32) {
+        let radius = self.radius();
+        let faces = radius.floor();
+        if radius - faces > MAX_VERTEX_PADDING {
+            (faces as u32 + 1, 0.0)
+        } else {
+            (faces as u32, radius - faces)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ObjectObstacle {
     pub coord: UVec2, // top-left
     pub dims: UVec2,
 }
 
-/// Todo: Call this when an obstacle object is destroyed to recreate the navmesh.
+impl ObjectObstacle {
+    /// Covers the faces with their center inside a rectangle of `size` around `center`,
+    /// `None` if there are none
+    pub fn from_footprint(tilemap: &Tilemap, center: Vec3, size: Vec2) -> Option<Self> {
+        let coord = (center.xz() - tilemap.to_pos_offset()) / TILE_DIMS - 0.5;
+        let half = size * 0.5 / TILE_DIMS;
+        let min = ((coord - half).floor() + 1.0).as_ivec2().max(IVec2::ZERO);
+        let max = ((coord + half).ceil() - 1.0)
+            .as_ivec2()
+            .min(tilemap.dims().as_ivec2() - 1);
+        (min.cmple(max).all()).then(|| Self {
+            coord: min.as_uvec2(),
+            dims: (max - min + 1).as_uvec2(),
+        })
+    }
+}
+
+/// Faces around a grid vertex, in the order of its neighbor polygons
+const VERT_TO_POLY_CCW: [IVec2; 4] = [
+    IVec2::new(-1, -1),
+    IVec2::new(-1, 0),
+    IVec2::new(0, 0),
+    IVec2::new(0, -1),
+];
+
+/// Faces covered by walls or objects
+fn grid_holes(dims: UVec2, walls: &[bool], objects: &[ObjectObstacle]) -> Vec<bool> {
+    let mut holes = walls.to_vec();
+    for obj in objects {
+        for y in obj.coord.y..(obj.coord.y + obj.dims.y).min(dims.y) {
+            for x in obj.coord.x..(obj.coord.x + obj.dims.x).min(dims.x) {
+                holes[(y * dims.x + x) as usize] = true;
+            }
+        }
+    }
+    holes
+}
+
+/// Grows holes by `distance` faces in every direction, faces next to the grid border
+/// count as next to a hole
+fn grow_holes(dims: UVec2, holes: &[bool], distance: u32) -> Vec<bool> {
+    let distance = distance as i32;
+    (0..dims.y as i32)
+        .cartesian_product(0..dims.x as i32)
+        .map(|(y, x)| {
+            (-distance..=distance)
+                .cartesian_product(-distance..=distance)
+                .any(|(dy, dx)| {
+                    let coord = IVec2::new(x + dx, y + dy);
+                    coord.cmplt(IVec2::ZERO).any()
+                        || coord.cmpge(dims.as_ivec2()).any()
+                        || holes[(coord.y as u32 * dims.x + coord.x as u32) as usize]
+                })
+        })
+        .collect()
+}
+
+/// Moves a grid vertex away from the holes around it, given in `VERT_TO_POLY_CCW` order
+fn vertex_padding(holes: [bool; 4], padding: f32) -> Vec2 {
+    let mut pad = Vec2::ZERO;
+    if holes[0] || holes[1] {
+        pad.x += padding
+    }
+    if holes[1] || holes[2] {
+        pad.y -= padding;
+    }
+    if holes[2] || holes[3] {
+        pad.x -= padding;
+    }
+    if holes[3] || holes[0] {
+        pad.y += padding;
+    }
+    pad
+}
+
 pub fn create_grid_mesh_with_holes(
     dims: UVec2,
     walls: &[bool],
@@ -19,21 +144,8 @@ pub fn create_grid_mesh_with_holes(
 
     let offset = dims.as_vec2() * -0.5;
 
-    let is_hole = |x: u32, y: u32| -> bool {
-        if walls[(y * dims.x + x) as usize] {
-            return true;
-        }
-        for obj in objects {
-            if x >= obj.coord.x
-                && x < obj.coord.x + obj.dims.x
-                && y >= obj.coord.y
-                && y < obj.coord.y + obj.dims.y
-            {
-                return true;
-            }
-        }
-        false
-    };
+    let holes = grid_holes(dims, walls, objects);
+    let is_hole = |x: u32, y: u32| -> bool { holes[(y * dims.x + x) as usize] };
 
     let mut num_polys = 0;
     for y in 0..dims.y {
@@ -52,12 +164,6 @@ pub fn create_grid_mesh_with_holes(
             let mut list = vec![];
             let mut holes = [false; 4];
 
-            const VERT_TO_POLY_CCW: [IVec2; 4] = [
-                IVec2::new(-1, -1),
-                IVec2::new(-1, 0),
-                IVec2::new(0, 0),
-                IVec2::new(0, -1),
-            ];
             for (i, d) in VERT_TO_POLY_CCW.into_iter().enumerate() {
                 let coord = coord + d;
                 if coord.x < 0
@@ -104,20 +210,7 @@ pub fn create_grid_mesh_with_holes(
                 (false, false, false, false) => {}
             }
 
-            let mut pad = Vec2::ZERO;
-            if holes[0] || holes[1] {
-                pad.x += padding
-            }
-            if holes[1] || holes[2] {
-                pad.y -= padding;
-            }
-            if holes[2] || holes[3] {
-                pad.x -= padding;
-            }
-            if holes[3] || holes[0] {
-                pad.y += padding;
-            }
-
+            let pad = vertex_padding(holes, padding);
             let pos = Vec2::new(coord.x as f32, coord.y as f32) + offset + pad;
 
             let id = (y * (dims.x + 1) + x) as usize;
@@ -125,7 +218,6 @@ pub fn create_grid_mesh_with_holes(
             num_verts += 1
         }
     }
-    dbg!(&num_verts);
 
     for y in 0..dims.y {
         for x in 0..dims.x {
@@ -165,3 +257,800 @@ pub fn create_grid_mesh_with_holes(
 
     (vertices, polygons)
 }
+
+/// Like [`create_grid_mesh_with_holes`], but merges walkable faces lying in the same plane
+/// into rectangles, greedily row by row. `planes` holds a key per face, faces without one
+/// are never merged. Faces next to holes keep polygons of their own, so the padding stays
+/// the same and merged polygons only have straight edges.
+pub fn create_merged_grid_mesh_with_holes(
+    dims: UVec2,
+    walls: &[bool],
+    objects: &[ObjectObstacle],
+    planes: &[Option<IVec3>],
+    padding: f32,
+) -> (Vec<Vertex>, Vec<Polygon>) {
+    let holes = grid_holes(dims, walls, objects);
+    let rects = merge_faces(dims, &holes, planes, vec![]);
+    rects_to_mesh(dims, &rects, padding)
+}
+
+/// Rectangle of faces as min and max (exclusive) coordinates
+type FaceRect = (UVec2, UVec2);
+
+/// Which of `rects` each face belongs to, `None` for the faces outside of all
+fn rect_owners(dims: UVec2, rects: &[FaceRect]) -> Vec<Option<isize>> {
+    let mut owners = vec![None; dims.element_product() as usize];
+    for (id, (min, max)) in rects.iter().enumerate() {
+        for y in min.y..max.y {
+            for x in min.x..max.x {
+                owners[(y * dims.x + x) as usize] = Some(id as isize);
+            }
+        }
+    }
+    owners
+}
+
+/// Merges the faces that aren't holes and not in one of the `kept` rectangles yet, see
+/// [`create_merged_grid_mesh_with_holes`]. Returns the kept rectangles followed by the new.
+fn merge_faces(
+    dims: UVec2,
+    holes: &[bool],
+    planes: &[Option<IVec3>],
+    kept: Vec<FaceRect>,
+) -> Vec<FaceRect> {
+    let face_id = |coord: IVec2| -> Option<usize> {
+        (coord.cmpge(IVec2::ZERO).all() && coord.cmplt(dims.as_ivec2()).all())
+            .then(|| (coord.y as u32 * dims.x + coord.x as u32) as usize)
+    };
+    let is_hole = |coord: IVec2| face_id(coord).map_or(true, |id| holes[id]);
+    let mergeable = (0..dims.y)
+        .cartesian_product(0..dims.x)
+        .map(|(y, x)| {
+            let coord = IVec2::new(x as i32, y as i32);
+            planes[(y * dims.x + x) as usize].is_some()
+                && (-1..=1)
+                    .cartesian_product(-1..=1)
+                    .all(|(dx, dy)| !is_hole(coord + IVec2::new(dx, dy)))
+        })
+        .collect_vec();
+
+    let mut owners = rect_owners(dims, &kept);
+    let mut rects = kept;
+    for y in 0..dims.y {
+        for x in 0..dims.x {
+            let id = (y * dims.x + x) as usize;
+            if holes[id] || owners[id].is_some() {
+                continue;
+            }
+            let mut max = UVec2::new(x + 1, y + 1);
+            if mergeable[id] {
+                let fits = |x: u32, y: u32| {
+                    let other = (y * dims.x + x) as usize;
+                    mergeable[other] && owners[other].is_none() && planes[other] == planes[id]
+                };
+                while max.x < dims.x && fits(max.x, y) {
+                    max.x += 1;
+                }
+                while max.y < dims.y && (x..max.x).all(|x| fits(x, max.y)) {
+                    max.y += 1;
+                }
+            }
+            for y in y..max.y {
+                for x in x..max.x {
+                    owners[(y * dims.x + x) as usize] = Some(rects.len() as isize);
+                }
+            }
+            rects.push((UVec2::new(x, y), max));
+        }
+    }
+    rects
+}
+
+/// Polygons of the rectangles of faces, their vertices moved away from the faces outside
+/// of all by `padding`
+fn rects_to_mesh(dims: UVec2, rects: &[FaceRect], padding: f32) -> (Vec<Vertex>, Vec<Polygon>) {
+    let offset = dims.as_vec2() * -0.5;
+    let owners = rect_owners(dims, rects);
+    let face_id = |coord: IVec2| -> Option<usize> {
+        (coord.cmpge(IVec2::ZERO).all() && coord.cmplt(dims.as_ivec2()).all())
+            .then(|| (coord.y as u32 * dims.x + coord.x as u32) as usize)
+    };
+
+    // Only rectangle corners become vertices, they end up on the edges of larger neighbors
+    let vert_stride = dims.x + 1;
+    let mut vertex_ids = vec![None; (vert_stride * (dims.y + 1)) as usize];
+    for (min, max) in rects {
+        for corner in [
+            *min,
+            UVec2::new(max.x, min.y),
+            *max,
+            UVec2::new(min.x, max.y),
+        ] {
+            vertex_ids[(corner.y * vert_stride + corner.x) as usize] = Some(0);
+        }
+    }
+    let mut vertices = vec![];
+    for y in 0..=dims.y {
+        for x in 0..=dims.x {
+            let id = (y * vert_stride + x) as usize;
+            if vertex_ids[id].is_none() {
+                continue;
+            }
+            let coord = IVec2::new(x as i32, y as i32);
+            let around = VERT_TO_POLY_CCW
+                .map(|d| face_id(coord + d).and_then(|id| owners[id]).unwrap_or(-1));
+            let mut list = around.to_vec();
+            list.dedup();
+            if list.len() > 1 && list.first() == list.last() {
+                list.pop();
+            }
+
+            let pad = vertex_padding(around.map(|p| p == -1), padding);
+            let pos = coord.as_vec2() + offset + pad;
+
+            vertex_ids[id] = Some(vertices.len() as u32);
+            vertices.push(Vertex::new(pos, list));
+        }
+    }
+
+    let polygons = rects
+        .iter()
+        .map(|(min, max)| {
+            // Same winding as the single face polygons, starting at the corner (max.x, min.y)
+            let boundary = (min.y..max.y)
+                .map(|y| UVec2::new(max.x, y))
+                .chain((min.x + 1..=max.x).rev().map(|x| UVec2::new(x, max.y)))
+                .chain((min.y + 1..=max.y).rev().map(|y| UVec2::new(min.x, y)))
+                .chain((min.x..max.x).map(|x| UVec2::new(x, min.y)));
+            let list = boundary
+                .filter_map(|c| vertex_ids[(c.y * vert_stride + c.x) as usize])
+                .collect_vec();
+            Polygon::new(list, false)
+        })
+        .collect_vec();
+
+    (vertices, polygons)
+}
+
+/// Faces minions can't walk on, on top of walls
+pub const MINION_BLOCKING_FLAGS: TileFlags = TileFlags::HAZARD.union(TileFlags::PLAYER_ONLY);
+
+/// Faces the player can't walk on or doesn't walk into on their own, on top of walls
+pub const PLAYER_BLOCKING_FLAGS: TileFlags = TileFlags::HAZARD.union(TileFlags::MINION_ONLY);
+
+/// Steepest slope characters can walk up, the climb limit of their kinematic controller
+pub const MAX_WALKABLE_SLOPE: f32 = std::f32::consts::FRAC_PI_4;
+
+/// Builds the navmesh of an agent for a level, with its walls, its slopes too steep to climb
+/// and the faces the agent avoids cut out, in world space. Also returns the elevation of its
+/// vertices.
+pub fn create_level_navmesh(
+    tilemap: &Tilemap,
+    objects: &[ObjectObstacle],
+    agent: NavAgent,
+) -> Result<(NavMesh, NavElevation), MeshError> {
+    let mut layout = NavMeshLayout::new(tilemap, agent);
+    layout.set_obstacles(objects);
+    layout.build()
+}
+
+/// The faces of a level an agent walks on, merged into rectangles. Kept between rebuilds of
+/// the navmesh, so obstacles that come and go only get the faces around them merged again.
+#[derive(Clone, Debug)]
+pub struct NavMeshLayout {
+    dims: UVec2,
+    /// Faces the agent can't walk on, before growing them by its clearance
+    walls: Vec<bool>,
+    planes: Vec<Option<IVec3>>,
+    grow: u32,
+    padding: f32,
+    /// Elevation of the grid vertices
+    heights: Vec<f32>,
+    /// Walls and obstacles, grown by the clearance of the agent
+    holes: Vec<bool>,
+    rects: Vec<FaceRect>,
+}
+
+impl NavMeshLayout {
+    /// Layout without any obstacles
+    pub fn new(tilemap: &Tilemap, agent: NavAgent) -> Self {
+        let walls = tilemap
+            .faces()
+            .enumerate()
+            .map(|(fid, face)| {
+                face.wall_height > 0
+                    || face.flags.intersects(agent.blocking_flags())
+                    || tilemap.face_slope(fid as u32) > MAX_WALKABLE_SLOPE
+            })
+            .collect::<Vec<_>>();
+        let planes = (0..walls.len() as u32)
+            .map(|fid| face_plane(tilemap, fid))
+            .collect_vec();
+        let heights = tilemap
+            .vert_data()
+            .iter()
+            .map(|v| v.elevation as f32 * SLOPE_HEIGHT)
+            .collect();
+        let (grow, padding) = agent.wall_clearance();
+
+        let mut layout = Self {
+            dims: tilemap.dims(),
+            walls,
+            planes,
+            grow,
+            padding,
+            heights,
+            holes: vec![],
+            rects: vec![],
+        };
+        layout.holes = layout.holes_with(&[]);
+        layout.rects = merge_faces(layout.dims, &layout.holes, &layout.planes, vec![]);
+        layout
+    }
+
+    fn holes_with(&self, objects: &[ObjectObstacle]) -> Vec<bool> {
+        let holes = grid_holes(self.dims, &self.walls, objects);
+        match self.grow {
+            0 => holes,
+            grow => grow_holes(self.dims, &holes, grow),
+        }
+    }
+
+    /// Cuts the faces under `objects` out instead of the ones before. Only the rectangles
+    /// next to faces that changed are merged again. False if no face changed.
+    pub fn set_obstacles(&mut self, objects: &[ObjectObstacle]) -> bool {
+        let holes = self.holes_with(objects);
+        let dims = self.dims.as_ivec2();
+        let changed = |coord: IVec2| {
+            coord.cmpge(IVec2::ZERO).all() && coord.cmplt(dims).all() && {
+                let id = (coord.y * dims.x + coord.x) as usize;
+                holes[id] != self.holes[id]
+            }
+        };
+        // Faces next to holes aren't merged, so the neighbors of changed faces change too
+        let dirty = (0..dims.y)
+            .cartesian_product(0..dims.x)
+            .map(|(y, x)| {
+                (-1..=1)
+                    .cartesian_product(-1..=1)
+                    .any(|(dy, dx)| changed(IVec2::new(x + dx, y + dy)))
+            })
+            .collect_vec();
+        if !dirty.contains(&true) {
+            return false;
+        }
+
+        let kept = self
+            .rects
+            .iter()
+            .filter(|(min, max)| {
+                (min.y..max.y)
+                    .cartesian_product(min.x..max.x)
+                    .all(|(y, x)| !dirty[(y * self.dims.x + x) as usize])
+            })
+            .copied()
+            .collect_vec();
+        self.rects = merge_faces(self.dims, &holes, &self.planes, kept);
+        self.holes = holes;
+        true
+    }
+
+    /// Navmesh of the current layout, in world space
+    pub fn build(&self) -> Result<(NavMesh, NavElevation), MeshError> {
+        let (vertices, polygons) = rects_to_mesh(self.dims, &self.rects, self.padding);
+        let elevation = NavElevation::new(self.dims, &self.heights, &vertices);
+        let mut navmesh = NavMesh::from_polyanya_mesh(polyanya::Mesh::new(vertices, polygons)?);
+        navmesh.set_transform(Transform::from_rotation(Quat::from_rotation_x(
+            -std::f32::consts::FRAC_PI_2,
+        )));
+        Ok((navmesh, elevation))
+    }
+}
+
+/// Heights of the vertices of a level navmesh, the elevation of the tilemap vertices they
+/// were made from (`vert_data` × [`SLOPE_HEIGHT`]). Polyanya meshes are flat, so they're
+/// kept next to it.
+#[derive(Clone, Debug, Default)]
+pub struct NavElevation {
+    /// By navmesh vertex id
+    vertices: Vec<Vec3>,
+    /// By position, for the corners of paths
+    heights: HashMap<IVec2, f32>,
+}
+
+impl NavElevation {
+    /// Positions closer than a 1/PRECISION of a tile are the same vertex
+    const PRECISION: f32 = 1024.0;
+
+    /// `heights` of the grid vertices of a grid of `dims` faces
+    fn new(dims: UVec2, heights: &[f32], vertices: &[Vertex]) -> Self {
+        let offset = dims.as_vec2() * 0.5;
+        let vertices = vertices
+            .iter()
+            .map(|vertex| {
+                // Vertices are moved less than half a tile away from their grid vertex
+                let coord = (vertex.coords + offset).round().as_uvec2();
+                let height = heights[(coord.y * (dims.x + 1) + coord.x) as usize];
+                Vec3::new(vertex.coords.x, height, vertex.coords.y)
+            })
+            .collect_vec();
+        let heights = vertices.iter().map(|v| (Self::key(*v), v.y)).collect();
+        Self { vertices, heights }
+    }
+
+    fn key(pos: Vec3) -> IVec2 {
+        (pos.xz() * Self::PRECISION).round().as_ivec2()
+    }
+
+    /// Position of a navmesh vertex in the world
+    pub fn vertex(&self, id: u32) -> Vec3 {
+        self.vertices[id as usize]
+    }
+
+    /// Lifts the points of a path found on the navmesh to `end`. Its corners are vertices
+    /// of the navmesh and get their height, the end keeps the one it was asked for.
+    pub fn elevate(&self, path: Vec<Vec3>, end: Vec3) -> Vec<Vec3> {
+        path.into_iter()
+            .map(|p| match self.heights.get(&Self::key(p)) {
+                Some(height) => p.with_y(*height),
+                None => p.with_y(end.y),
+            })
+            .collect()
+    }
+}
+
+/// Plane a face lies in, as elevation steps per face along X and Z and the elevation at
+/// the grid origin. `None` for faces bent along their diagonal.
+fn face_plane(tilemap: &Tilemap, fid: u32) -> Option<IVec3> {
+    let [e00, e10, e01, e11] = tilemap
+        .face_id_to_vert_ids(fid)
+        .map(|vid| tilemap.vert_data()[vid as usize].elevation as i32);
+    let coord = tilemap.face_grid().id_to_coord(fid).as_ivec2();
+    let (dx, dz) = (e10 - e00, e01 - e00);
+    (e00 + e11 == e10 + e01).then_some(IVec3::new(dx, dz, e00 - dx * coord.x - dz * coord.y))
+}
+
+/// Finds the point of the navmesh closest to `pos`, searching in rings up to `max_distance`
+/// away. Used for targets standing on their own obstacle.
+pub fn closest_point_in_mesh(navmesh: &NavMesh, pos: Vec3, max_distance: f32) -> Option<Vec3> {
+    const RING_STEP: f32 = 0.25;
+    const RING_SAMPLES: u32 = 16;
+
+    if navmesh.transformed_is_in_mesh(pos) {
+        return Some(pos);
+    }
+    (1..=(max_distance / RING_STEP).ceil() as u32).find_map(|ring| {
+        let radius = ring as f32 * RING_STEP;
+        (0..RING_SAMPLES)
+            .map(|i| {
+                let angle = i as f32 / RING_SAMPLES as f32 * std::f32::consts::TAU;
+                pos + Vec3::new(angle.cos(), 0.0, angle.sin()) * radius
+            })
+            .find(|p| navmesh.transformed_is_in_mesh(*p))
+    })
+}
+
+/// How far from the navmesh the ends of a link may be to still be walked to
+pub const NAV_LINK_SEARCH_DIST: f32 = 1.0;
+
+/// A way between two points of the level that isn't walked, e.g. a drop off a ledge or a
+/// ventilation shaft between rooms
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NavLink {
+    pub start: Vec3,
+    pub end: Vec3,
+    /// Can also be taken from `end` to `start`
+    pub two_way: bool,
+}
+
+impl NavLink {
+    pub fn reversed(self) -> Self {
+        Self {
+            start: self.end,
+            end: self.start,
+            ..self
+        }
+    }
+}
+
+/// Part of a path across links
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathLeg {
+    /// Points to walk to in order, on the navmesh
+    Walk(Vec<Vec3>),
+    /// Link to take from its start to its end
+    Link(NavLink),
+}
+
+/// Finds the shortest way from `from` to `to`, walking on the navmesh and taking links
+/// to get between its parts. Both points need to be in the navmesh.
+pub fn path_with_links(
+    navmesh: &NavMesh,
+    elevation: &NavElevation,
+    links: &[NavLink],
+    from: Vec3,
+    to: Vec3,
+) -> Option<Vec<PathLeg>> {
+    let walk = |a: Vec3, b: Vec3| -> Option<(f32, Vec<Vec3>)> {
+        if a.distance(b) < 1e-3 {
+            return Some((0.0, vec![]));
+        }
+        navmesh
+            .transformed_path(a, b)
+            .map(|p| (p.length, elevation.elevate(p.path, b)))
+    };
+
+    let links = links
+        .iter()
+        .flat_map(|l| std::iter::once(*l).chain(l.two_way.then(|| l.reversed())))
+        .collect_vec();
+    let snap = |p| closest_point_in_mesh(navmesh, p, NAV_LINK_SEARCH_DIST);
+    let starts = links.iter().map(|l| snap(l.start)).collect_vec();
+    let ends = links.iter().map(|l| snap(l.end)).collect_vec();
+
+    // Dijkstra over the link ends, the cost of one is the way to it and the link before
+    let mut costs = vec![f32::INFINITY; links.len()];
+    let mut prev = vec![None; links.len()];
+    let mut done = vec![false; links.len()];
+    let mut best = walk(from, to).map(|(length, _)| (length, None));
+    let mut current = (from, 0.0, None);
+    loop {
+        let (pos, cost, link) = current;
+        if link.is_some() {
+            if let Some((length, _)) = walk(pos, to) {
+                if best.map_or(true, |(best, _)| cost + length < best) {
+                    best = Some((cost + length, link));
+                }
+            }
+        }
+        for (id, start) in starts.iter().enumerate() {
+            let Some(start) = start.filter(|_| !done[id]) else {
+                continue;
+            };
+            let Some((length, _)) = walk(pos, start) else {
+                continue;
+            };
+            let cost = cost + length + links[id].start.distance(links[id].end);
+            if cost < costs[id] {
+                costs[id] = cost;
+                prev[id] = link;
+            }
+        }
+
+        let next = (0..links.len())
+            .filter(|id| !done[*id] && ends[*id].is_some() && costs[*id].is_finite())
+            .min_by(|a, b| costs[*a].total_cmp(&costs[*b]));
+        let Some(next) = next.filter(|id| best.map_or(true, |(best, _)| costs[*id] < best)) else {
+            break;
+        };
+        done[next] = true;
+        current = (ends[next].unwrap(), costs[next], Some(next));
+    }
+
+    let (_, mut link) = best?;
+    let mut taken = vec![];
+    while let Some(id) = link {
+        taken.push(id);
+        link = prev[id];
+    }
+    taken.reverse();
+
+    let mut legs = vec![];
+    let mut pos = from;
+    for id in taken {
+        let (_, path) = walk(pos, starts[id].unwrap())?;
+        if !path.is_empty() {
+            legs.push(PathLeg::Walk(path));
+        }
+        legs.push(PathLeg::Link(links[id]));
+        pos = ends[id].unwrap();
+    }
+    let (_, path) = walk(pos, to)?;
+    if !path.is_empty() {
+        legs.push(PathLeg::Walk(path));
+    }
+    Some(legs)
+}
+
+#[test]
+fn test_level_navmesh_slopes() {
+    let mut tilemap = Tilemap::new(UVec2::new(4, 1), 0).unwrap();
+    // A ramp of two elevation steps per tile, then a cliff of three steps
+    for (vid, elevation) in [0, 2, 4, 7, 7].into_iter().enumerate() {
+        tilemap.vert_data_mut()[vid].elevation = elevation;
+        tilemap.vert_data_mut()[vid + 5].elevation = elevation;
+    }
+    let (navmesh, elevation) = create_level_navmesh(&tilemap, &[], NavAgent::Minion).unwrap();
+    // Padded vertices keep the elevation of the grid vertex they were moved away from
+    for id in 0..navmesh.get().vertices.len() as u32 {
+        let vertex = elevation.vertex(id);
+        let steps = [0, 2, 4, 7, 7][(vertex.x + 2.0).round() as usize];
+        assert!((vertex.y - steps as f32 * SLOPE_HEIGHT).abs() < 1e-3);
+    }
+
+    let end = Vec3::new(-0.6, 2.8 * SLOPE_HEIGHT, 0.0);
+    let legs = path_with_links(&navmesh, &elevation, &[], Vec3::new(-1.5, 0.0, 0.0), end);
+    let Some([PathLeg::Walk(path)]) = legs.as_deref() else {
+        panic!("no single walk up the ramp: {legs:?}");
+    };
+    assert!(path.last().unwrap().abs_diff_eq(end, 1e-3));
+
+    assert!(tilemap.face_slope(2) > MAX_WALKABLE_SLOPE);
+    assert!(!navmesh.transformed_is_in_mesh(Vec3::new(0.5, 0.0, 0.0)));
+}
+
+#[test]
+fn test_level_navmesh_path_heights() {
+    let mut tilemap = Tilemap::new(UVec2::new(3, 3), 0).unwrap();
+    // Rising along X, with a wall in the middle to walk around
+    for vid in 0..tilemap.vert_data().len() {
+        tilemap.vert_data_mut()[vid].elevation = vid as u32 % 4;
+    }
+    tilemap.face_data_mut()[4].wall_height = 1;
+    let (navmesh, elevation) = create_level_navmesh(&tilemap, &[], NavAgent::Minion).unwrap();
+
+    let end = Vec3::new(1.0, 2.5 * SLOPE_HEIGHT, 1.0);
+    let legs = path_with_links(&navmesh, &elevation, &[], Vec3::new(-1.0, 0.0, -1.0), end);
+    let Some([PathLeg::Walk(path)]) = legs.as_deref() else {
+        panic!("no single walk across the level: {legs:?}");
+    };
+    let (end_point, corners) = path.split_last().unwrap();
+    assert!(end_point.abs_diff_eq(end, 1e-3));
+    assert!(!corners.is_empty());
+    for corner in corners {
+        let step = (corner.x + 1.5).round();
+        assert!((corner.y - step * SLOPE_HEIGHT).abs() < 1e-3, "{corner}");
+    }
+}
+
+#[test]
+fn test_level_navmesh_obstacles() {
+    let tilemap = Tilemap::new(UVec2::new(6, 6), 0).unwrap();
+
+    let center = ObjectObstacle::from_footprint(&tilemap, Vec3::new(0.5, 0.0, 0.5), Vec2::ONE);
+    assert_eq!(
+        center,
+        Some(ObjectObstacle {
+            coord: UVec2::new(3, 3),
+            dims: UVec2::ONE
+        })
+    );
+    let corner = ObjectObstacle::from_footprint(&tilemap, Vec3::ZERO, Vec2::splat(1.4));
+    assert_eq!(
+        corner,
+        Some(ObjectObstacle {
+            coord: UVec2::new(2, 2),
+            dims: UVec2::splat(2)
+        })
+    );
+    let outside = ObjectObstacle::from_footprint(&tilemap, Vec3::new(9.0, 0.0, 0.0), Vec2::ONE);
+    assert_eq!(outside, None);
+
+    let (navmesh, _) =
+        create_level_navmesh(&tilemap, &[corner.unwrap()], NavAgent::Minion).unwrap();
+    assert!(!navmesh.transformed_is_in_mesh(Vec3::ZERO));
+    let closest = closest_point_in_mesh(&navmesh, Vec3::ZERO, 2.0).unwrap();
+    assert!(navmesh.transformed_is_in_mesh(closest));
+    assert!(navmesh
+        .transformed_path(Vec3::new(-2.5, 0.0, -2.5), closest)
+        .is_some());
+}
+
+#[test]
+fn test_navmesh_layout_obstacles() {
+    let tilemap = Tilemap::new(UVec2::new(12, 12), 0).unwrap();
+    let mut layout = NavMeshLayout::new(&tilemap, NavAgent::Minion);
+    let before = layout.rects.clone();
+
+    let obstacle = ObjectObstacle {
+        coord: UVec2::new(1, 1),
+        dims: UVec2::splat(2),
+    };
+    assert!(layout.set_obstacles(&[obstacle]));
+    assert!(!layout.set_obstacles(&[obstacle]));
+    // Rectangles away from the obstacle are kept as they were
+    let near =
+        |(min, max): &FaceRect| min.cmple(UVec2::splat(4)).all() && max.cmpgt(UVec2::ZERO).all();
+    for rect in before.iter().filter(|rect| !near(rect)) {
+        assert!(layout.rects.contains(rect), "{rect:?}");
+    }
+
+    let (navmesh, _) = layout.build().unwrap();
+    let (rebuilt, _) = create_level_navmesh(&tilemap, &[obstacle], NavAgent::Minion).unwrap();
+    for (y, x) in (0..12).cartesian_product(0..12) {
+        let center = Vec3::new(x as f32 - 5.5, 0.0, y as f32 - 5.5);
+        assert_eq!(
+            navmesh.transformed_is_in_mesh(center),
+            rebuilt.transformed_is_in_mesh(center),
+            "{center}"
+        );
+    }
+    assert!(!navmesh.transformed_is_in_mesh(Vec3::new(-3.5, 0.0, -3.5)));
+
+    assert!(layout.set_obstacles(&[]));
+    let (navmesh, _) = layout.build().unwrap();
+    assert!(navmesh.transformed_is_in_mesh(Vec3::new(-3.5, 0.0, -3.5)));
+}
+
+#[test]
+fn test_level_navmesh_agents() {
+    let mut tilemap = Tilemap::new(UVec2::new(9, 9), 0).unwrap();
+    // A wall across the level with a gap of a single face
+    for y in (0..9).filter(|y| *y != 2) {
+        tilemap.face_data_mut()[y * 9 + 4].wall_height = 1;
+    }
+    let (left, right) = (Vec3::new(-3.0, 0.0, 0.0), Vec3::new(3.0, 0.0, 0.0));
+
+    let (small, _) = create_level_navmesh(&tilemap, &[], NavAgent::Minion).unwrap();
+    let (large, _) = create_level_navmesh(&tilemap, &[], NavAgent::Large).unwrap();
+    assert!(small.transformed_path(left, right).is_some());
+    assert!(large.transformed_is_in_mesh(left));
+    assert!(large.transformed_path(left, right).is_none());
+
+    // Wide enough for large agents
+    for y in 4..=6 {
+        tilemap.face_data_mut()[y * 9 + 4].wall_height = 0;
+    }
+    let (large, _) = create_level_navmesh(&tilemap, &[], NavAgent::Large).unwrap();
+    assert!(large.transformed_path(left, right).is_some());
+    assert!(!large.transformed_is_in_mesh(Vec3::new(0.0, 0.0, -2.0)));
+}
+
+#[test]
+fn test_path_with_links() {
+    let mut tilemap = Tilemap::new(UVec2::new(9, 5), 0).unwrap();
+    for y in 0..5 {
+        tilemap.face_data_mut()[y * 9 + 4].wall_height = 1;
+    }
+    let (navmesh, elevation) = create_level_navmesh(&tilemap, &[], NavAgent::Minion).unwrap();
+    let (left, right) = (Vec3::new(-3.0, 0.0, 0.0), Vec3::new(3.0, 0.0, 0.0));
+    assert_eq!(
+        path_with_links(&navmesh, &elevation, &[], left, right),
+        None
+    );
+
+    let link = NavLink {
+        start: Vec3::new(-1.0, 0.0, 1.0),
+        end: Vec3::new(1.0, 0.0, 1.0),
+        two_way: false,
+    };
+    let legs = path_with_links(&navmesh, &elevation, &[link], left, right).unwrap();
+    assert!(matches!(
+        legs.as_slice(),
+        [PathLeg::Walk(_), PathLeg::Link(l), PathLeg::Walk(_)] if *l == link
+    ));
+    assert_eq!(
+        path_with_links(&navmesh, &elevation, &[link], right, left),
+        None
+    );
+
+    let link = NavLink {
+        two_way: true,
+        ..link
+    };
+    let legs = path_with_links(&navmesh, &elevation, &[link], right, left).unwrap();
+    assert_eq!(legs[1], PathLeg::Link(link.reversed()));
+
+    // Links aren't taken when walking is shorter
+    let legs = path_with_links(
+        &navmesh,
+        &elevation,
+        &[link],
+        left,
+        Vec3::new(-1.0, 0.0, -1.0),
+    )
+    .unwrap();
+    assert!(matches!(legs.as_slice(), [PathLeg::Walk(_)]));
+}
+
+#[cfg(test)]
+fn random_grid(dims: UVec2, seed: u64) -> (Vec<bool>, Vec<Option<IVec3>>) {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut walls = vec![false; dims.element_product() as usize];
+    for _ in 0..dims.element_product() / 80 {
+        let size = UVec2::new(rng.gen_range(1..5), rng.gen_range(1..5));
+        let coord = UVec2::new(rng.gen_range(0..dims.x), rng.gen_range(0..dims.y));
+        for y in coord.y..(coord.y + size.y).min(dims.y) {
+            for x in coord.x..(coord.x + size.x).min(dims.x) {
+                walls[(y * dims.x + x) as usize] = true;
+            }
+        }
+    }
+    // A few slopes between the flat areas, and some bent faces
+    let planes = (0..dims.element_product())
+        .map(|id| match (id % dims.x) / 8 % 3 {
+            0 => Some(IVec3::ZERO),
+            1 => rng.gen_bool(0.9).then_some(IVec3::new(1, 0, 0)),
+            _ => Some(IVec3::new(0, 0, 3)),
+        })
+        .collect_vec();
+    (walls, planes)
+}
+
+#[test]
+fn test_merged_grid_mesh() {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    let dims = UVec2::new(40, 32);
+    let (walls, planes) = random_grid(dims, 7);
+    let objects = [ObjectObstacle {
+        coord: UVec2::new(20, 10),
+        dims: UVec2::new(3, 2),
+    }];
+
+    let padding = NavAgent::Minion.radius();
+    let (vertices, polygons) = create_grid_mesh_with_holes(dims, &walls, &objects, padding);
+    let tiles = polyanya::Mesh::new(vertices, polygons).unwrap();
+    let (vertices, polygons) =
+        create_merged_grid_mesh_with_holes(dims, &walls, &objects, &planes, padding);
+    let merged = polyanya::Mesh::new(vertices, polygons).unwrap();
+    assert!(merged.polygons.len() * 2 < tiles.polygons.len());
+
+    let mut rng = StdRng::seed_from_u64(3);
+    let offset = dims.as_vec2() * -0.5;
+    let mut found = 0;
+    for _ in 0..300 {
+        let mut point = || {
+            offset
+                + Vec2::new(
+                    rng.gen_range(0.0..dims.x as f32),
+                    rng.gen_range(0.0..dims.y as f32),
+                )
+        };
+        let (from, to) = (point(), point());
+        assert_eq!(tiles.point_in_mesh(from), merged.point_in_mesh(from));
+        match (tiles.path(from, to), merged.path(from, to)) {
+            (Some(a), Some(b)) => {
+                assert!((a.length - b.length).abs() < 1e-3, "{from} -> {to}");
+                found += 1;
+            }
+            (None, None) => {}
+            (a, b) => panic!("{from} -> {to}: {a:?} vs {b:?}"),
+        }
+    }
+    assert!(found > 100);
+}
+
+/// Run with `cargo test --release bench_merged_grid_mesh -- --ignored --nocapture`
+#[test]
+#[ignore]
+fn bench_merged_grid_mesh() {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use std::time::Instant;
+
+    let dims = UVec2::new(128, 128);
+    let (walls, planes) = random_grid(dims, 1);
+    let offset = dims.as_vec2() * -0.5;
+    let mut rng = StdRng::seed_from_u64(2);
+    let queries = (0..1000)
+        .map(|_| {
+            let mut point = || {
+                let coord = UVec2::new(rng.gen_range(0..dims.x), rng.gen_range(0..dims.y));
+                offset + coord.as_vec2() + 0.5
+            };
+            (point(), point())
+        })
+        .collect_vec();
+
+    let padding = NavAgent::Minion.radius();
+    let (vertices, polygons) = create_grid_mesh_with_holes(dims, &walls, &[], padding);
+    let tiles = polyanya::Mesh::new(vertices, polygons).unwrap();
+    let (vertices, polygons) =
+        create_merged_grid_mesh_with_holes(dims, &walls, &[], &planes, padding);
+    let merged = polyanya::Mesh::new(vertices, polygons).unwrap();
+
+    for (name, mesh) in [("tiles", &tiles), ("merged", &merged)] {
+        let start = Instant::now();
+        let found = queries
+            .iter()
+            .filter(|(from, to)| mesh.path(*from, *to).is_some())
+            .count();
+        println!(
+            "{name}: {} polygons, {found} paths in {:?}",
+            mesh.polygons.len(),
+            start.elapsed()
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use crate::framework::grid::Anchor2;
 use crate::framework::grid::Grid;
+use crate::framework::grid::ResizeIter;
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -11,12 +12,15 @@ pub const SLOPE_HEIGHT: f32 = 0.334;
 pub const WALL_HEIGHT: f32 = 1.0;
 pub const MAX_NUM_WALLS: usize = 6;
 
-#[derive(Default, Clone, Reflect, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, PartialEq, Reflect, Serialize, Deserialize)]
 pub struct FaceData {
     pub wall_height: u32,
     pub wall_top_tile_id: u32,
     pub wall_side_tile_ids: [u32; 4 * MAX_NUM_WALLS],
     pub tile_id: u32,
+    /// Defaulted so editor scenes from before tile flags still load
+    #[serde(default)]
+    pub flags: TileFlags,
 }
 impl FaceData {
     pub const DEFAULT: FaceData = FaceData {
@@ -24,10 +28,67 @@ impl FaceData {
         tile_id: 0,
         wall_top_tile_id: 0,
         wall_side_tile_ids: [0; 4 * MAX_NUM_WALLS],
+        flags: TileFlags::NONE,
     };
 }
 
-#[derive(Default, Reflect, Debug, Clone, Serialize, Deserialize)]
+/// Gameplay properties of a face, combined as bits
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub struct TileFlags(u8);
+
+impl TileFlags {
+    pub const NONE: Self = Self(0);
+    /// Kills minions that walk onto it
+    pub const HAZARD: Self = Self(1 << 0);
+    /// Characters move at [`SLOW_TILE_SPEED_FACTOR`] of their speed
+    pub const SLOW: Self = Self(1 << 1);
+    /// Minions can't be thrown onto it, or from it
+    pub const NO_THROW: Self = Self(1 << 2);
+    /// Only minions can pass
+    pub const MINION_ONLY: Self = Self(1 << 3);
+    /// Only the player can pass
+    pub const PLAYER_ONLY: Self = Self(1 << 4);
+
+    pub const ALL: [Self; 5] = [
+        Self::HAZARD,
+        Self::SLOW,
+        Self::NO_THROW,
+        Self::MINION_ONLY,
+        Self::PLAYER_ONLY,
+    ];
+
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+    pub const fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+    pub fn set(&mut self, other: Self, value: bool) {
+        match value {
+            true => self.0 |= other.0,
+            false => self.0 &= !other.0,
+        }
+    }
+
+    /// Name of a single flag
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::HAZARD => "Hazard",
+            Self::SLOW => "Slow",
+            Self::NO_THROW => "No Throw",
+            Self::MINION_ONLY => "Minion Only",
+            Self::PLAYER_ONLY => "Player Only",
+            _ => "Mixed",
+        }
+    }
+}
+
+pub const SLOW_TILE_SPEED_FACTOR: f32 = 0.5;
+
+#[derive(Default, Reflect, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct VertData {
     pub elevation: u32,
 }
@@ -36,7 +97,7 @@ impl VertData {
     pub const DEFAULT: VertData = VertData { elevation: 0 };
 }
 
-#[derive(Asset, Clone, Reflect, Serialize, Deserialize)]
+#[derive(Asset, Debug, Clone, Reflect, Serialize, Deserialize)]
 pub struct Tilemap {
     face_grid: Grid,
     vert_grid: Grid,
@@ -126,6 +187,18 @@ impl Tilemap {
         let y = (offset.y + z / TILE_SIZE_Y) as u32;
         (x < dims.x && y < dims.y).then_some((y * dims.x) + x)
     }
+    /// Flags of the face under a world position, none outside of the map
+    pub fn flags_at(&self, pos: Vec3) -> TileFlags {
+        self.pos_to_face_id(pos.x, pos.z)
+            .map(|fid| self.face_data[fid as usize].flags)
+            .unwrap_or_default()
+    }
+    /// Minions can't be thrown from or onto no-throw faces
+    pub fn allows_throw(&self, from: Vec3, to: Vec3) -> bool {
+        [from, to]
+            .into_iter()
+            .all(|pos| !self.flags_at(pos).contains(TileFlags::NO_THROW))
+    }
     pub fn face_id_to_center_pos_2d(&self, fid: u32) -> Option<Vec2> {
         let dims = self.face_grid.dims();
         let coord = self.face_grid.id_to_coord(fid);
@@ -163,6 +236,12 @@ impl Tilemap {
             .fold(u32::MAX, |acc, x| acc.min(x))
     }
 
+    /// Height walls on this face start at, the base elevation snapped down to whole walls
+    pub fn face_wall_base_height(&self, fid: u32) -> f32 {
+        let y_inc = ((self.face_base_elevation(fid) as f32 * SLOPE_HEIGHT) / WALL_HEIGHT) as u32;
+        y_inc as f32 * WALL_HEIGHT
+    }
+
     pub fn face_center_height(&self, fid: u32) -> f32 {
         (self
             .face_id_to_vert_ids(fid)
@@ -173,6 +252,47 @@ impl Tilemap {
             * SLOPE_HEIGHT
     }
 
+    /// Heights of the corners of a face, in the order of `face_id_to_vert_ids`
+    pub fn face_vert_heights(&self, fid: u32) -> [f32; 4] {
+        self.face_id_to_vert_ids(fid)
+            .map(|vid| self.vert_data[vid as usize].elevation as f32 * SLOPE_HEIGHT)
+    }
+
+    /// Angle in radians between the horizontal and the steepest of the two triangles a
+    /// face is built from
+    pub fn face_slope(&self, fid: u32) -> f32 {
+        let [h00, h10, h01, h11] = self.face_vert_heights(fid);
+        let p00 = Vec3::new(0.0, h00, 0.0);
+        let p10 = Vec3::new(TILE_SIZE_X, h10, 0.0);
+        let p01 = Vec3::new(0.0, h01, TILE_SIZE_Y);
+        let p11 = Vec3::new(TILE_SIZE_X, h11, TILE_SIZE_Y);
+        let slope = |a: Vec3, b: Vec3, c: Vec3| {
+            let normal = (b - a).cross(c - a);
+            (normal.y.abs() / normal.length()).acos()
+        };
+        slope(p00, p01, p11).max(slope(p11, p10, p00))
+    }
+
+    /// Height of the ground at a world position, following the triangles of the ground mesh
+    pub fn ground_height(&self, x: f32, z: f32) -> Option<f32> {
+        let fid = self.pos_to_face_id(x, z)?;
+        let [h00, h10, h01, h11] = self.face_vert_heights(fid);
+        let coord = self.size() * 0.5 + Vec2::new(x / TILE_SIZE_X, z / TILE_SIZE_Y);
+        let t = (coord - self.face_grid.id_to_coord(fid).as_vec2()).clamp(Vec2::ZERO, Vec2::ONE);
+        Some(match t.y >= t.x {
+            true => h00 + t.x * (h11 - h01) + t.y * (h01 - h00),
+            false => h00 + t.x * (h10 - h00) + t.y * (h11 - h10),
+        })
+    }
+
+    /// Moves a position onto the ground, leaving it as is outside of the map
+    pub fn snap_to_ground(&self, pos: Vec3) -> Vec3 {
+        match self.ground_height(pos.x, pos.z) {
+            Some(y) => pos.with_y(y),
+            None => pos,
+        }
+    }
+
     pub fn vert_neighbor_elevations(&mut self, vid: u32) -> impl Iterator<Item = u32> + '_ {
         self.vert_grid
             .neighbor_coords_8(self.vert_grid.id_to_coord(vid))
@@ -216,6 +336,42 @@ impl Tilemap {
         self.face_data = face_data;
         self.vert_data = vert_data;
     }
+
+    /// Copies the `dims` faces starting at `start`, along with their vertices
+    pub fn region(&self, start: UVec2, dims: UVec2) -> Option<Tilemap> {
+        if dims.min_element() == 0 || !self.face_grid.is_coord_in_grid(start + dims - UVec2::ONE) {
+            return None;
+        }
+        let mut region = Tilemap::new(dims, 0)?;
+        let start = start.as_ivec2();
+        for (dst, src) in ResizeIter::new(self.face_grid.dims(), dims, start).enumerate() {
+            if let Some(src) = src {
+                region.face_data[dst] = self.face_data[src as usize].clone();
+            }
+        }
+        let vert_dims = dims + UVec2::ONE;
+        for (dst, src) in ResizeIter::new(self.vert_grid.dims(), vert_dims, start).enumerate() {
+            if let Some(src) = src {
+                region.vert_data[dst] = self.vert_data[src as usize].clone();
+            }
+        }
+        Some(region)
+    }
+
+    /// Overwrites the area at `start` with `region`, whatever sticks out of the map is dropped
+    pub fn paste_region(&mut self, region: &Tilemap, start: IVec2) {
+        for (src, dst) in ResizeIter::new(self.face_grid.dims(), region.dims(), start).enumerate() {
+            if let Some(dst) = dst {
+                self.face_data[dst as usize] = region.face_data[src].clone();
+            }
+        }
+        let vert_dims = region.vert_grid.dims();
+        for (src, dst) in ResizeIter::new(self.vert_grid.dims(), vert_dims, start).enumerate() {
+            if let Some(dst) = dst {
+                self.vert_data[dst as usize] = region.vert_data[src].clone();
+            }
+        }
+    }
 }
 
 pub struct VertPosIter<'a> {
@@ -1,6 +1,7 @@
 use crate::framework::{
     audio::{Audio, AudioAsset, AudioChannel, AudioChannels, LoopConfig, Volume},
     easing::Easing,
+    level_asset::metadata::LevelMetadata,
 };
 use bevy::{asset::LoadState, prelude::*, time::Real};
 use std::time::Duration;
@@ -88,18 +89,27 @@ pub fn fade_in_channels(mut channels: ResMut<AudioChannels>) {
 }
 
 pub fn start_bgm_delayed(
-    bgm: Res<AudioAssets>,
+    ass: Res<AssetServer>,
+    metadata: Option<Res<LevelMetadata>>,
     mut audio: ResMut<Audio>,
     time: Res<Time<Real>>,
     mut timer: Local<Timer>,
+    mut bgm: Local<Option<Handle<AudioAsset>>>,
 ) {
     if timer.duration() == Duration::ZERO {
-        *timer = Timer::new(Duration::from_secs_f32(20.0), TimerMode::Once)
+        *timer = Timer::new(Duration::from_secs_f32(20.0), TimerMode::Once);
+        // load the level track early so it's ready by the time the timer runs out
+        *bgm = metadata
+            .filter(|m| !m.bgm_path.is_empty())
+            .map(|m| ass.load(m.bgm_path.clone()));
     }
     timer.tick(time.delta());
     if timer.just_finished() {
+        let Some(bgm) = bgm.clone() else {
+            return;
+        };
         audio.play_loop(
-            bgm.theme.clone(),
+            bgm,
             AudioChannel::BGM,
             LoopConfig {
                 looping: true,
@@ -2,7 +2,16 @@
 //! provided by `bevy_rapier3d`.
 //! The API supports jumping.
 
-use crate::game::collision_groups::{ACTOR_GROUP, GROUND_GROUP, WALL_GROUP};
+use crate::{
+    framework::{
+        navmesh::MAX_WALKABLE_SLOPE,
+        tilemap::{TileFlags, Tilemap, SLOW_TILE_SPEED_FACTOR},
+    },
+    game::{
+        collision_groups::{ACTOR_GROUP, GROUND_GROUP, WALL_GROUP},
+        LevelResources,
+    },
+};
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
 
@@ -10,9 +19,11 @@ pub const GROUND_TIMER: f32 = 0.5;
 pub const MOVEMENT_SPEED: f32 = 4.0;
 // pub const JUMP_SPEED: f32 = 20.0;
 pub const GRAVITY: f32 = -9.81;
+/// How far ahead of the character blocked faces stop it
+pub const TILE_BLOCKING_DISTANCE: f32 = 0.3;
 
 /// Controls how the character shall move.
-#[derive(Clone, Copy, Component, Reflect, Default, Debug)]
+#[derive(Clone, Copy, Component, Reflect, Debug)]
 #[reflect(Component)]
 pub struct CharacterWalkControl {
     /// Move direction. Doesn't need to be normalised.
@@ -22,6 +33,18 @@ pub struct CharacterWalkControl {
     /// Makes the character move in the specified direction.
     /// Resets on next frame.
     pub do_move: bool,
+    /// Multiplies [`MOVEMENT_SPEED`], e.g. for agile minions
+    pub speed_factor: f32,
+}
+
+impl Default for CharacterWalkControl {
+    fn default() -> Self {
+        Self {
+            direction: Vec3::ZERO,
+            do_move: false,
+            speed_factor: 1.0,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Component, Reflect, Default, Debug)]
@@ -31,11 +54,17 @@ pub struct CharacterWalkState {
     pub vertical_movement: f32,
 }
 
+/// Tile flags of faces the character can't walk onto
+#[derive(Clone, Copy, Component, Reflect, Default, Debug)]
+#[reflect(Component)]
+pub struct BlockingTileFlags(pub TileFlags);
+
 #[derive(Clone, Bundle, Debug)]
 pub struct KinematicCharacterBundle {
     pub controller: KinematicCharacterController,
     pub control: CharacterWalkControl,
     pub state: CharacterWalkState,
+    pub blocking: BlockingTileFlags,
     pub rigid_body: RigidBody,
 }
 
@@ -53,7 +82,7 @@ impl Default for KinematicCharacterBundle {
                     include_dynamic_bodies: false,
                 }),
                 // Don’t allow climbing slopes larger than 45 degrees.
-                max_slope_climb_angle: 45.0_f32.to_radians(),
+                max_slope_climb_angle: MAX_WALKABLE_SLOPE,
                 // Automatically slide down on slopes smaller than 30 degrees.
                 min_slope_slide_angle: 30.0_f32.to_radians(),
                 apply_impulse_to_dynamic_bodies: true,
@@ -64,6 +93,7 @@ impl Default for KinematicCharacterBundle {
             rigid_body: RigidBody::KinematicPositionBased,
             control: CharacterWalkControl::default(),
             state: CharacterWalkState::default(),
+            blocking: BlockingTileFlags::default(),
         }
     }
 }
@@ -84,7 +114,8 @@ pub fn update_kinematic_character(
         let mut movement = Vec3::ZERO;
         if walk.do_move {
             movement = Vec3::new(walk.direction.x, 0.0, walk.direction.z).normalize_or_zero()
-                * MOVEMENT_SPEED;
+                * MOVEMENT_SPEED
+                * walk.speed_factor;
         }
         walk.do_move = false;
         // no jumping
@@ -108,3 +139,103 @@ pub fn update_kinematic_character(
         controller.translation = Some(movement * delta_time);
     }
 }
+
+/// Slows characters down on slow faces and keeps them off the faces they can't enter
+pub fn apply_tile_flags(
+    level: Res<LevelResources>,
+    mut characters: Query<(
+        &mut KinematicCharacterController,
+        &GlobalTransform,
+        &BlockingTileFlags,
+    )>,
+) {
+    let Some(tilemap) = &level.tilemap else {
+        return;
+    };
+    for (mut controller, gx, blocking) in characters.iter_mut() {
+        let Some(translation) = controller.translation else {
+            continue;
+        };
+        let movement = tile_movement(tilemap, gx.translation(), translation, blocking.0);
+        controller.translation = Some(movement);
+    }
+}
+
+/// Movement of a character at `position` after the flags of the faces it walks on
+fn tile_movement(tilemap: &Tilemap, position: Vec3, translation: Vec3, blocked: TileFlags) -> Vec3 {
+    let mut movement = translation.with_y(0.0);
+    if tilemap.flags_at(position).contains(TileFlags::SLOW) {
+        movement *= SLOW_TILE_SPEED_FACTOR;
+    }
+    restrict_movement(tilemap, position, movement, blocked).with_y(translation.y)
+}
+
+/// Drops the parts of a horizontal movement that walk onto blocked faces, so characters
+/// slide along their edges. Characters that already stand on one can walk off it.
+fn restrict_movement(
+    tilemap: &Tilemap,
+    position: Vec3,
+    movement: Vec3,
+    blocked: TileFlags,
+) -> Vec3 {
+    if blocked == TileFlags::NONE || tilemap.flags_at(position).intersects(blocked) {
+        return movement;
+    }
+    let mut movement = movement;
+    for axis in [Vec3::X, Vec3::Z] {
+        let step = movement * axis;
+        let ahead = position + step.normalize_or_zero() * TILE_BLOCKING_DISTANCE;
+        if step != Vec3::ZERO && tilemap.flags_at(ahead).intersects(blocked) {
+            movement -= step;
+        }
+    }
+    movement
+}
+
+#[test]
+fn test_tile_movement() {
+    let mut tilemap = Tilemap::new(UVec2::new(5, 5), 0).unwrap();
+    // Face centers are at x - 2, z - 2
+    for y in 0..5 {
+        tilemap.face_data_mut()[y * 5 + 3].flags = TileFlags::PLAYER_ONLY;
+    }
+    tilemap.face_data_mut()[2 * 5 + 1].flags = TileFlags::SLOW;
+    tilemap.face_data_mut()[2].flags = TileFlags::NO_THROW;
+    let blocked = TileFlags::PLAYER_ONLY;
+
+    // Slides along the edge of a blocked face
+    let near = Vec3::new(0.3, 0.0, 0.0);
+    let diagonal = Vec3::new(0.5, 0.0, 0.5);
+    assert_eq!(
+        restrict_movement(&tilemap, near, diagonal, blocked),
+        Vec3::new(0.0, 0.0, 0.5)
+    );
+    assert_eq!(
+        restrict_movement(&tilemap, near, diagonal, TileFlags::NONE),
+        diagonal
+    );
+    let away = Vec3::new(-0.5, 0.0, 0.5);
+    assert_eq!(restrict_movement(&tilemap, near, away, blocked), away);
+    // Walks off a blocked face it already stands on
+    let on = Vec3::new(1.0, 0.0, 0.0);
+    assert_eq!(restrict_movement(&tilemap, on, diagonal, blocked), diagonal);
+
+    // Slowed down, but falls as fast as ever
+    let slow = Vec3::new(-1.0, 0.0, 0.0);
+    let translation = Vec3::new(0.4, -2.0, 0.0);
+    assert_eq!(
+        tile_movement(&tilemap, slow, translation, blocked),
+        Vec3::new(0.4 * SLOW_TILE_SPEED_FACTOR, -2.0, 0.0)
+    );
+    assert_eq!(
+        tile_movement(&tilemap, Vec3::ZERO, translation, blocked),
+        translation
+    );
+
+    // No throwing onto or from no-throw faces
+    let no_throw = Vec3::new(0.0, 0.0, -2.0);
+    assert!(tilemap.allows_throw(Vec3::ZERO, Vec3::new(1.0, 0.0, 1.0)));
+    assert!(!tilemap.allows_throw(Vec3::ZERO, no_throw));
+    assert!(!tilemap.allows_throw(no_throw, Vec3::ZERO));
+    assert_eq!(tilemap.flags_at(Vec3::new(9.0, 0.0, 0.0)), TileFlags::NONE);
+}
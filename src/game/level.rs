@@ -2,15 +2,20 @@ use crate::{
     framework::{
         level_asset::LevelAsset,
         loading_queue::{AssetLoadingCompleted, WatchAssetLoading},
-        navmesh,
+        navmesh::{NavAgent, NavMeshLayout, ObjectObstacle},
     },
     game::{
         collision_groups::{ACTOR_GROUP, GROUND_GROUP, TARGET_GROUP, WALL_GROUP},
-        objects::{self, assets::GameObjectAssets, definitions::ObjectDefKind},
+        minion::health::MinionLossStats,
+        objects::{
+            self,
+            assets::GameObjectAssets,
+            definitions::{ObjectDef, ObjectDefKind},
+        },
         LevelResources,
     },
 };
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
 use bevy_rapier3d::prelude::*;
 use vleue_navigator::NavMesh;
 
@@ -20,6 +25,28 @@ pub struct GroundTag;
 #[derive(Event)]
 pub struct LevelInitialized;
 
+/// Cuts the faces under an object out of the level navmesh while `blocking`
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+pub struct NavObstacle {
+    /// Footprint on the ground, along the world X and Z axes
+    pub size: Vec2,
+    /// Cleared to let minions walk through, e.g. for an opened door
+    pub blocking: bool,
+}
+
+impl NavObstacle {
+    pub fn new(size: Vec2) -> Self {
+        Self {
+            size,
+            blocking: true,
+        }
+    }
+}
+
+/// Sent after the level navmeshes were rebuilt around changed obstacles
+#[derive(Event)]
+pub struct NavMeshChanged;
+
 pub fn init_level(
     mut cmd: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
@@ -71,50 +98,21 @@ pub fn init_level(
         );
     }
 
-    // create navmesh
-    let dims = level.data().tilemap.dims();
-
-    let walls = level
-        .data()
-        .tilemap
-        .faces()
-        .map(|face| face.wall_height > 0)
-        .collect::<Vec<_>>();
-
-    let objects = vec![];
-
-    let handle = navs.reserve_handle();
-
-    let (vertices, polygons) = navmesh::create_grid_mesh_with_holes(dims, &walls, &objects, 0.4);
-
-    let mut navmesh = NavMesh::from_polyanya_mesh(polyanya::Mesh::new(vertices, polygons).unwrap());
-
-    let transform = Transform::from_rotation(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2));
-
-    navmesh.set_transform(transform);
-
-    // use rand::Rng;
-    // let dims_f32 = dims.as_vec2();
-    // let offset_2 = dims_f32 * -0.5;
-    // let offset = Vec3::new(offset_2.x, 0.0, offset_2.y);
-    // let mut rng = rand::thread_rng();
-    // for y in 0..dims.y {
-    //     for x in 0..dims.x {
-    //         let pos = Vec3::new(x as f32, rng.gen_range(0.0..10.0), y as f32)
-    //             + offset
-    //             + Vec3::splat(0.5);
-    //         let point = navmesh.transform().transform_point(pos).xy();
-    //         print!(
-    //             "{:02.02},{:02.02} => {}",
-    //             point.x,
-    //             point.y,
-    //             if navmesh.is_in_mesh(point) { "X" } else { "o" }
-    //         );
-    //     }
-    //     println!();
-    // }
-
-    navs.insert(handle.id(), navmesh);
+    // create navmeshes
+    let mut navmeshes = HashMap::new();
+    let mut nav_elevations = HashMap::new();
+    let mut nav_layouts = HashMap::new();
+    for agent in NavAgent::VARIANTS {
+        let layout = NavMeshLayout::new(&level.data().tilemap, agent);
+        match layout.build() {
+            Ok((navmesh, elevation)) => {
+                navmeshes.insert(agent, navs.add(navmesh));
+                nav_elevations.insert(agent, elevation);
+                nav_layouts.insert(agent, layout);
+            }
+            Err(e) => error!("Failed to create the {agent:?} level navmesh: {e:?}"),
+        }
+    }
 
     let spawnpoints = level
         .data()
@@ -125,7 +123,16 @@ pub fn init_level(
         .collect::<Vec<_>>();
 
     cmd.insert_resource(LevelResources {
-        navmesh: Some(handle),
+        navmeshes,
+        nav_elevations,
+        nav_layouts,
+        nav_links: level
+            .data()
+            .objects
+            .iter()
+            .filter_map(ObjectDef::nav_link)
+            .collect(),
+        tilemap: Some(level.data().tilemap.clone()),
         spawnpoints: Some(spawnpoints),
     });
 
@@ -133,28 +140,93 @@ pub fn init_level(
         let _ent = objects::spawn_object(&mut cmd, object, assets.as_ref());
     }
 
-    cmd.insert_resource(AmbientLight {
-        color: Color::WHITE,
-        // brightness: 250.,
-        brightness: 50.,
-    });
-    cmd.spawn(DirectionalLightBundle {
-        directional_light: DirectionalLight {
-            // illuminance: 2_500.0,
-            illuminance: 500.0,
-            shadows_enabled: true,
-
-            ..Default::default()
-        },
-        transform: Transform::IDENTITY
-            .with_translation(Vec3::new(5.0, 10.0, -5.0))
-            .looking_at(Vec3::ZERO, Vec3::Y),
-        ..Default::default()
-    });
+    let metadata = &level.data().metadata;
+    cmd.insert_resource(metadata.ambient_light.to_ambient_light());
+    cmd.spawn(metadata.directional_light.to_bundle());
+    cmd.insert_resource(metadata.clone());
+    cmd.insert_resource(MinionLossStats::default());
 
     initialized.send(LevelInitialized);
 }
 
+pub fn spawn_ornamental_meshes(
+    mut cmd: Commands,
+    ass: Res<AssetServer>,
+    mut load: EventReader<AssetLoadingCompleted<LevelAsset>>,
+    level: Res<Assets<LevelAsset>>,
+) {
+    let Some(load) = load.read().last() else {
+        return;
+    };
+    let Some(level) = level.get(&load.handle) else {
+        return;
+    };
+
+    for mesh in &level.data().meshes {
+        let mut ent = cmd.spawn(SceneBundle {
+            scene: ass.load(mesh.asset_path.clone()),
+            transform: mesh.transform(),
+            ..default()
+        });
+        if mesh.collider {
+            ent.insert(AsyncSceneCollider::default());
+        }
+    }
+}
+
+/// Updates the level navmeshes in place when obstacles spawn, despawn or stop blocking.
+/// Only the faces around those that changed are merged again, nothing is rebuilt while
+/// the covered faces stay the same.
+pub fn update_navmesh_obstacles(
+    mut level: ResMut<LevelResources>,
+    mut navs: ResMut<Assets<NavMesh>>,
+    changed: Query<(), Changed<NavObstacle>>,
+    mut removed: RemovedComponents<NavObstacle>,
+    obstacles: Query<(&GlobalTransform, &NavObstacle)>,
+    mut rebuilt: EventWriter<NavMeshChanged>,
+) {
+    let any_removed = removed.read().count() > 0;
+    if changed.is_empty() && !any_removed && !level.is_changed() {
+        return;
+    }
+    let Some(tilemap) = &level.tilemap else {
+        return;
+    };
+
+    let footprints = obstacles
+        .iter()
+        .filter(|(_, obstacle)| obstacle.blocking)
+        .filter_map(|(gx, obstacle)| {
+            ObjectObstacle::from_footprint(tilemap, gx.translation(), obstacle.size)
+        })
+        .collect::<Vec<_>>();
+
+    // Not a change of the level, that would update the navmeshes again
+    let level = level.bypass_change_detection();
+    let mut any_rebuilt = false;
+    for (agent, layout) in level.nav_layouts.iter_mut() {
+        if !layout.set_obstacles(&footprints) {
+            continue;
+        }
+        let (navmesh, elevation) = match layout.build() {
+            Ok(built) => built,
+            Err(e) => {
+                error!("Failed to rebuild the {agent:?} level navmesh: {e:?}");
+                continue;
+            }
+        };
+        let Some(asset) = level.navmeshes.get(agent).and_then(|h| navs.get_mut(h)) else {
+            continue;
+        };
+        *asset = navmesh;
+        level.nav_elevations.insert(*agent, elevation);
+        any_rebuilt = true;
+    }
+    if any_rebuilt {
+        rebuilt.send(NavMeshChanged);
+    }
+}
+
 #[derive(Resource)]
 pub struct UserDefinedStartupLevel(pub String);
 
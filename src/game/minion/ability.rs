@@ -0,0 +1,115 @@
+//! What minions can do besides walking, given by the colors they are made of
+
+use crate::game::{minion::MinionKind, objects::definitions::ColorDef};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+/// How much faster than other minions agile ones walk
+pub const AGILE_SPEED_FACTOR: f32 = 1.5;
+/// Force strong minions push heavy objects with
+pub const STRONG_PUSH_FORCE: f32 = 4000.0;
+/// Density of heavy objects, too much for the impulses of other characters
+pub const HEAVY_OBJECT_DENSITY: f32 = 50.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Reflect)]
+pub enum Ability {
+    /// Pushes heavy objects
+    Strong,
+    /// Walks faster
+    Agile,
+    /// Isn't seen by camera spotlights
+    Stealthy,
+    /// Powers power outlets
+    Conductive,
+    /// No effect yet, white and magenta minions have it
+    Heavy,
+    /// Nothing uses it yet either, it comes with white and cyan
+    Fleeting,
+}
+
+impl Ability {
+    pub const VARIANTS: [Ability; 6] = [
+        Ability::Strong,
+        Ability::Agile,
+        Ability::Stealthy,
+        Ability::Conductive,
+        Ability::Heavy,
+        Ability::Fleeting,
+    ];
+    pub const COUNT: usize = Self::VARIANTS.len();
+
+    #[rustfmt::skip]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Ability::Strong     => "Strong",
+            Ability::Agile      => "Agile",
+            Ability::Stealthy   => "Stealthy",
+            Ability::Conductive => "Conductive",
+            Ability::Heavy      => "Heavy",
+            Ability::Fleeting   => "Fleeting",
+        }
+    }
+}
+
+impl AsRef<str> for Ability {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl MinionKind {
+    #[rustfmt::skip]
+    pub fn has_ability(self, ability: Ability) -> bool {
+        let col = ColorDef::from(self);
+        match ability {
+            Ability::Strong     => col == ColorDef::White || col == ColorDef::Red    || col == ColorDef::Magenta,
+            Ability::Agile      => col == ColorDef::White || col == ColorDef::Green  || col == ColorDef::Yellow,
+            Ability::Stealthy   => col == ColorDef::White || col == ColorDef::Blue   || col == ColorDef::Cyan,
+            Ability::Conductive => col == ColorDef::White || col == ColorDef::Yellow,
+            Ability::Heavy      => col == ColorDef::White || col == ColorDef::Magenta,
+            Ability::Fleeting   => col == ColorDef::White || col == ColorDef::Cyan,
+        }
+    }
+
+    pub fn abilities(self) -> impl Iterator<Item = Ability> {
+        Ability::VARIANTS
+            .into_iter()
+            .filter(move |ability| self.has_ability(*ability))
+    }
+
+    /// Multiplies the walking speed of minions of this kind
+    pub fn speed_factor(self) -> f32 {
+        match self.has_ability(Ability::Agile) {
+            true => AGILE_SPEED_FACTOR,
+            false => 1.0,
+        }
+    }
+}
+
+/// Dynamic body only strong minions can move. Needs an [`ExternalImpulse`] to be pushed.
+#[derive(Clone, Copy, Default, Debug, Component, Reflect)]
+#[reflect(Component)]
+pub struct HeavyObject;
+
+/// Pushes the heavy objects strong minions walk into
+pub fn push_heavy_objects(
+    time: Res<Time>,
+    minion_q: Query<(&MinionKind, &KinematicCharacterControllerOutput)>,
+    mut heavy_q: Query<&mut ExternalImpulse, With<HeavyObject>>,
+) {
+    for (kind, output) in minion_q.iter() {
+        if !kind.has_ability(Ability::Strong) {
+            continue;
+        }
+        for collision in output.collisions.iter() {
+            let Ok(mut impulse) = heavy_q.get_mut(collision.entity) else {
+                continue;
+            };
+            let direction = collision
+                .translation_remaining
+                .with_y(0.0)
+                .normalize_or_zero();
+            impulse.impulse += direction * STRONG_PUSH_FORCE * time.delta_seconds();
+        }
+    }
+}
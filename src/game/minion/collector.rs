@@ -1,6 +1,12 @@
-use crate::game::minion::{MinionKind, MinionState};
+use crate::game::{
+    minion::{ability::Ability, MinionKind, MinionState},
+    objects::definitions::{ObjectDef, ObjectDefKind},
+};
 use bevy::{prelude::*, utils::HashMap};
 
+/// Minions of each kind the player starts a level with
+pub const STARTING_MINIONS_PER_KIND: u32 = 5;
+
 #[derive(Component, Reflect, Debug)]
 pub struct MinionStorage {
     storage: HashMap<MinionKind, u32>,
@@ -13,6 +19,17 @@ impl MinionStorage {
         }
     }
 
+    /// What the player starts a level with
+    pub fn player_start() -> Self {
+        let mut storage = Self::new();
+        for kind in MinionKind::VARIANTS {
+            for _ in 0..STARTING_MINIONS_PER_KIND {
+                storage.add_minion(kind);
+            }
+        }
+        storage
+    }
+
     pub fn add_minion(&mut self, ty: MinionKind) {
         *self.storage.entry(ty).or_default() += 1;
     }
@@ -34,10 +51,13 @@ impl MinionStorage {
     }
 }
 
+/// Minions that have to interact with an object at the same time. A minion counts both
+/// for its kind and for each of its abilities.
 #[derive(Clone, Debug, Component, Default, Reflect)]
 #[reflect(Component)]
 pub struct MinionInteractionRequirement {
     pub counts: HashMap<MinionKind, u32>,
+    pub abilities: HashMap<Ability, u32>,
     pub is_satisfied: bool,
 }
 
@@ -45,29 +65,72 @@ impl MinionInteractionRequirement {
     pub fn new(counts: HashMap<MinionKind, u32>) -> Self {
         Self {
             counts,
+            abilities: HashMap::new(),
+            is_satisfied: false,
+        }
+    }
+
+    pub fn with_abilities(abilities: HashMap<Ability, u32>) -> Self {
+        Self {
+            counts: HashMap::new(),
+            abilities,
             is_satisfied: false,
         }
     }
+
+    /// Requirement the object is spawned with, if it has one
+    pub fn for_object(def: &ObjectDef) -> Option<Self> {
+        match def.kind {
+            ObjectDefKind::DestructibleTargetTest => {
+                Some(Self::new([(MinionKind::Void, 2)].into_iter().collect()))
+            }
+            ObjectDefKind::PowerOutlet => Some(Self::with_abilities(
+                [(Ability::Conductive, def.number.max(1))]
+                    .into_iter()
+                    .collect(),
+            )),
+            ObjectDefKind::KeyDoor | ObjectDefKind::ExplosiveBarrel => Some(Self::new(
+                [(MinionKind::from(def.color), def.number.max(1))]
+                    .into_iter()
+                    .collect(),
+            )),
+            ObjectDefKind::Barrier => Some(Self::with_abilities(
+                [(Ability::Strong, def.number.max(1))].into_iter().collect(),
+            )),
+            _ => None,
+        }
+    }
 }
 
 pub fn update_minion_interaction_requirements(
     mut buffer: Local<HashMap<MinionKind, u32>>,
+    mut ability_buffer: Local<HashMap<Ability, u32>>,
     mut requirements_q: Query<(Entity, &mut MinionInteractionRequirement)>,
     minion_q: Query<(&MinionKind, &MinionState)>,
 ) {
     for (ent, mut req) in requirements_q.iter_mut() {
         buffer.clear();
+        ability_buffer.clear();
 
         minion_q
             .iter()
             .filter(|(_, st)| **st == MinionState::Interracting(ent))
             .map(|(kind, _)| *kind)
-            .for_each(|kind| *buffer.entry(kind).or_default() += 1);
+            .for_each(|kind| {
+                *buffer.entry(kind).or_default() += 1;
+                kind.abilities()
+                    .for_each(|ability| *ability_buffer.entry(ability).or_default() += 1);
+            });
 
         req.is_satisfied = req
             .counts
             .iter()
             .filter(|(_, cnt)| **cnt > 0)
-            .all(|(k, cnt)| buffer.get(k).map(|x| *x).unwrap_or_default() >= *cnt);
+            .all(|(k, cnt)| buffer.get(k).map(|x| *x).unwrap_or_default() >= *cnt)
+            && req
+                .abilities
+                .iter()
+                .filter(|(_, cnt)| **cnt > 0)
+                .all(|(a, cnt)| ability_buffer.get(a).map(|x| *x).unwrap_or_default() >= *cnt);
     }
 }
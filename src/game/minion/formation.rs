@@ -0,0 +1,126 @@
+use crate::game::{
+    minion::MinionState,
+    player::{minion_storage::MinionStorageInput, PlayerTag},
+};
+use bevy::prelude::*;
+use std::f32::consts::TAU;
+
+/// Distance between neighboring slots of a formation
+pub const FORMATION_SPACING: f32 = 0.8;
+/// Distance between the player and the closest slots
+pub const FORMATION_DISTANCE: f32 = 1.5;
+/// How far from their slot minions in formation stand still
+pub const FORMATION_TOLERANCE: f32 = 0.3;
+
+/// How minions following in formation line up around the player
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum Formation {
+    /// Side by side behind the player
+    #[default]
+    Line,
+    /// One after the other behind the player
+    Column,
+    /// All around the player
+    Circle,
+}
+
+impl Formation {
+    pub fn next(self) -> Self {
+        match self {
+            Formation::Line => Formation::Column,
+            Formation::Column => Formation::Circle,
+            Formation::Circle => Formation::Line,
+        }
+    }
+
+    /// Offset of slot `index` of `count` from the player, facing -Z
+    pub fn slot_offset(self, index: usize, count: usize) -> Vec3 {
+        let index = index as f32;
+        match self {
+            Formation::Line => {
+                let x = (index - (count as f32 - 1.0) * 0.5) * FORMATION_SPACING;
+                Vec3::new(x, 0.0, FORMATION_DISTANCE)
+            }
+            Formation::Column => Vec3::Z * (FORMATION_DISTANCE + index * FORMATION_SPACING),
+            Formation::Circle => {
+                let radius = f32::max(FORMATION_DISTANCE, count as f32 * FORMATION_SPACING / TAU);
+                let angle = index / count as f32 * TAU;
+                Vec3::new(angle.sin(), 0.0, angle.cos()) * radius
+            }
+        }
+    }
+}
+
+/// Where a minion following in formation is headed
+#[derive(Clone, Copy, Debug, Component)]
+pub struct FormationSlot(pub Vec3);
+
+/// Hands out the slots around the player to the minions following in formation, sorted
+/// by entity so they mostly keep their places while others join or leave
+pub fn update_formation_slots(
+    input: Res<MinionStorageInput>,
+    player_q: Query<&GlobalTransform, With<PlayerTag>>,
+    mut minion_q: Query<(Entity, &MinionState, Option<&mut FormationSlot>)>,
+    left_q: Query<(Entity, &MinionState), (Changed<MinionState>, With<FormationSlot>)>,
+    mut commands: Commands,
+) {
+    for (ent, state) in left_q.iter() {
+        if *state != MinionState::InFormation {
+            commands.entity(ent).remove::<FormationSlot>();
+        }
+    }
+
+    let Ok(player_gx) = player_q.get_single() else {
+        return;
+    };
+    let player_tf = player_gx.compute_transform();
+    let rotation = Quat::from_rotation_y(player_tf.rotation.to_euler(EulerRot::YXZ).0);
+
+    let mut members = minion_q
+        .iter_mut()
+        .filter(|(_, state, _)| **state == MinionState::InFormation)
+        .collect::<Vec<_>>();
+    members.sort_by_key(|(ent, ..)| *ent);
+
+    let count = members.len();
+    for (index, (ent, _, slot)) in members.into_iter().enumerate() {
+        let offset = input.formation.slot_offset(index, count);
+        let position = player_tf.translation + rotation * offset;
+        match slot {
+            Some(mut slot) => slot.0 = position,
+            None => {
+                commands.entity(ent).insert(FormationSlot(position));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_formation_slots() {
+    // Side by side, centered behind the player
+    let line = (0..3)
+        .map(|i| Formation::Line.slot_offset(i, 3))
+        .collect::<Vec<_>>();
+    assert!(line[0].abs_diff_eq(Vec3::new(-FORMATION_SPACING, 0.0, FORMATION_DISTANCE), 1e-5));
+    assert!(line[1].abs_diff_eq(Vec3::Z * FORMATION_DISTANCE, 1e-5));
+    assert!(line[2].abs_diff_eq(Vec3::new(FORMATION_SPACING, 0.0, FORMATION_DISTANCE), 1e-5));
+
+    // One after the other
+    let column = Formation::Column.slot_offset(2, 3);
+    assert!(column.abs_diff_eq(
+        Vec3::Z * (FORMATION_DISTANCE + 2.0 * FORMATION_SPACING),
+        1e-5
+    ));
+
+    // Evenly spread around the player, further away when there are many
+    let circle = Formation::Circle.slot_offset(1, 4);
+    assert!(circle.abs_diff_eq(Vec3::X * FORMATION_DISTANCE, 1e-5));
+    let crowd = (0..40)
+        .map(|i| Formation::Circle.slot_offset(i, 40))
+        .collect::<Vec<_>>();
+    let gap = crowd[0].distance(crowd[1]);
+    assert!((gap - FORMATION_SPACING).abs() < 0.01);
+    assert!(crowd.iter().all(|slot| slot.y == 0.0));
+
+    assert_eq!(Formation::Circle.next(), Formation::Line);
+}
@@ -0,0 +1,231 @@
+//! Minions take damage from hazard faces, laser grids and explosions, and play a short
+//! death animation once their health runs out.
+
+use crate::{
+    framework::audio::{Audio, AudioChannel, Volume},
+    game::{
+        audio::AudioAssets,
+        minion::{MinionKind, MinionPath, MinionState},
+    },
+};
+use bevy::{prelude::*, utils::HashMap};
+
+pub const MINION_MAX_HEALTH: f32 = 3.0;
+/// Damage per second minions take while standing on hazard faces
+pub const HAZARD_DAMAGE_PER_SEC: f32 = 12.0;
+/// How long the death animation plays before the minion is despawned
+pub const MINION_DEATH_SECS: f32 = 0.5;
+
+#[derive(Clone, Copy, Debug, Component, Reflect)]
+#[reflect(Component)]
+pub struct MinionHealth {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Default for MinionHealth {
+    fn default() -> Self {
+        Self {
+            current: MINION_MAX_HEALTH,
+            max: MINION_MAX_HEALTH,
+        }
+    }
+}
+
+impl MinionHealth {
+    /// Takes `amount` off the health. Returns whether the minion just died of it, which
+    /// is never the case again for more damage in the same frame.
+    pub fn take_damage(&mut self, amount: f32) -> bool {
+        if self.current <= 0.0 {
+            return false;
+        }
+        self.current -= amount;
+        self.current <= 0.0
+    }
+}
+
+/// What hurt a minion
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Reflect)]
+pub enum DamageCause {
+    Hazard,
+    LaserGrid,
+    Explosion,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct MinionDamage {
+    pub minion: Entity,
+    pub amount: f32,
+    pub cause: DamageCause,
+}
+
+/// Sent when the health of a minion runs out, before its death animation
+#[derive(Event, Clone, Copy, Debug)]
+pub struct MinionDied {
+    pub minion: Entity,
+    pub kind: MinionKind,
+    pub position: Vec3,
+    pub cause: DamageCause,
+}
+
+/// Hurts the minions within `radius`, the closer the more
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Explosion {
+    pub position: Vec3,
+    pub radius: f32,
+    pub damage: f32,
+}
+
+impl Explosion {
+    /// Damage at `position`, none from the edge of the radius on
+    pub fn damage_at(&self, position: Vec3) -> Option<f32> {
+        let distance = position.distance(self.position);
+        (distance < self.radius).then(|| self.damage * (1.0 - distance / self.radius))
+    }
+}
+
+/// Plays the death animation, the minion is despawned at its end
+#[derive(Component)]
+pub struct MinionDying {
+    timer: Timer,
+}
+
+/// Minions of each kind lost in the current level
+#[derive(Resource, Clone, Debug, Default, Reflect)]
+#[reflect(Resource)]
+pub struct MinionLossStats {
+    lost: HashMap<MinionKind, u32>,
+}
+
+impl MinionLossStats {
+    pub fn num_lost(&self, kind: MinionKind) -> u32 {
+        self.lost.get(&kind).copied().unwrap_or_default()
+    }
+
+    pub fn total(&self) -> u32 {
+        self.lost.values().sum()
+    }
+
+    fn count(&mut self, kind: MinionKind) {
+        *self.lost.entry(kind).or_default() += 1;
+    }
+}
+
+pub fn explosion_damage(
+    mut explosions: EventReader<Explosion>,
+    minion_q: Query<(Entity, &GlobalTransform), With<MinionHealth>>,
+    mut damage: EventWriter<MinionDamage>,
+) {
+    for explosion in explosions.read() {
+        for (minion, gx) in minion_q.iter() {
+            let Some(amount) = explosion.damage_at(gx.translation()) else {
+                continue;
+            };
+            damage.send(MinionDamage {
+                minion,
+                amount,
+                cause: DamageCause::Explosion,
+            });
+        }
+    }
+}
+
+/// Takes the damage off the health of minions and starts the death of those without any
+/// health left
+pub fn apply_minion_damage(
+    mut cmd: Commands,
+    mut damage: EventReader<MinionDamage>,
+    mut minion_q: Query<
+        (
+            &GlobalTransform,
+            &MinionKind,
+            &mut MinionState,
+            &mut MinionHealth,
+        ),
+        Without<MinionDying>,
+    >,
+    mut died: EventWriter<MinionDied>,
+    mut audio: ResMut<Audio>,
+    sfx: Res<AudioAssets>,
+) {
+    for damage in damage.read() {
+        let Ok((gx, kind, mut state, mut health)) = minion_q.get_mut(damage.minion) else {
+            continue;
+        };
+        if !health.take_damage(damage.amount) {
+            continue;
+        }
+
+        *state = MinionState::Idling;
+        cmd.entity(damage.minion)
+            .remove::<MinionPath>()
+            .insert(MinionDying {
+                timer: Timer::from_seconds(MINION_DEATH_SECS, TimerMode::Once),
+            });
+        died.send(MinionDied {
+            minion: damage.minion,
+            kind: *kind,
+            position: gx.translation(),
+            cause: damage.cause,
+        });
+        audio.play_vol(
+            sfx.minion_kill_1.clone(),
+            AudioChannel::SFX,
+            Volume::Amplitude(0.7),
+        );
+    }
+}
+
+/// Spins dying minions while they shrink away
+pub fn minion_dying(
+    mut cmd: Commands,
+    mut minion_q: Query<(Entity, &mut Transform, &mut MinionDying)>,
+    time: Res<Time>,
+) {
+    for (ent, mut tx, mut dying) in minion_q.iter_mut() {
+        dying.timer.tick(time.delta());
+        let t = dying.timer.fraction();
+        tx.scale = Vec3::splat((1.0 - t).max(0.01));
+        tx.rotate_y(time.delta_seconds() * 12.0);
+        if dying.timer.finished() {
+            cmd.entity(ent).despawn_recursive();
+        }
+    }
+}
+
+pub fn count_lost_minions(mut died: EventReader<MinionDied>, mut stats: ResMut<MinionLossStats>) {
+    for died in died.read() {
+        stats.count(died.kind);
+    }
+}
+
+#[test]
+fn test_minion_damage() {
+    let explosion = Explosion {
+        position: Vec3::new(1.0, 0.0, 1.0),
+        radius: 2.0,
+        damage: 4.0,
+    };
+    // Full damage at the center, falling off to none at the edge
+    assert_eq!(explosion.damage_at(explosion.position), Some(4.0));
+    assert_eq!(explosion.damage_at(Vec3::new(2.0, 0.0, 1.0)), Some(2.0));
+    assert_eq!(explosion.damage_at(Vec3::new(3.0, 0.0, 1.0)), None);
+    assert_eq!(explosion.damage_at(Vec3::new(1.0, 5.0, 1.0)), None);
+
+    // Dies once, even when more damage hits it in the same frame
+    let mut health = MinionHealth::default();
+    assert!(!health.take_damage(2.0));
+    assert!(health.take_damage(2.0));
+    assert!(!health.take_damage(2.0));
+    assert!(!health.take_damage(0.0));
+
+    let mut stats = MinionLossStats::default();
+    assert_eq!(stats.total(), 0);
+    stats.count(MinionKind::Red);
+    stats.count(MinionKind::Red);
+    stats.count(MinionKind::Blue);
+    assert_eq!(stats.num_lost(MinionKind::Red), 2);
+    assert_eq!(stats.num_lost(MinionKind::Blue), 1);
+    assert_eq!(stats.num_lost(MinionKind::Void), 0);
+    assert_eq!(stats.total(), 3);
+}
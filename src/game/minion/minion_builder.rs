@@ -1,9 +1,14 @@
-use crate::game::{
-    collision_groups::{ACTOR_GROUP, DETECTION_GROUP, GROUND_GROUP},
-    common::ShowForwardGizmo,
-    kinematic_char::KinematicCharacterBundle,
-    minion::{MinionAnimation, MinionKind, MinionState},
-    objects::{camera::Shineable, definitions::ColorDef},
+use crate::{
+    framework::navmesh::{NavAgent, MINION_BLOCKING_FLAGS},
+    game::{
+        collision_groups::{ACTOR_GROUP, DETECTION_GROUP, GROUND_GROUP},
+        common::ShowForwardGizmo,
+        kinematic_char::{BlockingTileFlags, CharacterWalkControl, KinematicCharacterBundle},
+        minion::{
+            health::MinionHealth, stuck::MinionProgress, MinionAnimation, MinionKind, MinionState,
+        },
+        objects::{camera::Shineable, definitions::ColorDef},
+    },
 };
 use bevy::{asset::LoadState, color::palettes::tailwind, prelude::*};
 use bevy_rapier3d::prelude::*;
@@ -37,11 +42,21 @@ impl MinionBuilder {
             },
             self.kind,
             self.state,
+            MinionProgress::default(),
+            MinionHealth::default(),
             Shineable,
             Collider::cuboid(0.3, COLLIDER_HALF_HEIGHT, 0.3),
             // good idea? add detection group so they get matched with the pickup cone
             CollisionGroups::new(ACTOR_GROUP, GROUND_GROUP | DETECTION_GROUP),
-            KinematicCharacterBundle::default(),
+            KinematicCharacterBundle {
+                blocking: BlockingTileFlags(MINION_BLOCKING_FLAGS),
+                control: CharacterWalkControl {
+                    speed_factor: self.kind.speed_factor(),
+                    ..default()
+                },
+                ..default()
+            },
+            NavAgent::Minion,
         );
         let body = PbrBundle {
             mesh: assets.body_mesh(self.kind),
@@ -1,9 +1,14 @@
 use crate::{
-    framework::easing::Easing,
+    framework::{
+        easing::Easing,
+        navmesh::{self, NavAgent, PathLeg},
+        tilemap::TileFlags,
+    },
     game::{
         collision_groups::{ACTOR_GROUP, GROUND_GROUP, TARGET_GROUP, WALL_GROUP},
         common::RootParent,
-        kinematic_char::KinematicCharacterBundle,
+        kinematic_char::{CharacterWalkState, KinematicCharacterBundle},
+        level::NavMeshChanged,
         minion::collector::MinionStorage,
         objects::camera::Shineable,
         player::{minion_storage::MinionStorageInput, PlayerTag},
@@ -13,20 +18,40 @@ use crate::{
 use bevy::{color::palettes::tailwind, prelude::*, time::Real};
 use bevy_rapier3d::{
     plugin::RapierContext,
-    prelude::{Collider, CollisionGroups, Group, QueryFilter},
+    prelude::{
+        Collider, ColliderDisabled, CollisionGroups, Group, KinematicCharacterController,
+        QueryFilter,
+    },
 };
+use formation::{FormationSlot, FORMATION_TOLERANCE};
+use health::{DamageCause, MinionDamage, MinionHealth, MinionLossStats, HAZARD_DAMAGE_PER_SEC};
 use minion_builder::MinionMeshTag;
 use std::f32::consts::{PI, TAU};
-use vleue_navigator::{NavMesh, TransformedPath};
+use stuck::{MinionLost, MinionRecovery};
+use throw::MinionFlight;
+use vleue_navigator::NavMesh;
 
 use super::common;
 
+pub mod ability;
 pub mod collector;
+pub mod formation;
+pub mod health;
 pub mod minion_builder;
+pub mod steering;
+pub mod stuck;
+pub mod throw;
 pub mod walk_target;
 
 pub const MINION_INTERRACTION_RANGE: f32 = 0.5;
 pub const MINION_NODE_DIST: f32 = 0.1;
+/// How far from a minion or its target the navmesh is searched when they stand off of it,
+/// e.g. on top of an obstacle
+pub const MINION_NAVMESH_SEARCH_DIST: f32 = 2.0;
+/// Speed minions take links with, a bit slower than walking
+pub const MINION_LINK_SPEED: f32 = 3.0;
+/// How high minions hop while taking a link
+pub const MINION_LINK_HOP_HEIGHT: f32 = 0.6;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Component, Reflect, Default)]
 pub enum MinionKind {
@@ -81,7 +106,7 @@ impl AsRef<str> for MinionKind {
 #[reflect(Component)]
 pub struct MinionTarget;
 
-#[derive(Clone, Copy, Default, Debug, Component, Reflect, PartialEq, Eq)]
+#[derive(Clone, Copy, Default, Debug, Component, Reflect, PartialEq)]
 #[reflect(Component)]
 pub enum MinionState {
     #[default]
@@ -89,6 +114,34 @@ pub enum MinionState {
     GoingToPlayer,
     GoingTo(Entity),
     Interracting(Entity),
+    /// Recalled, goes back into the storage of the player once it gets to them
+    Returning,
+    /// Ordered to wait at a position, walks back there when pushed away
+    Holding(Vec3),
+    /// Follows the player in its [`formation::FormationSlot`]
+    InFormation,
+}
+
+impl MinionState {
+    /// Minions that can be recalled or ordered around, i.e. not busy interacting
+    pub fn is_commandable(self) -> bool {
+        !matches!(self, MinionState::Interracting(_))
+    }
+
+    /// Ordered to hold or to follow in formation, which lasts until the next order
+    pub fn is_ordered(self) -> bool {
+        matches!(self, MinionState::Holding(_) | MinionState::InFormation)
+    }
+
+    /// Idles after failing to get to the target. Orders are kept and retried instead.
+    /// Returns whether the minion gave up.
+    fn give_up(&mut self) -> bool {
+        if self.is_ordered() {
+            return false;
+        }
+        *self = MinionState::Idling;
+        true
+    }
 }
 
 #[derive(Bundle)]
@@ -117,44 +170,63 @@ impl Default for MinionBundle {
 }
 
 #[derive(Component)]
-pub struct MinionPath(TransformedPath);
+/// Path to walk, and the target position it was built for
+pub struct MinionPath(Vec<Vec3>, Vec3);
+
+/// Link a minion is taking, moved along its ends without walking or colliding
+#[derive(Component)]
+pub struct MinionTraversal {
+    from: Vec3,
+    to: Vec3,
+    elapsed: f32,
+    duration: f32,
+}
+
+impl MinionTraversal {
+    pub fn new(from: Vec3, to: Vec3) -> Self {
+        Self {
+            from,
+            to,
+            elapsed: 0.0,
+            duration: (from.distance(to) / MINION_LINK_SPEED).max(0.3),
+        }
+    }
+}
 
-// TODO: render it more aligned to the level
 #[cfg(feature = "debug_visuals")]
 pub fn debug_navmesh(
     level_reses: Res<LevelResources>,
     navmeshes: Res<Assets<NavMesh>>,
     mut gizmos: Gizmos,
 ) {
-    let Some(navmesh) = &level_reses.navmesh else {
+    let Some(navmesh) = level_reses.navmesh(NavAgent::Minion) else {
         return;
     };
     let Some(navmesh) = navmeshes.get(navmesh.id()) else {
         return;
     };
+    let Some(elevation) = level_reses.nav_elevation(NavAgent::Minion) else {
+        return;
+    };
     let red = LinearRgba {
         red: 1.0,
         green: 0.0,
         blue: 0.0,
         alpha: 1.0,
     };
-    let verts = &navmesh.get().vertices;
-
     for poly in &navmesh.get().polygons {
         let fst = poly
             .vertices
             .iter()
             .map(|x| *x)
-            .map(|x| verts[x as usize].coords)
-            .map(|v| Vec3::new(v.x, 0.0, v.y));
+            .map(|x| elevation.vertex(x) + Vec3::Y * 0.05);
         let snd = poly
             .vertices
             .iter()
             .map(|x| *x)
             .skip(1)
             .chain(std::iter::once(poly.vertices[0]))
-            .map(|x| verts[x as usize].coords)
-            .map(|v| Vec3::new(v.x, 0.0, v.y));
+            .map(|x| elevation.vertex(x) + Vec3::Y * 0.05);
         for (start, end) in fst.zip(snd) {
             gizmos.line(start, end, red);
 
@@ -167,7 +239,7 @@ pub fn debug_navmesh(
 }
 
 pub fn minion_update_path(
-    mut minion_q: Query<(Entity, &MinionState, &MinionPath)>,
+    mut minion_q: Query<(Entity, &MinionState, &MinionPath, Option<&FormationSlot>)>,
     target_q: Query<&GlobalTransform, With<MinionTarget>>,
     player_q: Query<&GlobalTransform, With<PlayerTag>>,
     mut commands: Commands,
@@ -176,9 +248,9 @@ pub fn minion_update_path(
         return;
     };
 
-    for (ent, state, path) in minion_q.iter_mut() {
+    for (ent, state, path, slot) in minion_q.iter_mut() {
         let target_pos = match &state {
-            MinionState::GoingToPlayer => player_tf.translation(),
+            MinionState::GoingToPlayer | MinionState::Returning => player_tf.translation(),
             MinionState::GoingTo(target) => match target_q.get(*target) {
                 Ok(tf) => tf.translation(),
                 Err(e) => {
@@ -186,16 +258,26 @@ pub fn minion_update_path(
                     continue;
                 }
             },
+            MinionState::Holding(position) => *position,
+            MinionState::InFormation => match slot {
+                Some(slot) => slot.0,
+                None => continue,
+            },
             _ => continue,
         };
-        let target_navmesh_pos = Vec3::new(target_pos.x, 0.0, target_pos.z);
-        let Some(last) = path.0.path.last() else {
+        if path.0.is_empty() {
             // info!("Removing last path");
             commands.entity(ent).remove::<MinionPath>();
             continue;
-        };
+        }
 
-        if target_navmesh_pos.distance(*last) < MINION_NODE_DIST {
+        // Formation slots move with the player, small moves don't need a new path
+        let tolerance = if state.is_ordered() {
+            FORMATION_TOLERANCE
+        } else {
+            MINION_NODE_DIST
+        };
+        if target_pos.xz().distance(path.1.xz()) < tolerance {
             continue;
         }
         // info!("Removing path at the end of update");
@@ -206,24 +288,45 @@ pub fn minion_update_path(
 pub fn minion_build_path(
     level_reses: Res<LevelResources>,
     navmeshes: Res<Assets<NavMesh>>,
-    mut minion_q: Query<(Entity, &GlobalTransform, &mut MinionState), Without<MinionPath>>,
+    mut minion_q: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            &mut MinionState,
+            &NavAgent,
+            Option<&FormationSlot>,
+        ),
+        (
+            Without<MinionPath>,
+            Without<MinionTraversal>,
+            Without<MinionFlight>,
+        ),
+    >,
     target_q: Query<&GlobalTransform, With<MinionTarget>>,
     player_q: Query<&GlobalTransform, With<PlayerTag>>,
+    mut lost: EventWriter<MinionLost>,
     mut commands: Commands,
 ) {
     let Ok(player_tf) = player_q.get_single() else {
         return;
     };
-    let Some(navmesh) = &level_reses.navmesh else {
-        return;
-    };
-    let Some(navmesh) = navmeshes.get(navmesh) else {
-        return;
-    };
 
-    for (ent, tf, mut state) in minion_q.iter_mut() {
+    let snap = |p: Vec3| match &level_reses.tilemap {
+        Some(tilemap) => tilemap.snap_to_ground(p),
+        None => p,
+    };
+    for (ent, tf, mut state, agent, slot) in minion_q.iter_mut() {
+        let Some(navmesh) = level_reses
+            .navmesh(*agent)
+            .and_then(|navmesh| navmeshes.get(navmesh))
+        else {
+            continue;
+        };
+        let Some(elevation) = level_reses.nav_elevation(*agent) else {
+            continue;
+        };
         let target_pos = match state.as_ref() {
-            MinionState::GoingToPlayer => player_tf.translation(),
+            MinionState::GoingToPlayer | MinionState::Returning => player_tf.translation(),
             MinionState::GoingTo(target) => match target_q.get(*target) {
                 Ok(tf) => tf.translation(),
                 Err(e) => {
@@ -231,47 +334,116 @@ pub fn minion_build_path(
                     continue;
                 }
             },
+            MinionState::Holding(position) => *position,
+            MinionState::InFormation => match slot {
+                Some(slot) => slot.0,
+                None => continue,
+            },
             _ => continue,
         };
-
-        if !navmesh.transformed_is_in_mesh(tf.translation()) {
-            error!("Minion is not in the navigation: {:?}", tf.translation());
-            *state = MinionState::Idling;
+        // Holding and formation positions are never reached by `update_minion_state`
+        if state.is_ordered()
+            && tf.translation().xz().distance(target_pos.xz()) < FORMATION_TOLERANCE
+        {
             continue;
         }
-        if !navmesh.transformed_is_in_mesh(target_pos) {
-            warn!("Minion target is not in the navigation");
-            *state = MinionState::Idling;
+
+        let Some(start) =
+            navmesh::closest_point_in_mesh(navmesh, tf.translation(), MINION_NAVMESH_SEARCH_DIST)
+        else {
+            // Pushed or thrown off the navmesh, walks back onto it first
+            let back =
+                stuck::path_back_to_mesh(&level_reses, navmesh, tf.translation(), target_pos);
+            let recovery = match back {
+                Some(back) => {
+                    commands.entity(ent).insert(back);
+                    MinionRecovery::Repath
+                }
+                None if state.give_up() => MinionRecovery::Unrecoverable,
+                None => continue,
+            };
+            warn!("Minion is not in the navigation: {:?}", tf.translation());
+            lost.send(MinionLost {
+                minion: ent,
+                position: tf.translation(),
+                recovery,
+            });
             continue;
-        }
+        };
+        // Paths end on the ground under the target
+        let Some(end) =
+            navmesh::closest_point_in_mesh(navmesh, target_pos, MINION_NAVMESH_SEARCH_DIST)
+                .map(snap)
+        else {
+            if state.give_up() {
+                warn!("Minion target is not in the navigation");
+            }
+            continue;
+        };
 
-        let Some(path) = navmesh.transformed_path(
-            Vec3::new(tf.translation().x, 0.0, tf.translation().z),
-            Vec3::new(target_pos.x, 0.0, target_pos.z),
-        ) else {
-            warn!("Failed to find the path");
-            *state = MinionState::Idling;
+        let Some(legs) =
+            navmesh::path_with_links(navmesh, elevation, &level_reses.nav_links, start, end)
+        else {
+            if state.give_up() {
+                warn!("Failed to find the path");
+            }
             continue;
         };
+        // Only the next leg is kept, the path is built again once it's done
+        let next = legs.into_iter().find(|leg| match leg {
+            PathLeg::Walk(path) => path
+                .last()
+                .is_some_and(|p| p.xz().distance(start.xz()) > MINION_NODE_DIST),
+            PathLeg::Link(_) => true,
+        });
+        match next {
+            Some(PathLeg::Walk(path)) => {
+                commands.entity(ent).insert(MinionPath(path, target_pos));
+            }
+            Some(PathLeg::Link(link)) => {
+                let to = snap(link.end) + Vec3::Y * minion_builder::COLLIDER_HALF_HEIGHT;
+                commands
+                    .entity(ent)
+                    .insert((MinionTraversal::new(tf.translation(), to), ColliderDisabled));
+            }
+            None => {}
+        }
+    }
+}
 
-        commands.entity(ent).insert(MinionPath(path));
+/// Drops the paths of all minions after the navmesh changed, so they get built again
+pub fn minion_repath(
+    mut changed: EventReader<NavMeshChanged>,
+    minion_q: Query<Entity, With<MinionPath>>,
+    mut commands: Commands,
+) {
+    if changed.is_empty() {
+        return;
+    }
+    changed.clear();
+    for ent in minion_q.iter() {
+        commands.entity(ent).remove::<MinionPath>();
     }
 }
 
 pub fn minion_walk(
     level_reses: Res<LevelResources>,
     navmeshes: Res<Assets<NavMesh>>,
-    mut minion_q: Query<(&GlobalTransform, &mut CharacterWalkControl, &mut MinionPath)>,
+    mut minion_q: Query<(
+        &GlobalTransform,
+        &mut CharacterWalkControl,
+        &mut MinionPath,
+        &NavAgent,
+    )>,
 ) {
-    let Some(navmesh) = &level_reses.navmesh else {
-        return;
-    };
-    let Some(navmesh) = navmeshes.get(navmesh) else {
-        return;
-    };
-
-    for (tf, mut walk, mut path) in minion_q.iter_mut() {
-        let path = &mut path.0.path;
+    for (tf, mut walk, mut path, agent) in minion_q.iter_mut() {
+        let Some(navmesh) = level_reses
+            .navmesh(*agent)
+            .and_then(|navmesh| navmeshes.get(navmesh))
+        else {
+            continue;
+        };
+        let path = &mut path.0;
 
         if let Some(p) = path.first().map(|x| *x) {
             let minion_pos = navmesh.transform().transform_point(tf.translation()).xy();
@@ -284,25 +456,63 @@ pub fn minion_walk(
 
         if let Some(next) = path.first().map(|x| *x) {
             walk.do_move = true;
-            walk.direction = next - Vec3::new(tf.translation().x, 0.0, tf.translation().z);
+            walk.direction = next - tf.translation();
+        }
+    }
+}
+
+/// Moves minions along the links they take, hopping from one end to the other
+pub fn minion_traverse(
+    time: Res<Time>,
+    mut minion_q: Query<(
+        Entity,
+        &mut Transform,
+        &mut KinematicCharacterController,
+        &mut CharacterWalkControl,
+        &mut CharacterWalkState,
+        &mut MinionTraversal,
+    )>,
+    mut commands: Commands,
+) {
+    for (ent, mut tx, mut controller, mut walk, mut state, mut traversal) in minion_q.iter_mut() {
+        traversal.elapsed += time.delta_seconds();
+        let t = (traversal.elapsed / traversal.duration).min(1.0);
+        let hop = f32::sin(t * PI) * MINION_LINK_HOP_HEIGHT;
+        tx.translation = traversal.from.lerp(traversal.to, t) + Vec3::Y * hop;
+
+        // Nothing else moves them meanwhile, the walk only plays the animation
+        controller.translation = None;
+        *state = CharacterWalkState::default();
+        walk.do_move = t < 1.0;
+        walk.direction = traversal.to - traversal.from;
+        if t >= 1.0 {
+            commands
+                .entity(ent)
+                .remove::<(MinionTraversal, ColliderDisabled)>();
         }
     }
 }
 
 pub fn update_minion_state(
-    mut minion_q: Query<(Entity, &GlobalTransform, &mut MinionState)>,
+    mut minion_q: Query<
+        (Entity, &GlobalTransform, &MinionKind, &mut MinionState),
+        Without<MinionFlight>,
+    >,
     target_q: Query<&GlobalTransform, With<MinionTarget>>,
-    player_q: Query<(Entity, &GlobalTransform), With<PlayerTag>>,
+    mut player_q: Query<(Entity, &GlobalTransform, &mut MinionStorage), With<PlayerTag>>,
     rap_ctx: ResMut<RapierContext>,
     mut started: EventWriter<MinionStartedInteraction>,
+    mut commands: Commands,
 ) {
-    let Ok((player_ent, player_tf)) = player_q.get_single() else {
+    let Ok((player_ent, player_tf, mut storage)) = player_q.get_single_mut() else {
         return;
     };
 
-    for (minion, tf, mut state) in minion_q.iter_mut() {
+    for (minion, tf, kind, mut state) in minion_q.iter_mut() {
         let (target_pos, target_ent) = match state.as_ref() {
-            MinionState::GoingToPlayer => (player_tf.translation(), player_ent),
+            MinionState::GoingToPlayer | MinionState::Returning => {
+                (player_tf.translation(), player_ent)
+            }
             MinionState::GoingTo(target) => match target_q.get(*target) {
                 Ok(tf) => (tf.translation(), *target),
                 Err(e) => {
@@ -332,6 +542,10 @@ pub fn update_minion_state(
 
         match *state {
             MinionState::GoingToPlayer if is_target_reachable => *state = MinionState::Idling,
+            MinionState::Returning if is_target_reachable => {
+                storage.add_minion(*kind);
+                commands.entity(minion).despawn_recursive();
+            }
             MinionState::GoingTo(target) if is_target_reachable => {
                 *state = MinionState::Interracting(target);
                 started.send(MinionStartedInteraction {
@@ -366,6 +580,37 @@ pub fn cleanup_minion_state(
         }
     }
 }
+/// Minions standing on hazard faces burn, they don't last long there
+pub fn minion_hazard(
+    time: Res<Time>,
+    level: Res<LevelResources>,
+    minion_q: Query<
+        (Entity, &GlobalTransform),
+        (
+            With<MinionHealth>,
+            Without<MinionTraversal>,
+            Without<MinionFlight>,
+        ),
+    >,
+    mut damage: EventWriter<MinionDamage>,
+) {
+    let Some(tilemap) = &level.tilemap else {
+        return;
+    };
+    for (minion, gx) in minion_q.iter() {
+        if tilemap
+            .flags_at(gx.translation())
+            .contains(TileFlags::HAZARD)
+        {
+            damage.send(MinionDamage {
+                minion,
+                amount: HAZARD_DAMAGE_PER_SEC * time.delta_seconds(),
+                cause: DamageCause::Hazard,
+            });
+        }
+    }
+}
+
 #[cfg(feature = "debug_visuals")]
 pub fn display_navigator_path(
     navigator: Query<(&Transform, &MinionPath, &GlobalTransform)>,
@@ -373,7 +618,7 @@ pub fn display_navigator_path(
 ) {
     for (_tx, path, gx) in &navigator {
         let y = gx.translation().y + 0.5;
-        let mut to_display = path.0.path.clone();
+        let mut to_display = path.0.clone();
         // to_display.push(tx.translation);
         // to_display.push(path.current.clone());
         to_display.reverse();
@@ -407,13 +652,17 @@ pub fn update_chosen_minion_debug_ui(
     mut text: Query<&mut Text, With<ChosenMinionUi>>,
     minion: ResMut<MinionStorageInput>,
     mut storage: Query<&MinionStorage>,
+    stats: Res<MinionLossStats>,
 ) {
     let storage = storage.single_mut();
     let mut text = text.single_mut();
 
     for (i, kind) in MinionKind::VARIANTS.into_iter().enumerate() {
         let num_minions = storage.num_minions(kind);
-        text.sections[i].value = format!("{}: {}\n", kind.as_str(), num_minions);
+        text.sections[i].value = match stats.num_lost(kind) {
+            0 => format!("{}: {}\n", kind.as_str(), num_minions),
+            lost => format!("{}: {} ({lost} lost)\n", kind.as_str(), num_minions),
+        };
 
         text.sections[i].style.color = if kind == minion.chosen_ty {
             if num_minions == 0 {
@@ -0,0 +1,153 @@
+//! Local steering blended into the walk direction of minions following a path, so groups
+//! of them spread out as a crowd instead of stacking on the same path nodes.
+
+use crate::game::{
+    collision_groups::WALL_GROUP,
+    minion::{MinionKind, MinionPath},
+    CharacterWalkControl,
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+/// Weights and ranges of the minion steering, tunable in the inspector
+#[derive(Resource, Clone, Copy, Debug, Reflect)]
+#[reflect(Resource)]
+pub struct MinionSteering {
+    /// Pull towards the next node of the path
+    pub path_weight: f32,
+    /// Push away from minions closer than `separation_radius`
+    pub separation_weight: f32,
+    pub separation_radius: f32,
+    /// Pull towards the center of the minions within `cohesion_radius`
+    pub cohesion_weight: f32,
+    pub cohesion_radius: f32,
+    /// Push away from walls less than `avoidance_distance` ahead
+    pub avoidance_weight: f32,
+    pub avoidance_distance: f32,
+}
+
+impl Default for MinionSteering {
+    fn default() -> Self {
+        Self {
+            path_weight: 1.0,
+            separation_weight: 1.5,
+            separation_radius: 0.8,
+            cohesion_weight: 0.2,
+            cohesion_radius: 2.0,
+            avoidance_weight: 1.0,
+            avoidance_distance: 0.6,
+        }
+    }
+}
+
+impl MinionSteering {
+    /// Steering of a minion at `position` heading to `direction`, given the positions of
+    /// all minions, itself included
+    pub fn steer(&self, position: Vec3, direction: Vec3, others: &[Vec3]) -> Vec3 {
+        let mut separation = Vec3::ZERO;
+        let mut center = Vec3::ZERO;
+        let mut num_close = 0;
+        for other in others {
+            let offset = (position - *other).with_y(0.0);
+            let distance = offset.length();
+            if distance < 1e-4 || distance > self.cohesion_radius.max(self.separation_radius) {
+                continue;
+            }
+            if distance < self.separation_radius {
+                // Stronger the closer they are
+                separation += offset / distance * (1.0 - distance / self.separation_radius);
+            }
+            if distance < self.cohesion_radius {
+                center += *other;
+                num_close += 1;
+            }
+        }
+        let cohesion = match num_close {
+            0 => Vec3::ZERO,
+            n => (center / n as f32 - position)
+                .with_y(0.0)
+                .normalize_or_zero(),
+        };
+
+        direction.with_y(0.0).normalize_or_zero() * self.path_weight
+            + separation * self.separation_weight
+            + cohesion * self.cohesion_weight
+    }
+}
+
+/// Blends separation, cohesion and wall avoidance into the direction set by
+/// [`super::minion_walk`]
+pub fn minion_steering(
+    steering: Res<MinionSteering>,
+    rapier: Res<RapierContext>,
+    all_q: Query<&GlobalTransform, With<MinionKind>>,
+    mut minion_q: Query<(&GlobalTransform, &mut CharacterWalkControl), With<MinionPath>>,
+) {
+    let positions = all_q.iter().map(|gx| gx.translation()).collect::<Vec<_>>();
+    for (gx, mut walk) in minion_q.iter_mut() {
+        if !walk.do_move {
+            continue;
+        }
+        let position = gx.translation();
+        let mut direction = steering.steer(position, walk.direction, &positions);
+
+        let ahead = direction.normalize_or_zero();
+        let wall = (ahead != Vec3::ZERO)
+            .then(|| {
+                rapier.cast_ray_and_get_normal(
+                    position,
+                    ahead,
+                    steering.avoidance_distance,
+                    true,
+                    QueryFilter {
+                        groups: Some(CollisionGroups::new(Group::all(), WALL_GROUP)),
+                        ..default()
+                    },
+                )
+            })
+            .flatten();
+        if let Some((_, hit)) = wall {
+            let closeness = 1.0 - hit.time_of_impact / steering.avoidance_distance;
+            direction += hit.normal.with_y(0.0) * closeness * steering.avoidance_weight;
+        }
+
+        walk.direction = keep_heading(walk.direction, direction);
+    }
+}
+
+/// The `steered` direction, unless the crowd would stop the minion or turn it around on its
+/// path. It keeps heading in `direction` then.
+fn keep_heading(direction: Vec3, steered: Vec3) -> Vec3 {
+    match steered.dot(direction.with_y(0.0)) > 0.0 {
+        true => steered,
+        false => direction,
+    }
+}
+
+#[test]
+fn test_minion_steering() {
+    let steering = MinionSteering::default();
+    let position = Vec3::ZERO;
+
+    // Alone, only itself among the minions
+    let alone = steering.steer(position, Vec3::X * 3.0, &[position]);
+    assert!(alone.abs_diff_eq(Vec3::X * steering.path_weight, 1e-5));
+
+    // Pushed away from a minion too close to the side
+    let crowded = steering.steer(position, Vec3::X, &[position, Vec3::new(0.0, 0.0, 0.3)]);
+    assert!(crowded.z < 0.0);
+    let closer = steering.steer(position, Vec3::X, &[position, Vec3::new(0.0, 0.0, 0.1)]);
+    assert!(closer.z < crowded.z);
+
+    // Pulled towards a group a bit further away, but not beyond the cohesion radius
+    let group = [position, Vec3::new(0.0, 0.0, 1.5), Vec3::new(0.5, 0.0, 1.5)];
+    assert!(steering.steer(position, Vec3::X, &group).z > 0.0);
+    let far = steering.steer(position, Vec3::X, &[position, Vec3::new(0.0, 0.0, 5.0)]);
+    assert!(far.abs_diff_eq(alone, 1e-5));
+
+    // Never turned around or stopped by the crowd
+    assert_eq!(keep_heading(Vec3::X, Vec3::new(-1.0, 0.0, 0.5)), Vec3::X);
+    assert_eq!(keep_heading(Vec3::X, Vec3::ZERO), Vec3::X);
+    let sideways = Vec3::new(0.2, 0.0, -1.0);
+    assert_eq!(keep_heading(Vec3::X, sideways), sideways);
+}
@@ -0,0 +1,218 @@
+//! Notices minions that stopped getting closer to the next node of their path, e.g.
+//! wedged against a wall or pushed off the navmesh, and gets them going again.
+
+use crate::{
+    framework::navmesh::{self, NavAgent},
+    game::{
+        minion::{
+            formation::FORMATION_SPACING, minion_builder, MinionPath, MinionState, MinionTraversal,
+            MINION_INTERRACTION_RANGE,
+        },
+        LevelResources,
+    },
+};
+use bevy::prelude::*;
+use vleue_navigator::NavMesh;
+
+/// How long a minion may walk without getting closer to its next node
+pub const MINION_STUCK_SECS: f32 = 2.0;
+/// How much closer to its next node a minion has to get to count as progress
+pub const MINION_PROGRESS_DIST: f32 = 0.1;
+/// How far from a lost minion the navmesh is searched to bring it back
+pub const MINION_RECOVERY_SEARCH_DIST: f32 = 5.0;
+
+/// How close a minion got to the next node of its path, and since when
+#[derive(Clone, Copy, Debug, Default, Component, Reflect)]
+#[reflect(Component)]
+pub struct MinionProgress {
+    target: Option<Vec3>,
+    node: Option<Vec3>,
+    closest: f32,
+    stuck_for: f32,
+    /// Recoveries tried since the minion last made progress or got a new target
+    attempts: u32,
+}
+
+impl MinionProgress {
+    /// Tracks a minion at `position` walking along `path` for `delta` seconds. Returns
+    /// whether it is stuck. Stalling within `range` of the target doesn't count, minions
+    /// crowding around it are where they should be.
+    fn track(&mut self, path: &MinionPath, position: Vec3, range: f32, delta: f32) -> bool {
+        let Some(node) = path.0.first().copied() else {
+            return false;
+        };
+        if self.target != Some(path.1) {
+            *self = Self {
+                target: Some(path.1),
+                ..default()
+            };
+        }
+        let distance = position.xz().distance(node.xz());
+        if self.node != Some(node) || position.xz().distance(path.1.xz()) < range {
+            self.node = Some(node);
+            self.closest = distance;
+            self.stuck_for = 0.0;
+            return false;
+        }
+        if distance < self.closest - MINION_PROGRESS_DIST {
+            self.closest = distance;
+            self.stuck_for = 0.0;
+            self.attempts = 0;
+            return false;
+        }
+        self.stuck_for += delta;
+        self.stuck_for >= MINION_STUCK_SECS
+    }
+
+    /// Starts over after a recovery, which escalates if the minion gets stuck again
+    fn recovered(&mut self) {
+        self.node = None;
+        self.stuck_for = 0.0;
+        self.attempts += 1;
+    }
+}
+
+/// What was done about a minion that lost its way
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+pub enum MinionRecovery {
+    /// Walks back onto the navmesh and builds its path again from there
+    Repath,
+    /// Teleported back onto the navmesh, or past what it was stuck on
+    Snap,
+    /// Too far from the navmesh to be brought back, gave up on its target
+    Unrecoverable,
+}
+
+/// Sent when a minion got stuck or off the navmesh, for the UI to flag it
+#[derive(Event, Clone, Copy, Debug)]
+pub struct MinionLost {
+    pub minion: Entity,
+    pub position: Vec3,
+    pub recovery: MinionRecovery,
+}
+
+/// Path back onto the navmesh for a minion standing off of it, keeping its target
+pub fn path_back_to_mesh(
+    level_reses: &LevelResources,
+    navmesh: &NavMesh,
+    position: Vec3,
+    target: Vec3,
+) -> Option<MinionPath> {
+    let nearest = navmesh::closest_point_in_mesh(navmesh, position, MINION_RECOVERY_SEARCH_DIST)?;
+    let nearest = match &level_reses.tilemap {
+        Some(tilemap) => tilemap.snap_to_ground(nearest),
+        None => nearest,
+    };
+    Some(MinionPath(vec![nearest], target))
+}
+
+/// Tracks how close minions get to the next node of their path. Stuck ones first walk
+/// back onto the navmesh and build a new path, then get teleported, then give up.
+pub fn detect_stuck_minions(
+    time: Res<Time>,
+    level_reses: Res<LevelResources>,
+    navmeshes: Res<Assets<NavMesh>>,
+    mut minion_q: Query<
+        (
+            Entity,
+            &mut Transform,
+            &GlobalTransform,
+            &mut MinionState,
+            &MinionPath,
+            &NavAgent,
+            &mut MinionProgress,
+        ),
+        Without<MinionTraversal>,
+    >,
+    mut lost: EventWriter<MinionLost>,
+    mut commands: Commands,
+) {
+    for (ent, mut tx, gx, mut state, path, agent, mut progress) in minion_q.iter_mut() {
+        let position = gx.translation();
+        let range = match state.is_ordered() {
+            true => FORMATION_SPACING,
+            false => MINION_INTERRACTION_RANGE,
+        };
+        if !progress.track(path, position, range, time.delta_seconds()) {
+            continue;
+        }
+        let node = path.0[0];
+
+        let Some(navmesh) = level_reses
+            .navmesh(*agent)
+            .and_then(|navmesh| navmeshes.get(navmesh))
+        else {
+            continue;
+        };
+        let on_mesh = navmesh.transformed_is_in_mesh(position);
+        let back = path_back_to_mesh(&level_reses, navmesh, position, path.1);
+        let recovery = match back {
+            None => {
+                state.give_up();
+                commands.entity(ent).remove::<MinionPath>();
+                MinionRecovery::Unrecoverable
+            }
+            Some(back) if progress.attempts == 0 => {
+                if on_mesh {
+                    commands.entity(ent).remove::<MinionPath>();
+                } else {
+                    commands.entity(ent).insert(back);
+                }
+                MinionRecovery::Repath
+            }
+            Some(back) => {
+                // Past whatever it was wedged against, or onto the mesh it fell off of
+                let to = match on_mesh {
+                    true => node,
+                    false => back.0[0],
+                };
+                tx.translation = to + Vec3::Y * minion_builder::COLLIDER_HALF_HEIGHT;
+                commands.entity(ent).remove::<MinionPath>();
+                MinionRecovery::Snap
+            }
+        };
+        warn!("Minion {ent} is stuck at {position}: {recovery:?}");
+        lost.send(MinionLost {
+            minion: ent,
+            position,
+            recovery,
+        });
+        progress.recovered();
+    }
+}
+
+#[test]
+fn test_minion_progress() {
+    let path = MinionPath(vec![Vec3::X * 4.0], Vec3::X * 8.0);
+    let mut progress = MinionProgress::default();
+    let walk = |progress: &mut MinionProgress, path: &MinionPath, x: f32, secs: f32| {
+        progress.track(path, Vec3::X * x, MINION_INTERRACTION_RANGE, secs)
+    };
+
+    // Walking towards the node is progress, stalling is not
+    assert!(!walk(&mut progress, &path, 0.0, 0.0));
+    assert!(!walk(&mut progress, &path, 1.0, 1.5));
+    assert!(!walk(&mut progress, &path, 1.05, 1.5));
+    assert!(walk(&mut progress, &path, 1.05, 1.0));
+
+    // Escalates while stuck on the same target, back onto the next node
+    progress.recovered();
+    assert!(!walk(&mut progress, &path, 1.05, 0.0));
+    assert!(walk(&mut progress, &path, 1.05, MINION_STUCK_SECS));
+    assert_eq!(progress.attempts, 1);
+
+    // Progress again forgets the recoveries
+    assert!(!walk(&mut progress, &path, 2.0, 0.0));
+    assert_eq!(progress.attempts, 0);
+
+    // So does a new target
+    progress.recovered();
+    let other = MinionPath(vec![Vec3::X * 4.0], Vec3::Z * 8.0);
+    assert!(!walk(&mut progress, &other, 2.0, 0.0));
+    assert_eq!(progress.attempts, 0);
+
+    // Never stuck when crowding around the target
+    let close = MinionPath(vec![Vec3::X * 4.0], Vec3::X * 2.2);
+    assert!(!walk(&mut progress, &close, 2.0, 0.0));
+    assert!(!walk(&mut progress, &close, 2.0, MINION_STUCK_SECS * 2.0));
+}
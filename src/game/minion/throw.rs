@@ -0,0 +1,234 @@
+//! Minions thrown by the player fly along a ballistic arc to where the cursor aims, so they
+//! can be thrown over low walls and onto ledges. Walls they bump into mid-flight drop them.
+
+use crate::{
+    framework::tilemap::Tilemap,
+    game::{
+        collision_groups::{GROUND_GROUP, TARGET_GROUP, WALL_GROUP},
+        kinematic_char::{CharacterWalkState, GRAVITY},
+        minion::{minion_builder, walk_target::WalkTargetTag},
+        CharacterWalkControl,
+    },
+};
+use bevy::{color::palettes::tailwind, prelude::*};
+use bevy_rapier3d::prelude::*;
+
+/// How far the player throws minions at most
+pub const MINION_THROW_RANGE: f32 = 8.0;
+/// How high above the higher of both ends the arc peaks
+pub const MINION_THROW_APEX: f32 = 1.5;
+/// How long minions may keep falling past the end of the arc before they're put down
+pub const MINION_FLIGHT_TIMEOUT: f32 = 2.0;
+/// Steepest surface normal minions land on instead of dropping down along it
+pub const MINION_LANDING_NORMAL_Y: f32 = 0.7;
+/// Points the trajectory preview is drawn with
+const PREVIEW_POINTS: u32 = 32;
+
+/// Ballistic arc under [`GRAVITY`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ThrowArc {
+    pub origin: Vec3,
+    pub velocity: Vec3,
+    /// Time to get to the end the arc was aimed at
+    pub duration: f32,
+}
+
+impl ThrowArc {
+    /// Arc from `from` to `to`, peaking [`MINION_THROW_APEX`] above the higher of both
+    pub fn new(from: Vec3, to: Vec3) -> Self {
+        let gravity = -GRAVITY;
+        let apex = from.y.max(to.y) + MINION_THROW_APEX;
+        let up = f32::sqrt(2.0 * gravity * (apex - from.y));
+        let down = f32::sqrt(2.0 * (apex - to.y) / gravity);
+        let duration = up / gravity + down;
+        Self {
+            origin: from,
+            velocity: (to - from).with_y(0.0) / duration + Vec3::Y * up,
+            duration,
+        }
+    }
+
+    /// Arc a minion is thrown along from `from` towards `to`, cut short at
+    /// [`MINION_THROW_RANGE`]. Also returns where on the ground it ends.
+    pub fn aim(from: Vec3, to: Vec3, tilemap: Option<&Tilemap>) -> (Self, Vec3) {
+        let offset = (to - from).with_y(0.0);
+        let landing = match offset.length() > MINION_THROW_RANGE {
+            true => {
+                let to = from + offset.normalize() * MINION_THROW_RANGE;
+                match tilemap {
+                    Some(tilemap) => tilemap.snap_to_ground(to),
+                    None => to.with_y(from.y),
+                }
+            }
+            false => to,
+        };
+        let end = landing + Vec3::Y * minion_builder::COLLIDER_HALF_HEIGHT;
+        (Self::new(from, end), landing)
+    }
+
+    pub fn position(&self, t: f32) -> Vec3 {
+        self.origin + self.velocity * t + Vec3::Y * 0.5 * GRAVITY * t * t
+    }
+
+    pub fn velocity(&self, t: f32) -> Vec3 {
+        self.velocity + Vec3::Y * GRAVITY * t
+    }
+}
+
+/// Thrown minion in the air, moved along its arc without walking or colliding
+#[derive(Component)]
+pub struct MinionFlight {
+    arc: ThrowArc,
+    elapsed: f32,
+}
+
+impl MinionFlight {
+    pub fn new(arc: ThrowArc) -> Self {
+        Self { arc, elapsed: 0.0 }
+    }
+}
+
+/// First ground, wall or target hit on the way from `from` to `to`. Walk target flags are
+/// flown through.
+fn cast_segment(
+    rapier: &RapierContext,
+    walk_targets: &Query<(), With<WalkTargetTag>>,
+    from: Vec3,
+    to: Vec3,
+) -> Option<(Entity, RayIntersection)> {
+    let segment = to - from;
+    let length = segment.length();
+    if length < 1e-5 {
+        return None;
+    }
+    let predicate = |ent: Entity| !walk_targets.contains(ent);
+    rapier.cast_ray_and_get_normal(
+        from,
+        segment / length,
+        length,
+        true,
+        QueryFilter {
+            groups: Some(CollisionGroups::new(
+                Group::all(),
+                GROUND_GROUP | WALL_GROUP | TARGET_GROUP,
+            )),
+            predicate: Some(&predicate),
+            ..default()
+        },
+    )
+}
+
+/// Moves thrown minions along their arc. They land on the ground, ledges and targets they
+/// hit from above, and drop down along walls they fly into.
+pub fn minion_flight(
+    time: Res<Time>,
+    rapier: Res<RapierContext>,
+    walk_targets: Query<(), With<WalkTargetTag>>,
+    mut minion_q: Query<(
+        Entity,
+        &mut Transform,
+        &mut KinematicCharacterController,
+        &mut CharacterWalkControl,
+        &mut CharacterWalkState,
+        &mut MinionFlight,
+    )>,
+    mut commands: Commands,
+) {
+    for (ent, mut tx, mut controller, mut walk, mut state, mut flight) in minion_q.iter_mut() {
+        flight.elapsed += time.delta_seconds();
+        let from = tx.translation;
+        let to = flight.arc.position(flight.elapsed);
+
+        // Nothing else moves them meanwhile
+        controller.translation = None;
+        *state = CharacterWalkState::default();
+        walk.do_move = false;
+
+        let landing = match cast_segment(&rapier, &walk_targets, from, to) {
+            Some((_, hit)) if hit.normal.y >= MINION_LANDING_NORMAL_Y => Some(hit.point),
+            Some((_, hit)) => {
+                // Bumped into a wall, falls down along it
+                let away = hit.normal.with_y(0.0).normalize_or_zero();
+                let fall = flight.arc.velocity(flight.elapsed).y.min(0.0);
+                *flight = MinionFlight::new(ThrowArc {
+                    origin: hit.point + away * minion_builder::COLLIDER_HALF_HEIGHT,
+                    velocity: Vec3::Y * fall,
+                    duration: 0.0,
+                });
+                tx.translation = flight.arc.origin;
+                None
+            }
+            None if flight.elapsed > flight.arc.duration + MINION_FLIGHT_TIMEOUT => {
+                warn!("Thrown minion never landed: {to}");
+                Some(to)
+            }
+            None => {
+                tx.translation = to;
+                None
+            }
+        };
+        if let Some(point) = landing {
+            tx.translation = point + Vec3::Y * minion_builder::COLLIDER_HALF_HEIGHT;
+            commands
+                .entity(ent)
+                .remove::<(MinionFlight, ColliderDisabled)>();
+        }
+    }
+}
+
+/// Draws the arc the aimed minion would fly along, up to where it would hit something
+pub fn draw_throw_preview(
+    gizmos: &mut Gizmos,
+    rapier: &RapierContext,
+    walk_targets: &Query<(), With<WalkTargetTag>>,
+    arc: &ThrowArc,
+) {
+    let step = (arc.duration + MINION_FLIGHT_TIMEOUT) / PREVIEW_POINTS as f32;
+    let mut points = vec![arc.origin];
+    for i in 1..=PREVIEW_POINTS {
+        let from = points[points.len() - 1];
+        let to = arc.position(i as f32 * step);
+        if let Some((_, hit)) = cast_segment(rapier, walk_targets, from, to) {
+            points.push(hit.point);
+            if let Ok(normal) = Dir3::new(hit.normal) {
+                gizmos.circle(
+                    hit.point + hit.normal * 0.05,
+                    normal,
+                    0.3,
+                    tailwind::AMBER_300,
+                );
+            }
+            break;
+        }
+        points.push(to);
+    }
+    gizmos.linestrip(points, tailwind::AMBER_200);
+}
+
+#[test]
+fn test_throw_arc() {
+    // Up onto a ledge
+    let from = Vec3::new(1.0, 0.5, 2.0);
+    let to = Vec3::new(4.0, 2.0, -1.0);
+    let arc = ThrowArc::new(from, to);
+    assert!(arc.position(0.0).abs_diff_eq(from, 1e-5));
+    assert!(arc.position(arc.duration).abs_diff_eq(to, 1e-3));
+
+    // Peaks above the higher end, where it stops rising
+    let peak = arc.velocity.y / -GRAVITY;
+    assert!(peak > 0.0 && peak < arc.duration);
+    assert!(arc.velocity(peak).y.abs() < 1e-3);
+    assert!((arc.position(peak).y - (to.y + MINION_THROW_APEX)).abs() < 1e-3);
+
+    // Cut short at the throw range, on the height it was thrown from without a tilemap
+    let far = from + Vec3::new(30.0, 4.0, 40.0);
+    let (arc, landing) = ThrowArc::aim(from, far, None);
+    let expected = from + Vec3::new(3.0, 0.0, 4.0) / 5.0 * MINION_THROW_RANGE;
+    assert!(landing.abs_diff_eq(expected, 1e-3));
+    let end = landing + Vec3::Y * minion_builder::COLLIDER_HALF_HEIGHT;
+    assert!(arc.position(arc.duration).abs_diff_eq(end, 1e-3));
+
+    // Within range the aimed point is kept
+    let near = from + Vec3::new(2.0, 0.0, 1.0);
+    assert_eq!(ThrowArc::aim(from, near, None).1, near);
+}
@@ -5,15 +5,26 @@ use crate::{
         level_asset::{LevelAsset, LevelAssetLoader},
         loading_queue::{self, AssetLoadingCompleted, AssetLoadingQueue, WatchAssetLoading},
         logical_cursor::LogicalCursorPlugin,
+        navmesh::{NavAgent, NavElevation, NavLink, NavMeshLayout},
+        tilemap::Tilemap,
     },
     game::{
         game_cursor::GameCursorPlugin,
-        kinematic_char::{CharacterWalkControl, CharacterWalkState},
+        kinematic_char::{BlockingTileFlags, CharacterWalkControl, CharacterWalkState},
+        level::{NavMeshChanged, NavObstacle},
         minion::{
+            ability::{Ability, HeavyObject},
             collector::{MinionInteractionRequirement, MinionStorage},
+            formation::Formation,
+            health::{Explosion, MinionDamage, MinionDied, MinionHealth, MinionLossStats},
+            steering::MinionSteering,
+            stuck::{MinionLost, MinionProgress},
             MinionKind, MinionStartedInteraction, MinionState, MinionTarget,
         },
-        objects::{camera::CameraObjPlugin, cauldron},
+        objects::{
+            camera::CameraObjPlugin, cauldron, door::Door, explosive_barrel::ExplosiveBarrel,
+            laser_grid::LaserGrid, power_outlet::PowerOutlet,
+        },
         player::{
             minion_storage::{MinionStorageInput, MinionThrowTarget, PlayerCollector},
             player_builder::{self},
@@ -23,7 +34,7 @@ use crate::{
     },
     AppState,
 };
-use bevy::{prelude::*, window::CursorGrabMode};
+use bevy::{prelude::*, utils::HashMap, window::CursorGrabMode};
 use bevy_rapier3d::prelude::*;
 use vleue_navigator::{NavMesh, VleueNavigatorPlugin};
 
@@ -40,10 +51,29 @@ pub mod top_down_camera;
 
 #[derive(Debug, Default, Resource)]
 pub struct LevelResources {
-    pub navmesh: Option<Handle<NavMesh>>,
+    /// One navmesh per agent size
+    pub navmeshes: HashMap<NavAgent, Handle<NavMesh>>,
+    /// Heights of the vertices of the navmeshes
+    pub nav_elevations: HashMap<NavAgent, NavElevation>,
+    /// Walkable faces of the navmeshes, updated as obstacles come and go
+    pub nav_layouts: HashMap<NavAgent, NavMeshLayout>,
+    /// Drops, vents and holes between parts of the navmeshes
+    pub nav_links: Vec<NavLink>,
+    /// For looking up tile flags under characters
+    pub tilemap: Option<Tilemap>,
     pub spawnpoints: Option<Vec<(Vec3, u32, bool)>>,
 }
 
+impl LevelResources {
+    pub fn navmesh(&self, agent: NavAgent) -> Option<&Handle<NavMesh>> {
+        self.navmeshes.get(&agent)
+    }
+
+    pub fn nav_elevation(&self, agent: NavAgent) -> Option<&NavElevation> {
+        self.nav_elevations.get(&agent)
+    }
+}
+
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
@@ -78,19 +108,42 @@ impl Plugin for GamePlugin {
         .register_type::<CharacterWalkControl>()
         .register_type::<PlayerCollector>()
         .register_type::<CharacterWalkState>()
+        .register_type::<BlockingTileFlags>()
+        .register_type::<NavObstacle>()
+        .register_type::<NavAgent>()
         .register_type::<MinionKind>()
         .register_type::<MinionStorage>()
         .register_type::<MinionState>()
         .register_type::<MinionTarget>()
         .register_type::<MinionThrowTarget>()
         .register_type::<MinionInteractionRequirement>()
+        .register_type::<Ability>()
+        .register_type::<HeavyObject>()
+        .register_type::<PowerOutlet>()
+        .register_type::<Door>()
+        .register_type::<ExplosiveBarrel>()
+        .register_type::<MinionSteering>()
+        .register_type::<MinionProgress>()
+        .register_type::<MinionHealth>()
+        .register_type::<MinionLossStats>()
+        .register_type::<LaserGrid>()
+        .init_resource::<MinionLossStats>()
+        .init_resource::<MinionSteering>()
         .insert_resource(MinionStorageInput {
             chosen_ty: MinionKind::Void,
+            aiming: false,
             want_to_throw: false,
             to_where: MinionThrowTarget::Location(Vec3::ZERO),
             do_pickup: false,
+            command: None,
+            formation: Formation::default(),
         })
         .add_event::<MinionStartedInteraction>()
+        .add_event::<MinionLost>()
+        .add_event::<MinionDamage>()
+        .add_event::<MinionDied>()
+        .add_event::<Explosion>()
+        .add_event::<NavMeshChanged>()
         .add_event::<AddPlayerRespawnEvent>()
         .add_systems(
             OnEnter(AppState::Ingame),
@@ -117,10 +170,16 @@ impl Plugin for GamePlugin {
             FixedUpdate,
             (
                 minion::minion_walk,
+                minion::steering::minion_steering.after(minion::minion_walk),
                 // sneak in before the kinematic char, because the walk information gets erased
-                minion::update_animation.after(minion::minion_walk),
+                minion::update_animation.after(minion::steering::minion_steering),
                 player_builder::update_animation.after(minion::update_animation),
                 kinematic_char::update_kinematic_character.after(player_builder::update_animation),
+                kinematic_char::apply_tile_flags.after(kinematic_char::update_kinematic_character),
+                minion::minion_traverse.after(kinematic_char::apply_tile_flags),
+                minion::ability::push_heavy_objects,
+                minion::stuck::detect_stuck_minions.after(minion::minion_walk),
+                minion::throw::minion_flight.after(kinematic_char::apply_tile_flags),
             )
                 .run_if(in_state(AppState::Ingame)),
         )
@@ -129,25 +188,55 @@ impl Plugin for GamePlugin {
             (
                 audio::start_bgm_delayed,
                 minion::cleanup_minion_state,
+                player::minion_storage::minion_storage_command,
+                minion::formation::update_formation_slots
+                    .after(player::minion_storage::minion_storage_command),
                 minion::update_minion_state,
                 minion::minion_update_path.after(minion::update_minion_state),
                 minion::walk_target::walk_target_update.after(minion::update_minion_state),
                 minion::collector::update_minion_interaction_requirements
                     .after(minion::update_minion_state),
                 minion::update_chosen_minion_debug_ui,
+                minion::minion_hazard,
                 player::minion_storage::minion_storage_throw,
+                player::minion_storage::minion_throw_preview,
                 player::minion_storage::minion_storage_pickup,
                 player::add_player_respawn,
                 player::process_player_respawning.after(player::add_player_respawn),
                 objects::destructible_target_test::update_destructble_target,
+                objects::power_outlet::update_power_outlets
+                    .after(minion::collector::update_minion_interaction_requirements),
+                objects::door::update_doors
+                    .after(minion::collector::update_minion_interaction_requirements),
                 cauldron::process_cauldron_queue,
                 cauldron::queue_minion_for_cauldron,
             )
                 .run_if(in_state(AppState::Ingame)),
         )
+        .add_systems(
+            Update,
+            (
+                objects::laser_grid::laser_grid_damage,
+                objects::explosive_barrel::update_explosive_barrels
+                    .after(minion::collector::update_minion_interaction_requirements),
+                minion::health::explosion_damage
+                    .after(objects::explosive_barrel::update_explosive_barrels),
+                minion::health::apply_minion_damage
+                    .after(minion::minion_hazard)
+                    .after(objects::laser_grid::laser_grid_damage)
+                    .after(minion::health::explosion_damage),
+                minion::health::count_lost_minions.after(minion::health::apply_minion_damage),
+                minion::health::minion_dying,
+            )
+                .run_if(in_state(AppState::Ingame)),
+        )
         .add_systems(
             PostUpdate,
-            (minion::minion_build_path.after(TransformSystem::TransformPropagate))
+            (
+                level::update_navmesh_obstacles.after(TransformSystem::TransformPropagate),
+                minion::minion_repath.after(level::update_navmesh_obstacles),
+                minion::minion_build_path.after(minion::minion_repath),
+            )
                 .run_if(in_state(AppState::Ingame)),
         );
 
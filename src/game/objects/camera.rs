@@ -8,6 +8,8 @@ use crate::{
         collision_groups::{GROUND_GROUP, WALL_GROUP},
         common::{RootParent, ShowForwardGizmo},
         minion::{
+            ability::Ability,
+            health::MinionDying,
             minion_builder::{MinionAssets, MinionBuilder},
             MinionKind, MinionState,
         },
@@ -158,6 +160,7 @@ const fn spotlight_color(color: ColorDef) -> Srgba {
     }
 }
 
+/// Seen by camera spotlights, unless it's a stealthy minion
 #[derive(Component, Reflect)]
 pub struct Shineable;
 
@@ -176,7 +179,7 @@ fn update_shined_entities(
     mut state: Query<&mut ShinedEntityList>,
     // mut reader: EventReader<CollisionEvent>,
     cone: Query<(&ShineCone, &RootParent, &Transform, &GlobalTransform), Without<Shineable>>,
-    shineable: Query<(Entity, &GlobalTransform), With<Shineable>>,
+    shineable: Query<(Entity, &GlobalTransform, Option<&MinionKind>), With<Shineable>>,
 ) {
     for (cone, root, tx, gx) in cone.iter() {
         let Ok(mut state) = state.get_mut(root.parent()) else {
@@ -186,7 +189,10 @@ fn update_shined_entities(
             Transform::from_matrix(gx.compute_matrix() * tx.compute_matrix().inverse()).translation;
 
         state.entities.clear();
-        for (shineable, sgx) in shineable.iter() {
+        for (shineable, sgx, kind) in shineable.iter() {
+            if kind.is_some_and(|kind| kind.has_ability(Ability::Stealthy)) {
+                continue;
+            }
             let destination = sgx.translation();
             let direction = (destination - origin).normalize();
             let distance = origin.distance(destination);
@@ -447,7 +453,7 @@ fn spotlight_hit_player(
 fn spotlight_hit_minion(
     mut cmd: Commands,
     mut hit: EventReader<SpotlightHitEvent>,
-    minion: Query<(Entity, &MinionKind, &GlobalTransform, &MinionState)>,
+    minion: Query<(Entity, &MinionKind, &GlobalTransform, &MinionState), Without<MinionDying>>,
     mut audio: ResMut<Audio>,
     sfx: Res<AudioAssets>,
     assets: Res<MinionAssets>,
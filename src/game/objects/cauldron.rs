@@ -1,5 +1,6 @@
 use crate::game::{
     collision_groups::{ACTOR_GROUP, GROUND_GROUP, TARGET_GROUP},
+    level::NavObstacle,
     minion::{
         minion_builder::{MinionAssets, MinionBuilder},
         MinionKind, MinionPath, MinionStartedInteraction, MinionState, MinionTarget,
@@ -31,6 +32,7 @@ impl CauldronBuilder<'_> {
             self.0.color,
             Collider::cylinder(1.0, 1.5),
             CollisionGroups::new(TARGET_GROUP | GROUND_GROUP, GROUND_GROUP | ACTOR_GROUP),
+            NavObstacle::new(Vec2::splat(1.4)),
         );
         let base = (
             SpatialBundle::default(),
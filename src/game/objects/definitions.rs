@@ -2,7 +2,7 @@ use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::ops::{Add, Sub};
 
-use crate::game::minion::MinionKind;
+use crate::{framework::navmesh::NavLink, game::minion::MinionKind};
 
 macro_rules! object_enum { // pfft, strum..
     (
@@ -51,7 +51,7 @@ pub struct ObjectDef {
 object_enum! {
     #[derive(Debug, Clone, Copy, Reflect, Serialize, Deserialize, PartialEq, Eq)]
     #[rustfmt::skip]
-    pub enum ObjectDefKind (u32, 6) {
+    pub enum ObjectDefKind (u32, 14) {
         SpawnPoint         = 0x0001 : "Spawn Point",
         Cauldron           = 0x0101 : "Tinting Cauldron",
         Camera             = 0x0102 : "Camera",
@@ -59,15 +59,9 @@ object_enum! {
         // ControlPanel       = 0x0104 : "Control Panel",
         // PressurePlate      = 0x0105 : "Pressure Plate",
         // Key                = 0x0106 : "Key",
-        // KeyDoor            = 0x0107 : "Locked Door",
-        // Barrier            = 0x0108 : "Locked Barrier",
-        // PowerOutlet        = 0x0109 : "Power Outlet",
         // EmptySocket        = 0x010A : "Empty Socket",
-        // ExplosiveBarrel    = 0x010B : "Explosive Barrel",
         // Anglerfish         = 0x010C : "Anglerfish",
-        // Ventilation        = 0x010D : "Ventilation",
         // Well               = 0x0201  : "Well",
-        // BigHole            = 0x0202  : "Big Hole",
         // LaserDrill         = 0x0203  : "Laser Drill",
         // VaultDoor          = 0x0204  : "Vault Door",
         // Painting           = 0x0205  : "Pretentious Painting",
@@ -79,6 +73,44 @@ object_enum! {
         // WineBottle         = 0x020B  : "Wine Bottle",
         DestructibleTargetTest = 0xFF01 : "Destructible Target Test",
         PhysicsCubesTest       = 0xFF02 : "Physics Cubes Test",
+        // Level files store the position of a kind in this list, new ones go last
+        LedgeDrop          = 0x010E : "Ledge Drop",
+        Ventilation        = 0x010D : "Ventilation",
+        BigHole            = 0x0202 : "Big Hole",
+        PowerOutlet        = 0x0109 : "Power Outlet",
+        KeyDoor            = 0x0107 : "Locked Door",
+        Barrier            = 0x0108 : "Locked Barrier",
+        HeavyCrate         = 0x010F : "Heavy Crate",
+        ExplosiveBarrel    = 0x010B : "Explosive Barrel",
+    }
+}
+
+impl ObjectDefKind {
+    /// Kinds connecting parts of the level minions can't walk between
+    pub fn is_nav_link(self) -> bool {
+        matches!(
+            self,
+            ObjectDefKind::LedgeDrop | ObjectDefKind::Ventilation | ObjectDefKind::BigHole
+        )
+    }
+}
+
+impl ObjectDef {
+    /// The way between the first two `pos_refs` of drops, vents and holes minions can take
+    pub fn nav_link(&self) -> Option<NavLink> {
+        let two_way = match self.kind {
+            ObjectDefKind::Ventilation => true,
+            ObjectDefKind::LedgeDrop | ObjectDefKind::BigHole => false,
+            _ => return None,
+        };
+        let [start, end, ..] = self.pos_refs[..] else {
+            return None;
+        };
+        Some(NavLink {
+            start,
+            end,
+            two_way,
+        })
     }
 }
 
@@ -185,35 +217,5 @@ impl From<ColorDef> for MinionKind {
     }
 }
 
-// #[derive(Clone, Copy, PartialEq, Eq)]
-// #[repr(u8)]
-// pub enum Ability {
-//     Strong,
-//     Agile,
-//     Stealthy,
-//     Conductive,
-//     Heavy,
-//     Fleeting,
-// }
-
-// pub struct Minion {
-//     color: Color,
-// }
-
-// impl Minion {
-//     #[rustfmt::skip]
-//     pub fn has_ability(&self, ability: Ability) -> bool {
-//         let col = self.color;
-//         match ability {
-//             Ability::Strong     => col == Color::White || col == Color::Red    || col == Color::Magenta,
-//             Ability::Agile      => col == Color::White || col == Color::Green  || col == Color::Yellow,
-//             Ability::Stealthy   => col == Color::White || col == Color::Blue   || col == Color::Cyan,
-//             Ability::Conductive => col == Color::White || col == Color::Yellow,
-//             Ability::Heavy      => col == Color::White || col == Color::Magenta,
-//             Ability::Fleeting   => col == Color::White || col == Color::Cyan,
-//         }
-//     }
-// }
-
 #[derive(Debug, Clone, Copy, Reflect, Serialize, Deserialize, PartialEq, Eq)]
 pub enum Tag {}
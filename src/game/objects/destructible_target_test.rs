@@ -2,10 +2,11 @@
 
 use crate::game::{
     collision_groups::{ACTOR_GROUP, GROUND_GROUP, TARGET_GROUP},
-    minion::{collector::MinionInteractionRequirement, MinionKind, MinionTarget},
+    level::NavObstacle,
+    minion::{collector::MinionInteractionRequirement, MinionTarget},
     objects::{assets::GameObjectAssets, definitions::ObjectDef},
 };
-use bevy::{prelude::*, utils::HashMap};
+use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
 
 #[derive(Clone, Copy, Component, Debug, Reflect, Default)]
@@ -24,17 +25,13 @@ impl DestructibleTargetTestBuilder<'_> {
     pub fn build(self, cmd: &mut Commands, _assets: &GameObjectAssets) -> Entity {
         cmd.spawn((
             DestructibleTargetBundle {
-                requirement: {
-                    let mut map = HashMap::new();
-                    map.insert(MinionKind::Void, 2);
-
-                    MinionInteractionRequirement::new(map)
-                },
+                requirement: MinionInteractionRequirement::for_object(self.0).unwrap_or_default(),
                 ..default()
             },
             TransformBundle::from(Transform::from_translation(self.0.position)),
             Collider::cuboid(1.0, 1.0, 1.0),
             CollisionGroups::new(TARGET_GROUP | GROUND_GROUP, GROUND_GROUP | ACTOR_GROUP),
+            NavObstacle::new(Vec2::splat(2.0)),
         ))
         .id()
     }
@@ -0,0 +1,79 @@
+//! Doors minions of their color hold open, and barriers strong minions push away for good.
+//! Both are cut out of the navmesh while closed.
+
+use crate::game::{
+    collision_groups::{ACTOR_GROUP, TARGET_GROUP, WALL_GROUP},
+    level::NavObstacle,
+    minion::{collector::MinionInteractionRequirement, MinionTarget},
+    objects::{
+        assets::GameObjectAssets,
+        definitions::{ObjectDef, ObjectDefKind},
+    },
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+#[derive(Clone, Copy, Component, Debug, Reflect, Default)]
+#[reflect(Component)]
+pub struct Door {
+    pub open: bool,
+    /// Stays open once it was opened, for barriers
+    pub latch: bool,
+}
+
+pub struct DoorBuilder<'a>(pub &'a ObjectDef);
+
+impl DoorBuilder<'_> {
+    pub fn build(self, cmd: &mut Commands, assets: &GameObjectAssets) -> Entity {
+        cmd.spawn((
+            Door {
+                open: false,
+                latch: self.0.kind == ObjectDefKind::Barrier,
+            },
+            MinionTarget,
+            MinionInteractionRequirement::for_object(self.0).unwrap_or_default(),
+            PbrBundle {
+                mesh: assets.dummy_cube_mesh.clone(),
+                material: assets.dummy_cube_material(self.0.color),
+                transform: Transform::IDENTITY
+                    .with_translation(self.0.position + Vec3::Y * 0.5)
+                    .with_rotation(Quat::from_rotation_y(self.0.rotation))
+                    .with_scale(Vec3::new(1.0, 1.0, 0.2)),
+                ..Default::default()
+            },
+            Collider::cuboid(0.5, 0.5, 0.5),
+            CollisionGroups::new(WALL_GROUP | TARGET_GROUP, ACTOR_GROUP),
+            NavObstacle::new(Vec2::ONE),
+        ))
+        .id()
+    }
+}
+
+/// Opens doors while their requirement is met and barriers once it was. Open ones are
+/// hidden and let everyone walk through.
+pub fn update_doors(
+    mut cmd: Commands,
+    mut door_q: Query<(
+        Entity,
+        &MinionInteractionRequirement,
+        &mut Door,
+        &mut NavObstacle,
+        &mut Visibility,
+    )>,
+) {
+    for (ent, req, mut door, mut obstacle, mut visibility) in door_q.iter_mut() {
+        let open = req.is_satisfied || (door.latch && door.open);
+        if open == door.open {
+            continue;
+        }
+        door.open = open;
+        obstacle.blocking = !open;
+        if open {
+            *visibility = Visibility::Hidden;
+            cmd.entity(ent).insert(ColliderDisabled);
+        } else {
+            *visibility = Visibility::Inherited;
+            cmd.entity(ent).remove::<ColliderDisabled>();
+        }
+    }
+}
@@ -0,0 +1,67 @@
+//! Barrels that blow up once enough minions of their color light them, clearing the way
+//! and hurting the minions around them
+
+use crate::game::{
+    collision_groups::{ACTOR_GROUP, GROUND_GROUP, TARGET_GROUP},
+    level::NavObstacle,
+    minion::{collector::MinionInteractionRequirement, health::Explosion, MinionTarget},
+    objects::{assets::GameObjectAssets, definitions::ObjectDef},
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+/// How far from the barrel minions get hurt
+pub const BARREL_EXPLOSION_RADIUS: f32 = 2.5;
+/// Damage at the center of the explosion
+pub const BARREL_EXPLOSION_DAMAGE: f32 = 2.0;
+
+#[derive(Clone, Copy, Component, Debug, Reflect, Default)]
+#[reflect(Component)]
+pub struct ExplosiveBarrel;
+
+pub struct ExplosiveBarrelBuilder<'a>(pub &'a ObjectDef);
+
+impl ExplosiveBarrelBuilder<'_> {
+    pub fn build(self, cmd: &mut Commands, assets: &GameObjectAssets) -> Entity {
+        cmd.spawn((
+            ExplosiveBarrel,
+            MinionTarget,
+            MinionInteractionRequirement::for_object(self.0).unwrap_or_default(),
+            PbrBundle {
+                mesh: assets.dummy_cube_mesh.clone(),
+                material: assets.dummy_cube_material(self.0.color),
+                transform: Transform::IDENTITY
+                    .with_translation(self.0.position + Vec3::Y * 0.4)
+                    .with_rotation(Quat::from_rotation_y(self.0.rotation))
+                    .with_scale(Vec3::splat(0.8)),
+                ..Default::default()
+            },
+            Collider::cuboid(0.5, 0.5, 0.5),
+            CollisionGroups::new(TARGET_GROUP | GROUND_GROUP, GROUND_GROUP | ACTOR_GROUP),
+            NavObstacle::new(Vec2::splat(0.8)),
+        ))
+        .id()
+    }
+}
+
+/// Blows up barrels once their requirement is met
+pub fn update_explosive_barrels(
+    mut cmd: Commands,
+    barrel_q: Query<
+        (Entity, &GlobalTransform, &MinionInteractionRequirement),
+        With<ExplosiveBarrel>,
+    >,
+    mut explosions: EventWriter<Explosion>,
+) {
+    for (ent, gx, req) in barrel_q.iter() {
+        if !req.is_satisfied {
+            continue;
+        }
+        explosions.send(Explosion {
+            position: gx.translation(),
+            radius: BARREL_EXPLOSION_RADIUS,
+            damage: BARREL_EXPLOSION_DAMAGE,
+        });
+        cmd.entity(ent).despawn_recursive();
+    }
+}
@@ -0,0 +1,31 @@
+//! Crates too heavy for anyone but strong minions to push out of the way
+
+use crate::game::{
+    minion::ability::{HeavyObject, HEAVY_OBJECT_DENSITY},
+    objects::{assets::GameObjectAssets, definitions::ObjectDef},
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+pub struct HeavyCrateBuilder<'a>(pub &'a ObjectDef);
+
+impl HeavyCrateBuilder<'_> {
+    pub fn build(self, cmd: &mut Commands, assets: &GameObjectAssets) -> Entity {
+        cmd.spawn((
+            HeavyObject,
+            PbrBundle {
+                mesh: assets.dummy_cube_mesh.clone(),
+                material: assets.dummy_cube_material(self.0.color),
+                transform: Transform::IDENTITY
+                    .with_translation(self.0.position + Vec3::Y * 0.5)
+                    .with_rotation(Quat::from_rotation_y(self.0.rotation)),
+                ..Default::default()
+            },
+            RigidBody::Dynamic,
+            Collider::cuboid(0.5, 0.5, 0.5),
+            ColliderMassProperties::Density(HEAVY_OBJECT_DENSITY),
+            ExternalImpulse::default(),
+        ))
+        .id()
+    }
+}
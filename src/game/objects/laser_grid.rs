@@ -0,0 +1,69 @@
+//! Laser grids burn the minions walking through them, thrown ones can fly over them
+
+use crate::game::{
+    level::NavObstacle,
+    minion::health::{DamageCause, MinionDamage, MinionHealth},
+    objects::{assets::GameObjectAssets, definitions::ObjectDef},
+};
+use bevy::prelude::*;
+
+/// Damage per second minions take inside a laser grid
+pub const LASER_GRID_DAMAGE_PER_SEC: f32 = 20.0;
+/// Half the size of the space a laser grid hurts minions in, across one tile
+pub const LASER_GRID_HALF_EXTENTS: Vec3 = Vec3::new(0.5, 0.5, 0.3);
+/// Ground cut out of the navmesh, minions only get through by being thrown
+pub const LASER_GRID_FOOTPRINT: Vec2 = Vec2::ONE;
+
+#[derive(Clone, Copy, Component, Debug, Reflect, Default)]
+#[reflect(Component)]
+pub struct LaserGrid;
+
+pub struct LaserGridBuilder<'a>(pub &'a ObjectDef);
+
+impl LaserGridBuilder<'_> {
+    pub fn build(self, cmd: &mut Commands, assets: &GameObjectAssets) -> Entity {
+        let root = (
+            LaserGrid,
+            SpatialBundle {
+                transform: Transform::IDENTITY
+                    .with_translation(self.0.position + Vec3::Y * LASER_GRID_HALF_EXTENTS.y)
+                    .with_rotation(Quat::from_rotation_y(self.0.rotation)),
+                ..Default::default()
+            },
+            NavObstacle::new(LASER_GRID_FOOTPRINT),
+        );
+        let beams = PbrBundle {
+            mesh: assets.dummy_cube_mesh.clone(),
+            material: assets.dummy_cube_material(self.0.color),
+            transform: Transform::from_scale(Vec3::new(1.0, 1.0, 0.05)),
+            ..Default::default()
+        };
+        cmd.spawn(root)
+            .with_children(|cmd| {
+                cmd.spawn(beams);
+            })
+            .id()
+    }
+}
+
+pub fn laser_grid_damage(
+    time: Res<Time>,
+    grid_q: Query<&GlobalTransform, With<LaserGrid>>,
+    minion_q: Query<(Entity, &GlobalTransform), With<MinionHealth>>,
+    mut damage: EventWriter<MinionDamage>,
+) {
+    for grid_gx in grid_q.iter() {
+        let (_, rotation, translation) = grid_gx.to_scale_rotation_translation();
+        for (minion, gx) in minion_q.iter() {
+            let local = rotation.inverse() * (gx.translation() - translation);
+            if !local.abs().cmple(LASER_GRID_HALF_EXTENTS).all() {
+                continue;
+            }
+            damage.send(MinionDamage {
+                minion,
+                amount: LASER_GRID_DAMAGE_PER_SEC * time.delta_seconds(),
+                cause: DamageCause::LaserGrid,
+            });
+        }
+    }
+}
@@ -4,7 +4,12 @@ use crate::game::objects::{
     cauldron::CauldronBuilder,
     definitions::{ObjectDef, ObjectDefKind},
     destructible_target_test::DestructibleTargetTestBuilder,
+    door::DoorBuilder,
+    explosive_barrel::ExplosiveBarrelBuilder,
+    heavy_crate::HeavyCrateBuilder,
+    laser_grid::LaserGridBuilder,
     physics_cubes_test::PhysicsCubeTestBuilder,
+    power_outlet::PowerOutletBuilder,
 };
 use bevy::prelude::*;
 
@@ -13,7 +18,12 @@ pub mod camera;
 pub mod cauldron;
 pub mod definitions;
 pub mod destructible_target_test;
+pub mod door;
+pub mod explosive_barrel;
+pub mod heavy_crate;
+pub mod laser_grid;
 pub mod physics_cubes_test;
+pub mod power_outlet;
 
 pub fn spawn_object(
     mut cmd: &mut Commands,
@@ -29,6 +39,10 @@ pub fn spawn_object(
             let builder = CameraObjBuilder(object);
             builder.build(&mut cmd, &assets)
         }
+        ObjectDefKind::LaserGrid => {
+            let builder = LaserGridBuilder(object);
+            builder.build(&mut cmd, &assets)
+        }
         ObjectDefKind::DestructibleTargetTest => {
             let builder = DestructibleTargetTestBuilder(object);
             builder.build(&mut cmd, &assets)
@@ -37,7 +51,27 @@ pub fn spawn_object(
             let builder = PhysicsCubeTestBuilder(object);
             builder.build(&mut cmd, &assets)
         }
-        ObjectDefKind::SpawnPoint => cmd
+        ObjectDefKind::PowerOutlet => {
+            let builder = PowerOutletBuilder(object);
+            builder.build(&mut cmd, &assets)
+        }
+        ObjectDefKind::KeyDoor | ObjectDefKind::Barrier => {
+            let builder = DoorBuilder(object);
+            builder.build(&mut cmd, &assets)
+        }
+        ObjectDefKind::HeavyCrate => {
+            let builder = HeavyCrateBuilder(object);
+            builder.build(&mut cmd, &assets)
+        }
+        ObjectDefKind::ExplosiveBarrel => {
+            let builder = ExplosiveBarrelBuilder(object);
+            builder.build(&mut cmd, &assets)
+        }
+        // Markers, links are taken from the level data
+        ObjectDefKind::SpawnPoint
+        | ObjectDefKind::LedgeDrop
+        | ObjectDefKind::Ventilation
+        | ObjectDefKind::BigHole => cmd
             .spawn(SpatialBundle {
                 transform: Transform::IDENTITY
                     .with_translation(object.position)
@@ -0,0 +1,66 @@
+//! Outlets that are powered while enough conductive minions stand at them
+
+use crate::game::{
+    collision_groups::{ACTOR_GROUP, GROUND_GROUP, TARGET_GROUP},
+    level::NavObstacle,
+    minion::{collector::MinionInteractionRequirement, MinionTarget},
+    objects::{
+        assets::GameObjectAssets,
+        definitions::{ColorDef, ObjectDef},
+    },
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+#[derive(Clone, Copy, Component, Debug, Reflect, Default)]
+#[reflect(Component)]
+pub struct PowerOutlet {
+    pub powered: bool,
+}
+
+pub struct PowerOutletBuilder<'a>(pub &'a ObjectDef);
+
+impl PowerOutletBuilder<'_> {
+    pub fn build(self, cmd: &mut Commands, assets: &GameObjectAssets) -> Entity {
+        cmd.spawn((
+            PowerOutlet::default(),
+            MinionTarget,
+            MinionInteractionRequirement::for_object(self.0).unwrap_or_default(),
+            PbrBundle {
+                mesh: assets.dummy_cube_mesh.clone(),
+                material: assets.dummy_cube_material(ColorDef::Void),
+                transform: Transform::IDENTITY
+                    .with_translation(self.0.position + Vec3::Y * 0.5)
+                    .with_rotation(Quat::from_rotation_y(self.0.rotation))
+                    .with_scale(Vec3::splat(0.4)),
+                ..Default::default()
+            },
+            Collider::cuboid(0.5, 0.5, 0.5),
+            CollisionGroups::new(TARGET_GROUP | GROUND_GROUP, GROUND_GROUP | ACTOR_GROUP),
+            NavObstacle::new(Vec2::splat(0.4)),
+        ))
+        .id()
+    }
+}
+
+/// Powers outlets while their requirement is met and lights them up in the color of
+/// conductive minions
+pub fn update_power_outlets(
+    assets: Res<GameObjectAssets>,
+    mut outlet_q: Query<(
+        &MinionInteractionRequirement,
+        &mut PowerOutlet,
+        &mut Handle<StandardMaterial>,
+    )>,
+) {
+    for (req, mut outlet, mut material) in outlet_q.iter_mut() {
+        if outlet.powered == req.is_satisfied {
+            continue;
+        }
+        outlet.powered = req.is_satisfied;
+        *material = assets.dummy_cube_material(match outlet.powered {
+            true => ColorDef::Yellow,
+            false => ColorDef::Void,
+        });
+    }
+}
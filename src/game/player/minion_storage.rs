@@ -1,17 +1,22 @@
 use std::f32::consts::PI;
 
 use crate::{
-    framework::audio::{Audio, AudioChannel, Volume},
+    framework::{
+        audio::{Audio, AudioChannel, Volume},
+        tilemap::Tilemap,
+    },
     game::{
         audio::AudioAssets,
-        game_cursor::GameCursor,
         minion::{
             collector::MinionStorage,
+            formation::Formation,
+            health::MinionDying,
             minion_builder::{MinionAssets, MinionBuilder},
-            walk_target::WalkTargetBuilder,
+            throw::{self, MinionFlight, ThrowArc},
+            walk_target::{WalkTargetBuilder, WalkTargetTag},
         },
         objects::assets::GameObjectAssets,
-        CharacterWalkControl, MinionKind, MinionState,
+        CharacterWalkControl, LevelResources, MinionKind, MinionState,
     },
 };
 use bevy::prelude::*;
@@ -28,21 +33,57 @@ pub enum MinionThrowTarget {
 #[derive(Clone, Copy, Debug, Component, Reflect)]
 pub struct PlayerCollector;
 
+/// Orders for all minions of the chosen kind
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+pub enum MinionCommand {
+    /// Back into the storage
+    RecallAll,
+    /// Wait where they stand
+    Hold,
+    /// Follow the player in formation, switches to the next one when they already do
+    Follow,
+}
+
 #[derive(Clone, Copy, Debug, Resource, Reflect)]
 pub struct MinionStorageInput {
     pub chosen_ty: MinionKind,
+    /// Held before throwing, shows where the minion would fly
+    pub aiming: bool,
     pub want_to_throw: bool,
     pub to_where: MinionThrowTarget,
     pub do_pickup: bool,
+    pub command: Option<MinionCommand>,
+    pub formation: Formation,
+}
+
+/// Where the thrown minion starts, the arc it flies along and where it lands
+fn aim_throw(
+    player_gx: &GlobalTransform,
+    to_where: MinionThrowTarget,
+    target_q: &Query<&GlobalTransform>,
+    tilemap: Option<&Tilemap>,
+) -> Option<(ThrowArc, Vec3)> {
+    let target = match to_where {
+        MinionThrowTarget::Location(pos) => pos,
+        MinionThrowTarget::Ent(e) => target_q.get(e).ok()?.translation(),
+    };
+    let player_position = player_gx.translation();
+    let direction = match (target - player_position).with_y(0.0).try_normalize() {
+        Some(direction) => direction,
+        None => *player_gx.forward(),
+    };
+    let from = player_position + direction * 0.5 + Vec3::Y * 0.5;
+    Some(ThrowArc::aim(from, target, tilemap))
 }
 
 pub fn minion_storage_throw(
     mut min_inp: ResMut<MinionStorageInput>,
     mut player_q: Query<(&GlobalTransform, &mut MinionStorage)>,
+    target_q: Query<&GlobalTransform>,
+    level: Res<LevelResources>,
     mut commands: Commands,
     minion_assets: Res<MinionAssets>,
     object_assets: Res<GameObjectAssets>,
-    cursor: Res<GameCursor>,
     mut audio: ResMut<Audio>,
     sfx: Res<AudioAssets>,
 ) {
@@ -56,6 +97,18 @@ pub fn minion_storage_throw(
 
     min_inp.want_to_throw = false;
 
+    let Some((arc, landing)) = aim_throw(tf, min_inp.to_where, &target_q, level.tilemap.as_ref())
+    else {
+        return;
+    };
+
+    // Neither standing on nor aiming at a no-throw face
+    if let Some(tilemap) = &level.tilemap {
+        if !tilemap.allows_throw(tf.translation(), landing) {
+            return;
+        }
+    }
+
     let ty = min_inp.chosen_ty;
     if !mins.extract_minion(ty) {
         return;
@@ -63,25 +116,20 @@ pub fn minion_storage_throw(
 
     let target_id = match min_inp.to_where {
         MinionThrowTarget::Ent(e) => e,
-        MinionThrowTarget::Location(pos) => {
-            WalkTargetBuilder::new(pos).build(&mut commands, &object_assets)
+        MinionThrowTarget::Location(_) => {
+            WalkTargetBuilder::new(landing).build(&mut commands, &object_assets)
         }
     };
 
-    let player_position = tf.translation();
-    let minion_pos = match &cursor.hit {
-        Some(hit) => {
-            player_position + (hit.point - player_position).normalize() * 0.5 + Vec3::Y * 0.5
-        }
-        None => player_position + tf.forward() * 0.5 + Vec3::Y * 0.5,
-    };
-
     let minion = MinionBuilder::new(
         min_inp.chosen_ty,
-        minion_pos,
+        arc.origin,
         MinionState::GoingTo(target_id),
     )
     .build(&mut commands, &minion_assets);
+    commands
+        .entity(minion)
+        .insert((MinionFlight::new(arc), ColliderDisabled));
 
     audio.play_spatial_vol(
         sfx.send_minion_1.clone(),
@@ -91,6 +139,89 @@ pub fn minion_storage_throw(
     );
 }
 
+/// Trajectory of the minion about to be thrown, while aiming
+pub fn minion_throw_preview(
+    min_inp: Res<MinionStorageInput>,
+    player_q: Query<(&GlobalTransform, &MinionStorage)>,
+    target_q: Query<&GlobalTransform>,
+    walk_targets: Query<(), With<WalkTargetTag>>,
+    level: Res<LevelResources>,
+    rapier: Res<RapierContext>,
+    mut gizmos: Gizmos,
+) {
+    if !min_inp.aiming {
+        return;
+    }
+    let Ok((tf, mins)) = player_q.get_single() else {
+        return;
+    };
+    if mins.num_minions(min_inp.chosen_ty) == 0 {
+        return;
+    }
+    let Some((arc, _)) = aim_throw(tf, min_inp.to_where, &target_q, level.tilemap.as_ref()) else {
+        return;
+    };
+    throw::draw_throw_preview(&mut gizmos, &rapier, &walk_targets, &arc);
+}
+
+pub fn minion_storage_command(
+    mut min_inp: ResMut<MinionStorageInput>,
+    mut minion_q: Query<(&GlobalTransform, &MinionKind, &mut MinionState), Without<MinionDying>>,
+) {
+    let Some(command) = min_inp.command.take() else {
+        return;
+    };
+    let ty = min_inp.chosen_ty;
+    let mut minions = minion_q
+        .iter_mut()
+        .filter(|(_, kind, state)| **kind == ty && state.is_commandable())
+        .peekable();
+    if minions.peek().is_none() {
+        return;
+    }
+
+    min_inp.formation = command.apply(
+        min_inp.formation,
+        minions.map(|(gx, _, state)| (gx.translation(), state.into_inner())),
+    );
+}
+
+impl MinionCommand {
+    /// Orders the `minions`, given by position and state, and returns the formation they
+    /// follow in afterwards
+    fn apply<'a>(
+        self,
+        formation: Formation,
+        minions: impl IntoIterator<Item = (Vec3, &'a mut MinionState)>,
+    ) -> Formation {
+        match self {
+            MinionCommand::RecallAll => {
+                minions
+                    .into_iter()
+                    .for_each(|(_, state)| *state = MinionState::Returning);
+                formation
+            }
+            MinionCommand::Hold => {
+                minions
+                    .into_iter()
+                    .for_each(|(position, state)| *state = MinionState::Holding(position));
+                formation
+            }
+            MinionCommand::Follow => {
+                let mut all_following = true;
+                for (_, state) in minions {
+                    all_following &= *state == MinionState::InFormation;
+                    *state = MinionState::InFormation;
+                }
+                match all_following {
+                    true => formation.next(),
+                    false => formation,
+                }
+            }
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct MinionToWhereDebugUi;
 
@@ -120,7 +251,7 @@ pub fn debug_minion_to_where_ui(
 pub fn minion_storage_pickup(
     mut min_inp: ResMut<MinionStorageInput>,
     rap_ctx: ResMut<RapierContext>,
-    dropped_mins: Query<(Entity, &MinionKind)>,
+    dropped_mins: Query<(Entity, &MinionKind), Without<MinionDying>>,
     mut collector: Query<&Children, With<PlayerCollector>>,
     mut player_q: Query<
         (&mut Transform, &mut MinionStorage, &CharacterWalkControl),
@@ -169,3 +300,42 @@ pub fn minion_storage_pickup(
         commands.entity(min).despawn_recursive();
     }
 }
+
+#[test]
+fn test_minion_command() {
+    let mut states = [MinionState::Returning, MinionState::Holding(Vec3::X)];
+    let command_all = |command: MinionCommand, formation, states: &mut [MinionState]| {
+        let positions = [Vec3::ZERO, Vec3::Z];
+        command.apply(formation, positions.into_iter().zip(states.iter_mut()))
+    };
+
+    let formation = command_all(MinionCommand::Hold, Formation::Line, &mut states);
+    assert_eq!(formation, Formation::Line);
+    assert_eq!(
+        states,
+        [
+            MinionState::Holding(Vec3::ZERO),
+            MinionState::Holding(Vec3::Z)
+        ]
+    );
+
+    // The first follow order lines them up, the next ones cycle the formation
+    let formation = command_all(MinionCommand::Follow, formation, &mut states);
+    assert_eq!(formation, Formation::Line);
+    assert_eq!(states, [MinionState::InFormation; 2]);
+    let formation = command_all(MinionCommand::Follow, formation, &mut states);
+    assert_eq!(formation, Formation::Column);
+    let formation = command_all(MinionCommand::Follow, formation, &mut states);
+    assert_eq!(formation, Formation::Circle);
+    let formation = command_all(MinionCommand::Follow, formation, &mut states);
+    assert_eq!(formation, Formation::Line);
+
+    // Unless one of them did something else in between
+    states[1] = MinionState::Holding(Vec3::Z);
+    let formation = command_all(MinionCommand::Follow, formation, &mut states);
+    assert_eq!(formation, Formation::Line);
+
+    let formation = command_all(MinionCommand::RecallAll, formation, &mut states);
+    assert_eq!(formation, Formation::Line);
+    assert_eq!(states, [MinionState::Returning; 2]);
+}
@@ -3,7 +3,7 @@ use crate::{
     framework::audio::{Audio, AudioChannel},
     game::{
         game_cursor::GameCursor,
-        player::minion_storage::{MinionStorageInput, MinionThrowTarget},
+        player::minion_storage::{MinionCommand, MinionStorageInput, MinionThrowTarget},
         top_down_camera::TopDownCamera,
         CharacterWalkControl, MinionKind,
     },
@@ -14,7 +14,10 @@ use player_builder::{PlayerAssets, PlayerBuilder, PlayerMeshTag, COLLIDER_HALF_H
 use std::time::Duration;
 
 #[cfg(feature = "debug_visuals")]
-use {crate::game::LevelResources, vleue_navigator::NavMesh};
+use {
+    crate::{framework::navmesh::NavAgent, game::LevelResources},
+    vleue_navigator::NavMesh,
+};
 
 pub mod minion_storage;
 pub mod player_builder;
@@ -87,8 +90,19 @@ pub fn player_controls(
         }
     };
 
-    minion.want_to_throw = mouse_buttons.just_pressed(MouseButton::Left);
+    // Aimed while held, thrown on release
+    minion.aiming = mouse_buttons.pressed(MouseButton::Left);
+    minion.want_to_throw = mouse_buttons.just_released(MouseButton::Left);
     minion.do_pickup = keyboard.pressed(KeyCode::KeyQ);
+    if keyboard.just_pressed(KeyCode::KeyR) {
+        minion.command = Some(MinionCommand::RecallAll);
+    }
+    if keyboard.just_pressed(KeyCode::KeyH) {
+        minion.command = Some(MinionCommand::Hold);
+    }
+    if keyboard.just_pressed(KeyCode::KeyF) {
+        minion.command = Some(MinionCommand::Follow);
+    }
 
     if keyboard.just_pressed(KeyCode::Digit1) {
         minion.chosen_ty = MinionKind::Void;
@@ -126,7 +140,7 @@ pub fn show_player_control_gizmos(
     let Some(level_reses) = level_reses else {
         return;
     };
-    let Some(navmesh) = &level_reses.navmesh else {
+    let Some(navmesh) = level_reses.navmesh(NavAgent::Player) else {
         return;
     };
     let Some(navmesh) = navmeshes.get(navmesh) else {
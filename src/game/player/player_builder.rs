@@ -1,10 +1,13 @@
-use crate::game::{
-    collision_groups::{ACTOR_GROUP, DETECTION_GROUP, GROUND_GROUP, TARGET_GROUP, WALL_GROUP},
-    common::{self, ShowForwardGizmo},
-    kinematic_char::{CharacterWalkControl, KinematicCharacterBundle},
-    minion::{collector::MinionStorage, MinionKind},
-    objects::{camera::Shineable, definitions::ColorDef},
-    player::{minion_storage::PlayerCollector, PlayerTag},
+use crate::{
+    framework::{navmesh::NavAgent, tilemap::TileFlags},
+    game::{
+        collision_groups::{ACTOR_GROUP, DETECTION_GROUP, GROUND_GROUP, TARGET_GROUP, WALL_GROUP},
+        common::{self, ShowForwardGizmo},
+        kinematic_char::{BlockingTileFlags, CharacterWalkControl, KinematicCharacterBundle},
+        minion::collector::MinionStorage,
+        objects::camera::Shineable,
+        player::{minion_storage::PlayerCollector, PlayerTag},
+    },
 };
 use bevy::{asset::LoadState, color::palettes::tailwind, prelude::*, time::Real};
 use bevy_rapier3d::prelude::*;
@@ -25,14 +28,7 @@ impl PlayerBuilder {
     }
 
     pub fn build(self, cmd: &mut Commands, assets: &PlayerAssets) -> Entity {
-        let mut minion_storage = MinionStorage::new();
-
-        for color in ColorDef::VARIANTS {
-            let kind = MinionKind::from(color);
-            for _ in 0..5 {
-                minion_storage.add_minion(kind);
-            }
-        }
+        let minion_storage = MinionStorage::player_start();
         let root = (
             Name::new("Player"),
             ShowForwardGizmo,
@@ -48,7 +44,11 @@ impl PlayerBuilder {
                 visibility: Visibility::Hidden,
                 ..default()
             },
-            KinematicCharacterBundle::default(),
+            KinematicCharacterBundle {
+                blocking: BlockingTileFlags(TileFlags::MINION_ONLY),
+                ..default()
+            },
+            NavAgent::Player,
             Shineable,
         );
         let collector = (SpatialBundle::default(), PlayerCollector);
@@ -62,7 +62,11 @@ fn main() -> AppExit {
             .add_systems(OnEnter(AppState::InitLevel), load_level)
             .add_systems(
                 Update,
-                (game::level::init_level, wait_for_level_load)
+                (
+                    game::level::init_level,
+                    game::level::spawn_ornamental_meshes,
+                    wait_for_level_load,
+                )
                     .run_if(in_state(AppState::InitLevel)),
             )
             .add_systems(OnExit(AppState::InitLevel), cleanup_loading_screen);
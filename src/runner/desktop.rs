@@ -1,6 +1,20 @@
-use crate::GameRunArgs;
+use crate::{
+    framework::{
+        level_asset::LevelAsset,
+        tileset::{Tileset, TILESET_TILE_NUM},
+    },
+    game::{minion::collector::MinionStorage, objects::definitions::ColorDef},
+    tooling::{
+        level_baker,
+        level_generator::{self, GeneratorParams},
+        level_inspector::LevelReport,
+        level_solver::PuzzleReport,
+    },
+    GameRunArgs,
+};
 use bevy::prelude::*;
 use clap::{Parser, Subcommand};
+use std::path::{Path, PathBuf};
 
 #[cfg(feature = "editor")]
 use crate::tooling::editor::LevelEditorPlugin;
@@ -19,15 +33,68 @@ struct Cli {
 enum Command {
     #[command()]
     Editor,
+    /// Bakes editor scenes into `.level` files without opening a window
+    #[command()]
+    Bake {
+        /// Editor scenes (`.ron`) to bake
+        #[arg(required = true)]
+        scenes: Vec<PathBuf>,
+        /// Directory the baked levels are written to, named after their scene
+        #[arg(short, long, default_value = "assets/level")]
+        out_dir: PathBuf,
+    },
+    /// Converts a level between the binary `.level` and the text `.level.ron` format
+    #[command()]
+    Convert { input: PathBuf, output: PathBuf },
+    /// Prints a summary of `.level` files and checks them for errors
+    #[command(visible_alias = "validate")]
+    Inspect {
+        #[arg(required = true)]
+        levels: Vec<PathBuf>,
+    },
+    /// Checks that the color puzzles of `.level` files can be solved with the minions
+    /// the player starts with
+    #[command()]
+    Solve {
+        #[arg(required = true)]
+        levels: Vec<PathBuf>,
+    },
+    /// Generates a random level and bakes it
+    #[command()]
+    Generate {
+        /// Where the baked `.level` is written
+        output: PathBuf,
+        #[arg(short, long, default_value_t = 0)]
+        seed: u64,
+        #[arg(long, default_value_t = 48)]
+        width: u32,
+        #[arg(long, default_value_t = 48)]
+        height: u32,
+        #[arg(long, default_value_t = 6)]
+        rooms: u32,
+        #[arg(long, default_value_t = 2)]
+        cameras: u32,
+        /// Colors minions have to be mixed into, comma separated
+        #[arg(long, value_delimiter = ',', default_value = "yellow", value_parser = parse_color)]
+        colors: Vec<ColorDef>,
+        /// Also writes the editor scene (`.ron`), to touch the level up in the editor
+        #[arg(long)]
+        scene: Option<PathBuf>,
+    },
 }
 
 pub fn create_app() -> (App, GameRunArgs) {
+    let args = Cli::parse();
+
+    // Headless commands exit before any window or plugin is created
+    if let Some(code) = args.command.as_ref().and_then(run_headless_command) {
+        std::process::exit(code);
+    }
+
     let mut app = App::new();
     app.add_plugins(DefaultPlugins);
 
     // Check for editor
-    let args = Cli::parse();
-
     cfg_if::cfg_if! {
         if #[cfg(feature = "editor")] {
             match args.command {
@@ -42,7 +109,7 @@ pub fn create_app() -> (App, GameRunArgs) {
                         },
                     )
                 }
-                None => (
+                _ => (
                     app,
                     GameRunArgs {
                         init: true,
@@ -65,3 +132,145 @@ pub fn create_app() -> (App, GameRunArgs) {
 pub fn run_app(app: &mut App) -> AppExit {
     app.run()
 }
+
+/// Runs a command that doesn't need the `App`. Returns the process exit code, or `None`
+/// if the command starts the game or editor.
+fn run_headless_command(command: &Command) -> Option<i32> {
+    match command {
+        Command::Editor => None,
+        Command::Bake { scenes, out_dir } => Some(bake(scenes, out_dir)),
+        Command::Convert { input, output } => Some(convert(input, output)),
+        Command::Inspect { levels } => Some(inspect(levels)),
+        Command::Solve { levels } => Some(solve(levels)),
+        Command::Generate {
+            output,
+            seed,
+            width,
+            height,
+            rooms,
+            cameras,
+            colors,
+            scene,
+        } => {
+            let params = GeneratorParams {
+                seed: *seed,
+                dims: UVec2::new(*width, *height),
+                rooms: *rooms,
+                cameras: *cameras,
+                required_colors: colors.clone(),
+            };
+            Some(generate(&params, output, scene.as_deref()))
+        }
+    }
+}
+
+fn parse_color(name: &str) -> Result<ColorDef, String> {
+    ColorDef::VARIANTS
+        .into_iter()
+        .find(|c| c.as_str().eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("unknown color `{name}`"))
+}
+
+fn bake(scenes: &[PathBuf], out_dir: &Path) -> i32 {
+    if let Err(e) = std::fs::create_dir_all(out_dir) {
+        eprintln!("{}: {e}", out_dir.display());
+        return 1;
+    }
+    let mut failed = 0;
+    for scene in scenes {
+        let Some(name) = scene.file_stem() else {
+            eprintln!("{}: not a file", scene.display());
+            failed += 1;
+            continue;
+        };
+        let output = out_dir.join(name).with_extension("level");
+        match level_baker::bake_file(scene, &output) {
+            Ok(()) => println!("{} -> {}", scene.display(), output.display()),
+            Err(e) => {
+                eprintln!("{}: {e:#}", scene.display());
+                failed += 1;
+            }
+        }
+    }
+    (failed > 0) as i32
+}
+
+fn convert(input: &Path, output: &Path) -> i32 {
+    let result = LevelAsset::read(&input.to_string_lossy())
+        .and_then(|level| level.save(&output.to_string_lossy()));
+    match result {
+        Ok(()) => {
+            println!("{} -> {}", input.display(), output.display());
+            0
+        }
+        Err(e) => {
+            eprintln!("{}: {e:#}", input.display());
+            1
+        }
+    }
+}
+
+fn generate(params: &GeneratorParams, output: &Path, scene: Option<&Path>) -> i32 {
+    let tileset = Tileset::new(UVec2::new(TILESET_TILE_NUM[0], TILESET_TILE_NUM[1])).unwrap();
+    let result = level_generator::generate_level(params, &tileset).and_then(|(ron, level)| {
+        level.save(&output.to_string_lossy())?;
+        if let Some(scene) = scene {
+            ron.write(scene)?;
+        }
+        Ok(())
+    });
+    match result {
+        Ok(()) => {
+            println!("Seed {} -> {}", params.seed, output.display());
+            0
+        }
+        Err(e) => {
+            eprintln!("Seed {}: {e:#}", params.seed);
+            1
+        }
+    }
+}
+
+fn inspect(levels: &[PathBuf]) -> i32 {
+    let mut failed = 0;
+    for level in levels {
+        println!("{}:", level.display());
+        match LevelAsset::read(&level.to_string_lossy()) {
+            Ok(asset) => {
+                let report = LevelReport::new(asset.data());
+                print!("{report}");
+                if !report.errors.is_empty() {
+                    failed += 1;
+                }
+            }
+            Err(e) => {
+                println!("Failed to read: {e:#}");
+                failed += 1;
+            }
+        }
+    }
+    (failed > 0) as i32
+}
+
+fn solve(levels: &[PathBuf]) -> i32 {
+    let storage = MinionStorage::player_start();
+    let mut failed = 0;
+    for level in levels {
+        println!("{}:", level.display());
+        match LevelAsset::read(&level.to_string_lossy()) {
+            Ok(asset) => {
+                let data = asset.data();
+                let report = PuzzleReport::new(&data.tilemap, &data.objects, &storage);
+                print!("{report}");
+                if !report.is_solvable() {
+                    failed += 1;
+                }
+            }
+            Err(e) => {
+                println!("Failed to read: {e:#}");
+                failed += 1;
+            }
+        }
+    }
+    (failed > 0) as i32
+}
@@ -0,0 +1,171 @@
+use crate::framework::{grid::Grid, tilemap::Tilemap};
+use bevy::{math::UVec2, utils::HashSet};
+use std::collections::BTreeMap;
+
+pub const MAX_BRUSH_RADIUS: u32 = 16;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BrushShape {
+    #[default]
+    Square,
+    Circle,
+}
+
+impl BrushShape {
+    pub const ALL: [Self; 2] = [Self::Square, Self::Circle];
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Square => "Square",
+            Self::Circle => "Circle",
+        }
+    }
+}
+
+/// How the strength of elevation brushes fades towards the brush edge
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BrushFalloff {
+    #[default]
+    Constant,
+    Linear,
+    Smooth,
+}
+
+impl BrushFalloff {
+    pub const ALL: [Self; 3] = [Self::Constant, Self::Linear, Self::Smooth];
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Constant => "Constant",
+            Self::Linear => "Linear",
+            Self::Smooth => "Smooth",
+        }
+    }
+
+    /// Weight for a normalized distance from the brush center, `t` in [0, 1)
+    pub fn weight(&self, t: f32) -> f32 {
+        let t = t.clamp(0., 1.);
+        match self {
+            Self::Constant => 1.,
+            Self::Linear => 1. - t,
+            Self::Smooth => 1. - t * t * (3. - 2. * t),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Brush {
+    pub shape: BrushShape,
+    /// Faces reached in each direction from the center face, 0 is a single face
+    pub radius: u32,
+    pub falloff: BrushFalloff,
+    /// Elevation steps applied at the brush center
+    pub strength: u32,
+}
+
+impl Default for Brush {
+    fn default() -> Self {
+        Self {
+            shape: BrushShape::Square,
+            radius: 0,
+            falloff: BrushFalloff::Constant,
+            strength: 1,
+        }
+    }
+}
+
+impl Brush {
+    /// Face ids covered by the brush paired with their falloff weight
+    pub fn faces(&self, grid: &Grid, center: UVec2) -> Vec<(u32, f32)> {
+        let r = self.radius as i32;
+        let center = center.as_ivec2();
+        let dims = grid.dims().as_ivec2();
+        let mut faces = Vec::new();
+        for y in (center.y - r).max(0)..=(center.y + r).min(dims.y - 1) {
+            for x in (center.x - r).max(0)..=(center.x + r).min(dims.x - 1) {
+                let d = (x - center.x, y - center.y);
+                let dist = match self.shape {
+                    BrushShape::Square => d.0.abs().max(d.1.abs()) as f32,
+                    BrushShape::Circle => ((d.0 * d.0 + d.1 * d.1) as f32).sqrt(),
+                };
+                if dist > r as f32 + 0.5 {
+                    continue;
+                }
+                let weight = self.falloff.weight(dist / (r + 1) as f32);
+                faces.push((grid.coord_to_id(UVec2::new(x as u32, y as u32)), weight));
+            }
+        }
+        faces
+    }
+
+    /// Vertex ids of all covered faces, each with the highest weight of its faces
+    pub fn verts(&self, tilemap: &Tilemap, center: UVec2) -> Vec<(u32, f32)> {
+        let mut verts = BTreeMap::<u32, f32>::new();
+        for (fid, weight) in self.faces(tilemap.face_grid(), center) {
+            for vid in tilemap.face_id_to_vert_ids(fid) {
+                let w = verts.entry(vid).or_default();
+                *w = w.max(weight);
+            }
+        }
+        verts.into_iter().collect()
+    }
+
+    /// Elevation steps for a weight, may be 0 towards the edge of a falloff brush
+    pub fn amount(&self, weight: f32) -> u32 {
+        (self.strength as f32 * weight).round() as u32
+    }
+}
+
+/// Ids touched by the current stroke, so holding the mouse applies a brush once per id
+#[derive(Debug, Default)]
+pub struct BrushStroke {
+    visited: HashSet<u32>,
+}
+
+impl BrushStroke {
+    pub fn begin(&mut self) {
+        self.visited.clear();
+    }
+    /// Returns true the first time an id is visited during the stroke
+    pub fn visit(&mut self, id: u32) -> bool {
+        self.visited.insert(id)
+    }
+}
+
+#[test]
+fn test_brush_footprint() {
+    let grid = Grid::new(UVec2::new(8, 8)).unwrap();
+    let center = UVec2::new(4, 4);
+
+    let mut brush = Brush::default();
+    assert_eq!(
+        brush.faces(&grid, center),
+        vec![(grid.coord_to_id(center), 1.)]
+    );
+
+    brush.radius = 2;
+    assert_eq!(brush.faces(&grid, center).len(), 25);
+    brush.shape = BrushShape::Circle;
+    assert_eq!(brush.faces(&grid, center).len(), 21);
+
+    // Clipped against the grid edge
+    brush.shape = BrushShape::Square;
+    brush.radius = 1;
+    assert_eq!(brush.faces(&grid, UVec2::ZERO).len(), 4);
+
+    brush.radius = 2;
+    brush.falloff = BrushFalloff::Linear;
+    brush.strength = 3;
+    let faces = brush.faces(&grid, center);
+    let weight = |coord: UVec2| {
+        let fid = grid.coord_to_id(coord);
+        faces.iter().find(|(id, _)| *id == fid).unwrap().1
+    };
+    assert_eq!(brush.amount(weight(center)), 3);
+    assert_eq!(brush.amount(weight(UVec2::new(5, 4))), 2);
+    assert_eq!(brush.amount(weight(UVec2::new(6, 6))), 1);
+
+    let mut stroke = BrushStroke::default();
+    assert!(stroke.visit(3));
+    assert!(!stroke.visit(3));
+    stroke.begin();
+    assert!(stroke.visit(3));
+}
@@ -0,0 +1,183 @@
+use crate::{
+    framework::tilemap::{Tilemap, MAX_NUM_WALLS},
+    tooling::editor::tilemap_controls::TilemapControls,
+};
+use anyhow::{bail, Context};
+use bevy::math::UVec2;
+use image::{GrayImage, Luma};
+use std::path::{Path, PathBuf};
+
+/// Vertex elevations and face wall heights, read from or written to grayscale images.
+///
+/// The elevation image has one pixel per vertex, so it's one pixel larger than the tilemap
+/// in each direction. Black is elevation 0 and white the editor's max elevation.
+/// Wall heights live in an optional mask next to it, see [`Heightmap::walls_path`], with one
+/// pixel per face going from no wall at black to [`MAX_NUM_WALLS`] at white.
+pub struct Heightmap {
+    dims: UVec2,
+    elevations: Vec<u32>,
+    wall_heights: Option<Vec<u32>>,
+}
+
+impl Heightmap {
+    /// Tilemap dims the heightmap fits onto
+    pub fn dims(&self) -> UVec2 {
+        self.dims
+    }
+
+    /// `level.png` keeps its wall mask in `level.walls.png`
+    pub fn walls_path<P: AsRef<Path>>(path: P) -> PathBuf {
+        path.as_ref().with_extension("walls.png")
+    }
+
+    /// Reads the elevation image and, if there is one, its wall mask
+    pub fn read<P: AsRef<Path>>(path: P, max_elevation: u32) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let elevations = image::open(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?
+            .to_luma8();
+        let walls_path = Self::walls_path(path);
+        let walls = match walls_path.is_file() {
+            true => Some(
+                image::open(&walls_path)
+                    .with_context(|| format!("Failed to read {}", walls_path.display()))?
+                    .to_luma8(),
+            ),
+            false => None,
+        };
+        Self::from_images(&elevations, walls.as_ref(), max_elevation)
+    }
+
+    pub fn from_images(
+        elevations: &GrayImage,
+        walls: Option<&GrayImage>,
+        max_elevation: u32,
+    ) -> anyhow::Result<Self> {
+        let (w, h) = elevations.dimensions();
+        if w < 2 || h < 2 {
+            bail!("Heightmap is {w}x{h}, it needs at least 2x2 pixels for a single face");
+        }
+        let dims = UVec2::new(w - 1, h - 1);
+        if let Some(walls) = walls {
+            if walls.dimensions() != (dims.x, dims.y) {
+                let (ww, wh) = walls.dimensions();
+                bail!(
+                    "Wall mask is {ww}x{wh}, expected one pixel per face: {}x{}",
+                    dims.x,
+                    dims.y
+                );
+            }
+        }
+        Ok(Self {
+            dims,
+            elevations: elevations
+                .pixels()
+                .map(|p| from_gray(p.0[0], max_elevation))
+                .collect(),
+            wall_heights: walls.map(|walls| {
+                walls
+                    .pixels()
+                    .map(|p| from_gray(p.0[0], MAX_NUM_WALLS as u32))
+                    .collect()
+            }),
+        })
+    }
+
+    pub fn from_tilemap(tilemap: &Tilemap) -> Self {
+        Self {
+            dims: tilemap.dims(),
+            elevations: tilemap.vert_data().iter().map(|v| v.elevation).collect(),
+            wall_heights: Some(tilemap.face_data().iter().map(|f| f.wall_height).collect()),
+        }
+    }
+
+    /// Elevation image and wall mask, if the heightmap has wall heights
+    pub fn to_images(&self, max_elevation: u32) -> (GrayImage, Option<GrayImage>) {
+        let vert_dims = self.dims + UVec2::ONE;
+        let elevations = GrayImage::from_fn(vert_dims.x, vert_dims.y, |x, y| {
+            let elevation = self.elevations[(y * vert_dims.x + x) as usize];
+            Luma([to_gray(elevation, max_elevation)])
+        });
+        let walls = self.wall_heights.as_ref().map(|heights| {
+            GrayImage::from_fn(self.dims.x, self.dims.y, |x, y| {
+                let height = heights[(y * self.dims.x + x) as usize];
+                Luma([to_gray(height, MAX_NUM_WALLS as u32)])
+            })
+        });
+        (elevations, walls)
+    }
+
+    pub fn write<P: AsRef<Path>>(&self, path: P, max_elevation: u32) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let (elevations, walls) = self.to_images(max_elevation);
+        elevations.save(path)?;
+        if let Some(walls) = walls {
+            walls.save(Self::walls_path(path))?;
+        }
+        Ok(())
+    }
+
+    /// Overwrites the elevations, and wall heights if present, then evens out slopes that
+    /// are steeper than the controls allow
+    pub fn apply(&self, tilemap: &mut Tilemap, controls: &TilemapControls) -> anyhow::Result<()> {
+        if tilemap.dims() != self.dims {
+            bail!(
+                "Heightmap fits a {}x{} tilemap, this one is {}x{}",
+                self.dims.x,
+                self.dims.y,
+                tilemap.dims().x,
+                tilemap.dims().y
+            );
+        }
+        for (vert, elevation) in tilemap.vert_data_mut().iter_mut().zip(&self.elevations) {
+            vert.elevation = *elevation;
+        }
+        if let Some(heights) = &self.wall_heights {
+            for (face, height) in tilemap.face_data_mut().iter_mut().zip(heights) {
+                face.wall_height = *height;
+            }
+        }
+        controls.clamp_slopes(tilemap);
+        Ok(())
+    }
+}
+
+fn from_gray(gray: u8, max: u32) -> u32 {
+    (gray as f32 / 255. * max as f32).round() as u32
+}
+
+fn to_gray(value: u32, max: u32) -> u8 {
+    (value.min(max) as f32 / max.max(1) as f32 * 255.).round() as u8
+}
+
+#[test]
+fn test_heightmap_images() {
+    let controls = TilemapControls::new(32, 6);
+    let mut tilemap = Tilemap::new(UVec2::new(4, 3), 6).unwrap();
+    tilemap.vert_data_mut()[7].elevation = 9;
+    tilemap.face_data_mut()[5].wall_height = 2;
+
+    let (elevations, walls) = Heightmap::from_tilemap(&tilemap).to_images(controls.max_elevation());
+    assert_eq!(elevations.dimensions(), (5, 4));
+    assert_eq!(walls.as_ref().unwrap().dimensions(), (4, 3));
+
+    let heightmap =
+        Heightmap::from_images(&elevations, walls.as_ref(), controls.max_elevation()).unwrap();
+    assert_eq!(heightmap.dims(), tilemap.dims());
+    let mut restored = Tilemap::new(tilemap.dims(), 0).unwrap();
+    heightmap.apply(&mut restored, &controls).unwrap();
+    assert!(restored.vert_data() == tilemap.vert_data());
+    assert!(restored.face_data() == tilemap.face_data());
+
+    // A white spike on black ground is cut down to what the slope rules allow
+    let mut spike = GrayImage::new(5, 5);
+    spike.put_pixel(2, 2, Luma([255]));
+    let heightmap = Heightmap::from_images(&spike, None, controls.max_elevation()).unwrap();
+    let mut tilemap = Tilemap::new(UVec2::new(4, 4), 0).unwrap();
+    heightmap.apply(&mut tilemap, &controls).unwrap();
+    assert_eq!(tilemap.vert_data()[12].elevation, 6);
+    assert!(heightmap
+        .apply(&mut Tilemap::new(UVec2::new(3, 4), 0).unwrap(), &controls)
+        .is_err());
+    assert!(Heightmap::from_images(&spike, Some(&spike), 32).is_err());
+}
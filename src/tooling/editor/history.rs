@@ -0,0 +1,433 @@
+//! Undo/redo for the tilemap editor.
+//!
+//! Edits aren't recorded as they happen. Instead the history keeps a copy of the
+//! document as of the last commit, and each commit stores the difference to it.
+//! That way any editor action becomes undoable by sending a [`CommitEdit`] after it.
+
+use crate::{
+    framework::tilemap::{FaceData, Tilemap, VertData},
+    tooling::{object_def_builder::ObjectDefBuilder, tilemap_asset::OrnamentalMeshBuilder},
+};
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+/// Number of edits that can be undone
+pub const MAX_HISTORY: usize = 100;
+
+/// Records everything edited since the last commit as one undo step
+#[derive(Event)]
+pub struct CommitEdit {
+    pub label: String,
+    /// Fold into the previous step if it has the same label and no mouse button was
+    /// released since. Keeps continuous edits like dragging a value to a single step.
+    pub merge: bool,
+}
+
+impl CommitEdit {
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            merge: false,
+        }
+    }
+    pub fn merged(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            merge: true,
+        }
+    }
+}
+
+#[derive(Event, Clone, Copy)]
+pub enum HistoryAction {
+    Undo,
+    Redo,
+    /// Undo or redo until this many steps are applied
+    JumpTo(usize),
+    /// Forget all steps, e.g. after loading a different scene
+    Clear,
+}
+
+/// Which parts of the document an undo step touched
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Touched {
+    pub tilemap: bool,
+    pub objects: bool,
+    pub ornaments: bool,
+}
+
+impl Touched {
+    fn merge(self, other: Touched) -> Self {
+        Self {
+            tilemap: self.tilemap || other.tilemap,
+            objects: self.objects || other.objects,
+            ornaments: self.ornaments || other.ornaments,
+        }
+    }
+}
+
+/// The editable parts of a scene
+pub struct Document<'a> {
+    pub tilemap: &'a mut Tilemap,
+    pub objects: &'a mut Vec<ObjectDefBuilder>,
+    pub ornaments: &'a mut Vec<OrnamentalMeshBuilder>,
+}
+
+#[derive(Resource)]
+pub struct EditHistory {
+    tilemap: Tilemap,
+    objects: Vec<ObjectDefBuilder>,
+    ornaments: Vec<OrnamentalMeshBuilder>,
+    steps: VecDeque<Step>,
+    /// Number of steps currently applied, the rest can be redone
+    position: usize,
+    sealed: bool,
+}
+
+struct Step {
+    label: String,
+    changes: Vec<Change>,
+}
+
+enum Change {
+    /// `(vid, before, after)`
+    Verts(Vec<(u32, VertData, VertData)>),
+    /// `(fid, before, after)`
+    Faces(Vec<(u32, FaceData, FaceData)>),
+    /// The dimensions changed, so there's nothing to diff against
+    Tilemap(Box<Tilemap>, Box<Tilemap>),
+    Objects(Vec<ObjectDefBuilder>, Vec<ObjectDefBuilder>),
+    Ornaments(Vec<OrnamentalMeshBuilder>, Vec<OrnamentalMeshBuilder>),
+}
+
+impl Change {
+    fn apply(
+        &self,
+        undo: bool,
+        tilemap: &mut Tilemap,
+        objects: &mut Vec<ObjectDefBuilder>,
+        ornaments: &mut Vec<OrnamentalMeshBuilder>,
+    ) -> Touched {
+        match self {
+            Change::Verts(verts) => {
+                for (vid, before, after) in verts {
+                    tilemap.vert_data_mut()[*vid as usize] = pick(undo, before, after).clone();
+                }
+                Touched {
+                    tilemap: true,
+                    ..default()
+                }
+            }
+            Change::Faces(faces) => {
+                for (fid, before, after) in faces {
+                    tilemap.face_data_mut()[*fid as usize] = pick(undo, before, after).clone();
+                }
+                Touched {
+                    tilemap: true,
+                    ..default()
+                }
+            }
+            Change::Tilemap(before, after) => {
+                *tilemap = pick(undo, before, after).as_ref().clone();
+                Touched {
+                    tilemap: true,
+                    ..default()
+                }
+            }
+            Change::Objects(before, after) => {
+                *objects = pick(undo, before, after).clone();
+                Touched {
+                    objects: true,
+                    ..default()
+                }
+            }
+            Change::Ornaments(before, after) => {
+                *ornaments = pick(undo, before, after).clone();
+                Touched {
+                    ornaments: true,
+                    ..default()
+                }
+            }
+        }
+    }
+}
+
+fn pick<T>(undo: bool, before: T, after: T) -> T {
+    if undo {
+        before
+    } else {
+        after
+    }
+}
+
+impl EditHistory {
+    pub fn new(
+        tilemap: &Tilemap,
+        objects: &[ObjectDefBuilder],
+        ornaments: &[OrnamentalMeshBuilder],
+    ) -> Self {
+        Self {
+            tilemap: tilemap.clone(),
+            objects: objects.to_vec(),
+            ornaments: ornaments.to_vec(),
+            steps: VecDeque::new(),
+            position: 0,
+            sealed: true,
+        }
+    }
+
+    /// Forgets all steps, e.g. after loading a different scene
+    pub fn reset(
+        &mut self,
+        tilemap: &Tilemap,
+        objects: &[ObjectDefBuilder],
+        ornaments: &[OrnamentalMeshBuilder],
+    ) {
+        *self = Self::new(tilemap, objects, ornaments);
+    }
+
+    /// Ends merging into the current step
+    pub fn seal(&mut self) {
+        self.sealed = true;
+    }
+
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.steps.iter().map(|step| step.label.as_str())
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Stores the difference between `doc` and the last commit as a new step.
+    /// Returns `None` if nothing changed.
+    pub fn commit(&mut self, label: &str, merge: bool, doc: Document) -> Option<Touched> {
+        let can_merge = merge
+            && !self.sealed
+            && self.position == self.steps.len()
+            && self.steps.back().is_some_and(|step| step.label == label);
+        if can_merge {
+            // Rewind the baseline so the diff covers both steps
+            let step = self.steps.pop_back().unwrap();
+            for change in step.changes.iter().rev() {
+                change.apply(
+                    true,
+                    &mut self.tilemap,
+                    &mut self.objects,
+                    &mut self.ornaments,
+                );
+            }
+            self.position -= 1;
+        }
+        self.sealed = !merge;
+
+        let changes = self.diff(doc.tilemap, doc.objects, doc.ornaments);
+        if changes.is_empty() {
+            return None;
+        }
+
+        let mut touched = Touched::default();
+        for change in changes.iter() {
+            touched = touched.merge(change.apply(
+                false,
+                &mut self.tilemap,
+                &mut self.objects,
+                &mut self.ornaments,
+            ));
+        }
+
+        self.steps.truncate(self.position);
+        self.steps.push_back(Step {
+            label: label.to_owned(),
+            changes,
+        });
+        if self.steps.len() > MAX_HISTORY {
+            self.steps.pop_front();
+        }
+        self.position = self.steps.len();
+        Some(touched)
+    }
+
+    pub fn undo(&mut self, doc: Document) -> Option<Touched> {
+        self.jump_to(self.position.checked_sub(1)?, doc)
+    }
+
+    pub fn redo(&mut self, doc: Document) -> Option<Touched> {
+        self.jump_to(self.position + 1, doc)
+    }
+
+    /// Undoes or redoes steps until `position` of them are applied.
+    /// Edits that weren't committed yet are lost.
+    pub fn jump_to(&mut self, position: usize, doc: Document) -> Option<Touched> {
+        if position > self.steps.len() || position == self.position {
+            return None;
+        }
+        self.sealed = true;
+
+        let mut touched = Touched::default();
+        while self.position != position {
+            let undo = position < self.position;
+            let step = if undo {
+                self.position -= 1;
+                &self.steps[self.position]
+            } else {
+                self.position += 1;
+                &self.steps[self.position - 1]
+            };
+            let changes: Box<dyn Iterator<Item = &Change>> = if undo {
+                Box::new(step.changes.iter().rev())
+            } else {
+                Box::new(step.changes.iter())
+            };
+            for change in changes {
+                change.apply(
+                    undo,
+                    &mut self.tilemap,
+                    &mut self.objects,
+                    &mut self.ornaments,
+                );
+                touched =
+                    touched.merge(change.apply(undo, doc.tilemap, doc.objects, doc.ornaments));
+            }
+        }
+
+        // Anything that wasn't committed is dropped to keep the document in sync
+        let tilemap_synced = doc.tilemap.dims() == self.tilemap.dims()
+            && doc.tilemap.vert_data() == self.tilemap.vert_data()
+            && doc.tilemap.face_data() == self.tilemap.face_data();
+        if !tilemap_synced {
+            doc.tilemap.clone_from(&self.tilemap);
+            touched.tilemap = true;
+        }
+        if *doc.objects != self.objects {
+            doc.objects.clone_from(&self.objects);
+            touched.objects = true;
+        }
+        if *doc.ornaments != self.ornaments {
+            doc.ornaments.clone_from(&self.ornaments);
+            touched.ornaments = true;
+        }
+        Some(touched)
+    }
+
+    fn diff(
+        &self,
+        tilemap: &Tilemap,
+        objects: &[ObjectDefBuilder],
+        ornaments: &[OrnamentalMeshBuilder],
+    ) -> Vec<Change> {
+        let mut changes = vec![];
+
+        if tilemap.dims() != self.tilemap.dims() {
+            changes.push(Change::Tilemap(
+                Box::new(self.tilemap.clone()),
+                Box::new(tilemap.clone()),
+            ));
+        } else {
+            let verts = self
+                .tilemap
+                .vert_data()
+                .iter()
+                .zip(tilemap.vert_data())
+                .enumerate()
+                .filter(|(_, (before, after))| before != after)
+                .map(|(vid, (before, after))| (vid as u32, before.clone(), after.clone()))
+                .collect::<Vec<_>>();
+            if !verts.is_empty() {
+                changes.push(Change::Verts(verts));
+            }
+
+            let faces = self
+                .tilemap
+                .face_data()
+                .iter()
+                .zip(tilemap.face_data())
+                .enumerate()
+                .filter(|(_, (before, after))| before != after)
+                .map(|(fid, (before, after))| (fid as u32, before.clone(), after.clone()))
+                .collect::<Vec<_>>();
+            if !faces.is_empty() {
+                changes.push(Change::Faces(faces));
+            }
+        }
+
+        if objects != self.objects {
+            changes.push(Change::Objects(self.objects.clone(), objects.to_vec()));
+        }
+        if ornaments != self.ornaments {
+            changes.push(Change::Ornaments(
+                self.ornaments.clone(),
+                ornaments.to_vec(),
+            ));
+        }
+        changes
+    }
+}
+
+#[test]
+fn test_edit_history() {
+    use crate::framework::grid::Anchor2;
+
+    let mut tilemap = Tilemap::new(UVec2::new(4, 4), 0).unwrap();
+    let mut objects = vec![];
+    let mut ornaments = vec![];
+    let mut history = EditHistory::new(&tilemap, &objects, &ornaments);
+    macro_rules! doc {
+        () => {
+            Document {
+                tilemap: &mut tilemap,
+                objects: &mut objects,
+                ornaments: &mut ornaments,
+            }
+        };
+    }
+
+    assert!(history.commit("Nothing", false, doc!()).is_none());
+
+    tilemap.vert_data_mut()[3].elevation = 2;
+    tilemap.face_data_mut()[1].wall_height = 1;
+    let touched = history.commit("Raise", false, doc!()).unwrap();
+    assert!(touched.tilemap && !touched.objects);
+
+    objects.push(ObjectDefBuilder::default());
+    history.commit("Add object", false, doc!());
+
+    // Consecutive merged edits become one step
+    objects[0].number = 1;
+    history.commit("Edit object", true, doc!());
+    objects[0].number = 2;
+    history.commit("Edit object", true, doc!());
+    assert_eq!(history.labels().count(), 3);
+
+    tilemap.resize_anchored(UVec2::new(2, 2), Anchor2::TOP_LEFT, 0);
+    history.commit("Resize", false, doc!());
+
+    history.undo(doc!()).unwrap();
+    assert_eq!(tilemap.dims(), UVec2::new(4, 4));
+    assert_eq!(tilemap.vert_data()[3].elevation, 2);
+
+    history.undo(doc!()).unwrap();
+    assert_eq!(objects[0].number, 0);
+
+    history.jump_to(0, doc!()).unwrap();
+    assert!(objects.is_empty());
+    assert_eq!(tilemap.vert_data()[3].elevation, 0);
+    assert_eq!(tilemap.face_data()[1].wall_height, 0);
+    assert!(history.undo(doc!()).is_none());
+
+    history.redo(doc!()).unwrap();
+    assert_eq!(tilemap.face_data()[1].wall_height, 1);
+
+    // A new edit drops the undone steps
+    tilemap.face_data_mut()[2].tile_id = 5;
+    history.commit("Paint", false, doc!());
+    assert_eq!(history.labels().collect::<Vec<_>>(), ["Raise", "Paint"]);
+    assert!(history.redo(doc!()).is_none());
+
+    for i in 0..MAX_HISTORY as u32 + 10 {
+        tilemap.face_data_mut()[0].tile_id = i + 10;
+        history.commit("Paint", false, doc!());
+    }
+    assert_eq!(history.labels().count(), MAX_HISTORY);
+    assert_eq!(history.position(), MAX_HISTORY);
+}
@@ -5,7 +5,7 @@ use crate::{
         tilemap::SLOPE_HEIGHT,
     },
     game::{
-        objects::{assets::GameObjectAssets, camera::CameraObjPlugin},
+        objects::{assets::load_object_assets, camera::CameraObjPlugin},
         player::AddPlayerRespawnEvent,
         LevelResources,
     },
@@ -16,14 +16,19 @@ use bevy_inspector_egui::quick::WorldInspectorPlugin;
 use bevy_rapier3d::plugin::{NoUserData, RapierPhysicsPlugin};
 use tilemap_editor::TilemapEditorPlugin;
 
-pub mod object_def_builder;
-pub mod tilemap_asset;
+pub mod brush;
+pub mod heightmap;
+pub mod history;
+pub mod stamp;
 pub mod tilemap_controls;
 pub mod tilemap_editor;
-pub mod tilemap_mesh_builder;
 pub mod widgets {
+    pub mod brush;
     pub mod file_selector;
+    pub mod generator;
+    pub mod level_metadata;
     pub mod object_def;
+    pub mod ornament;
     pub mod tilemap_size;
     pub mod tileset;
 }
@@ -49,10 +54,9 @@ impl Plugin for LevelEditorPlugin {
             color: Color::WHITE,
             brightness: 500.,
         })
-        .init_resource::<GameObjectAssets>()
         .init_resource::<LevelResources>()
         .add_event::<AddPlayerRespawnEvent>()
-        .add_systems(Startup, setup);
+        .add_systems(Startup, (setup, load_object_assets));
 
         #[cfg(feature = "debug_visuals")]
         {
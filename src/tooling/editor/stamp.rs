@@ -0,0 +1,289 @@
+use crate::{
+    framework::tilemap::{FaceData, Tilemap, MAX_NUM_WALLS},
+    tooling::object_def_builder::ObjectDefBuilder,
+};
+use anyhow::bail;
+use bevy::math::{IVec2, UVec2};
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::{fs::OpenOptions, io::BufWriter, path::Path};
+
+/// A rectangular piece of a tilemap with the objects standing on it.
+/// Doubles as the editor clipboard and as a reusable stamp file.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TilemapStamp {
+    pub version: u32,
+    pub tilemap: Tilemap,
+    /// Coords are relative to the stamp, `obj_refs` index into this list
+    pub objects: Vec<ObjectDefBuilder>,
+}
+
+impl TilemapStamp {
+    pub const CURRENT_VERSION: u32 = 1;
+
+    /// Copies the `dims` faces at `start`. Objects outside the region are left out,
+    /// along with any references pointing out of it.
+    pub fn copy(
+        tilemap: &Tilemap,
+        objects: &[ObjectDefBuilder],
+        start: UVec2,
+        dims: UVec2,
+    ) -> Option<Self> {
+        let region = tilemap.region(start, dims)?;
+        let inside = |coord: UVec2| coord.cmpge(start).all() && coord.cmplt(start + dims).all();
+
+        let ids = objects
+            .iter()
+            .scan(0, |next, obj| {
+                let id = inside(obj.coord).then_some(*next);
+                *next += id.is_some() as u32;
+                Some(id)
+            })
+            .collect::<Vec<_>>();
+
+        let objects = objects
+            .iter()
+            .filter(|obj| inside(obj.coord))
+            .map(|obj| ObjectDefBuilder {
+                coord: obj.coord - start,
+                obj_refs: obj
+                    .obj_refs
+                    .iter()
+                    .filter_map(|r| ids.get(*r as usize).copied().flatten())
+                    .collect(),
+                coord_refs: obj
+                    .coord_refs
+                    .iter()
+                    .filter(|c| inside(**c))
+                    .map(|c| *c - start)
+                    .collect(),
+                ..obj.clone()
+            })
+            .collect();
+
+        Some(Self {
+            version: Self::CURRENT_VERSION,
+            tilemap: region,
+            objects,
+        })
+    }
+
+    pub fn dims(&self) -> UVec2 {
+        self.tilemap.dims()
+    }
+
+    /// Writes the stamp with its top left face at `start`. Objects are appended to `objects`,
+    /// except those landing outside the map.
+    pub fn paste(&self, tilemap: &mut Tilemap, objects: &mut Vec<ObjectDefBuilder>, start: IVec2) {
+        tilemap.paste_region(&self.tilemap, start);
+
+        let grid = *tilemap.face_grid();
+        let place = |coord: UVec2| {
+            let coord = coord.as_ivec2() + start;
+            (coord.cmpge(IVec2::ZERO).all() && grid.is_coord_in_grid(coord.as_uvec2()))
+                .then_some(coord.as_uvec2())
+        };
+
+        let first = objects.len() as u32;
+        let ids = self
+            .objects
+            .iter()
+            .scan(first, |next, obj| {
+                let id = place(obj.coord).map(|_| *next);
+                *next += id.is_some() as u32;
+                Some(id)
+            })
+            .collect::<Vec<_>>();
+
+        for obj in self.objects.iter() {
+            let Some(coord) = place(obj.coord) else {
+                continue;
+            };
+            objects.push(ObjectDefBuilder {
+                coord,
+                obj_refs: obj
+                    .obj_refs
+                    .iter()
+                    .filter_map(|r| ids.get(*r as usize).copied().flatten())
+                    .collect(),
+                coord_refs: obj.coord_refs.iter().filter_map(|c| place(*c)).collect(),
+                ..obj.clone()
+            });
+        }
+    }
+
+    /// Quarter turn, taking +X to +Y on the grid
+    pub fn rotated(&self) -> Self {
+        let dims = self.dims();
+        self.remapped(
+            UVec2::new(dims.y, dims.x),
+            |c, d| UVec2::new(d.y - 1 - c.y, c.x),
+            |side| (side + 1) % 4,
+            |obj| obj.rotation = obj.rotation.rotated(-2),
+        )
+    }
+
+    /// Flipped along the X axis
+    pub fn mirrored(&self) -> Self {
+        self.remapped(
+            self.dims(),
+            |c, d| UVec2::new(d.x - 1 - c.x, c.y),
+            |side| [2, 1, 0, 3][side],
+            |obj| obj.rotation = obj.rotation.mirrored(),
+        )
+    }
+
+    /// `coord` maps a coord within grid dims to its new spot, it's used for both faces and
+    /// vertices. `side` maps wall side indices, see `FaceData::wall_side_tile_ids`.
+    fn remapped(
+        &self,
+        dims: UVec2,
+        coord: impl Fn(UVec2, UVec2) -> UVec2,
+        side: impl Fn(usize) -> usize,
+        object: impl Fn(&mut ObjectDefBuilder),
+    ) -> Self {
+        let src = &self.tilemap;
+        let mut tilemap = Tilemap::new(dims, 0).expect("stamp dims are never zero");
+
+        let (src_grid, dst_grid) = (*src.face_grid(), *tilemap.face_grid());
+        for (fid, face) in src.face_data().iter().enumerate() {
+            let dst =
+                dst_grid.coord_to_id(coord(src_grid.id_to_coord(fid as u32), src_grid.dims()));
+            let mut wall_side_tile_ids = [0; 4 * MAX_NUM_WALLS];
+            for (i, tid) in face.wall_side_tile_ids.iter().enumerate() {
+                wall_side_tile_ids[i / 4 * 4 + side(i % 4)] = *tid;
+            }
+            tilemap.face_data_mut()[dst as usize] = FaceData {
+                wall_side_tile_ids,
+                ..face.clone()
+            };
+        }
+
+        let (src_grid, dst_grid) = (*src.vert_grid(), *tilemap.vert_grid());
+        for (vid, vert) in src.vert_data().iter().enumerate() {
+            let dst =
+                dst_grid.coord_to_id(coord(src_grid.id_to_coord(vid as u32), src_grid.dims()));
+            tilemap.vert_data_mut()[dst as usize] = vert.clone();
+        }
+
+        let faces = self.dims();
+        let objects = self
+            .objects
+            .iter()
+            .map(|obj| {
+                let mut obj = ObjectDefBuilder {
+                    coord: coord(obj.coord, faces),
+                    coord_refs: obj.coord_refs.iter().map(|c| coord(*c, faces)).collect(),
+                    ..obj.clone()
+                };
+                object(&mut obj);
+                obj
+            })
+            .collect();
+
+        Self {
+            version: self.version,
+            tilemap,
+            objects,
+        }
+    }
+
+    pub fn read<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        let stamp: Self = ron::de::from_bytes(&bytes)?;
+        if stamp.version != Self::CURRENT_VERSION {
+            bail!(
+                "Stamp version {} is not supported, expected {}",
+                stamp.version,
+                Self::CURRENT_VERSION
+            );
+        }
+        Ok(stamp)
+    }
+
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(path)?;
+        let writer = BufWriter::new(file);
+        ron::ser::to_writer_pretty(writer, self, PrettyConfig::new())?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_stamp_copy_paste() {
+    use crate::tooling::object_def_builder::Rot8;
+
+    let mut tilemap = Tilemap::new(UVec2::new(6, 4), 0).unwrap();
+    let grid = *tilemap.face_grid();
+    let vgrid = *tilemap.vert_grid();
+    tilemap.face_data_mut()[grid.coord_to_id(UVec2::new(1, 1)) as usize].tile_id = 7;
+    tilemap.face_data_mut()[grid.coord_to_id(UVec2::new(2, 1)) as usize].wall_side_tile_ids[2] = 9;
+    tilemap.vert_data_mut()[vgrid.coord_to_id(UVec2::new(1, 1)) as usize].elevation = 3;
+    let objects = vec![
+        ObjectDefBuilder {
+            coord: UVec2::new(2, 1),
+            obj_refs: vec![2],
+            ..Default::default()
+        },
+        ObjectDefBuilder {
+            coord: UVec2::new(5, 3),
+            ..Default::default()
+        },
+        ObjectDefBuilder {
+            coord: UVec2::new(1, 2),
+            obj_refs: vec![0, 1],
+            rotation: Rot8::D90,
+            ..Default::default()
+        },
+    ];
+
+    // 3x2 region starting at (1, 1), the object at (5, 3) is outside
+    let stamp = TilemapStamp::copy(&tilemap, &objects, UVec2::new(1, 1), UVec2::new(3, 2)).unwrap();
+    assert_eq!(stamp.dims(), UVec2::new(3, 2));
+    assert_eq!(stamp.tilemap.face_data()[0].tile_id, 7);
+    assert_eq!(stamp.tilemap.vert_data()[0].elevation, 3);
+    assert_eq!(stamp.objects.len(), 2);
+    assert_eq!(stamp.objects[0].obj_refs, vec![1]);
+    assert_eq!(stamp.objects[1].obj_refs, vec![0]);
+    assert!(TilemapStamp::copy(&tilemap, &objects, UVec2::new(4, 1), UVec2::new(3, 2)).is_none());
+
+    // Four quarter turns and two mirrors are no-ops
+    let turned = stamp.rotated();
+    assert_eq!(turned.dims(), UVec2::new(2, 3));
+    // (0, 0) ends up at (1, 0), the +X wall side turns into +Z
+    assert_eq!(turned.tilemap.face_data()[1].tile_id, 7);
+    assert_eq!(turned.tilemap.face_data()[3].wall_side_tile_ids[3], 9);
+    assert_eq!(turned.objects[1].rotation, Rot8::D0);
+    let back = turned.rotated().rotated().rotated();
+    assert!(back.tilemap.face_data() == stamp.tilemap.face_data());
+    assert!(back.tilemap.vert_data() == stamp.tilemap.vert_data());
+    assert_eq!(back.objects, stamp.objects);
+    let mirrored = stamp.mirrored();
+    assert_eq!(mirrored.tilemap.face_data()[2].tile_id, 7);
+    assert_eq!(mirrored.tilemap.face_data()[1].wall_side_tile_ids[0], 9);
+    assert_eq!(mirrored.objects[1].rotation, Rot8::D270);
+    assert_eq!(mirrored.mirrored().objects, stamp.objects);
+
+    let mut dst = Tilemap::new(UVec2::new(4, 4), 1).unwrap();
+    let mut dst_objects = vec![ObjectDefBuilder::default()];
+    stamp.paste(&mut dst, &mut dst_objects, IVec2::ZERO);
+    assert_eq!(dst.face_data()[0].tile_id, 7);
+    assert_eq!(dst.face_data()[1].wall_side_tile_ids[2], 9);
+    assert_eq!(dst.vert_data()[0].elevation, 3);
+    assert_eq!(dst_objects.len(), 3);
+    assert_eq!(dst_objects[1].obj_refs, vec![2]);
+
+    // Partially off the map, whatever sticks out is dropped
+    let mut dst = Tilemap::new(UVec2::new(4, 4), 1).unwrap();
+    let mut dst_objects = vec![ObjectDefBuilder::default()];
+    stamp.paste(&mut dst, &mut dst_objects, IVec2::new(2, -1));
+    assert_eq!(dst.vert_data()[1].elevation, 1);
+    assert_eq!(dst.vert_data()[2].elevation, 0);
+    assert_eq!(dst_objects.len(), 2);
+    assert_eq!(dst_objects[1].coord, UVec2::new(2, 0));
+    assert!(dst_objects[1].obj_refs.is_empty());
+}
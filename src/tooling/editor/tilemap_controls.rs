@@ -16,20 +16,37 @@ impl TilemapControls {
         }
     }
 
+    pub fn max_elevation(&self) -> u32 {
+        self.max_elevation
+    }
+
+    /// Lowers vertices until none rises more than the max slope above its neighbors
+    pub fn clamp_slopes(&self, tilemap: &mut Tilemap) {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for vid in 0..tilemap.vert_data().len() as u32 {
+                let cap = tilemap
+                    .vert_neighbor_elevations(vid)
+                    .min()
+                    .map_or(u32::MAX, |e| e + self.max_elevation_slope)
+                    .min(self.max_elevation);
+                let vert = &mut tilemap.vert_data_mut()[vid as usize];
+                if vert.elevation > cap {
+                    vert.elevation = cap;
+                    changed = true;
+                }
+            }
+        }
+    }
+
     /// Raises tile elevation up to the configured cap
     pub fn raise_face_elevation(&self, tilemap: &mut Tilemap, fid: u32, amount: u32) {
         if !tilemap.face_grid().is_id_in_grid(fid) {
             return;
         }
         for vid in tilemap.face_id_to_vert_ids(fid) {
-            let slope = tilemap
-                .vert_neighbor_elevations(vid)
-                .fold(u32::MAX, |acc, x| u32::min(acc, x));
-            let vert = &mut tilemap.vert_data_mut()[vid as usize];
-            vert.elevation = u32::min(
-                vert.elevation + amount,
-                u32::min(self.max_elevation, slope + self.max_elevation_slope),
-            );
+            self.raise_vert_elevation(tilemap, vid, amount);
         }
     }
     pub fn lower_face_elevation(&self, tilemap: &mut Tilemap, fid: u32, amount: u32) {
@@ -37,13 +54,49 @@ impl TilemapControls {
             return;
         }
         for vid in tilemap.face_id_to_vert_ids(fid) {
-            let slope = tilemap
+            self.lower_vert_elevation(tilemap, vid, amount);
+        }
+    }
+
+    pub fn raise_vert_elevation(&self, tilemap: &mut Tilemap, vid: u32, amount: u32) {
+        let slope = tilemap
+            .vert_neighbor_elevations(vid)
+            .fold(u32::MAX, |acc, x| u32::min(acc, x));
+        let vert = &mut tilemap.vert_data_mut()[vid as usize];
+        vert.elevation = u32::min(
+            vert.elevation + amount,
+            u32::min(self.max_elevation, slope + self.max_elevation_slope),
+        );
+    }
+    pub fn lower_vert_elevation(&self, tilemap: &mut Tilemap, vid: u32, amount: u32) {
+        let slope = tilemap
+            .vert_neighbor_elevations(vid)
+            .fold(u32::MIN, |acc, x| u32::max(acc, x));
+        let vert = &mut tilemap.vert_data_mut()[vid as usize];
+        vert.elevation = u32::max(
+            u32::saturating_sub(vert.elevation, amount),
+            u32::saturating_sub(slope, self.max_elevation_slope),
+        );
+    }
+
+    /// Moves all vertices of a face towards `elevation`, as far as the slope limit
+    /// to their neighbors allows
+    pub fn flatten_face_elevation(&self, tilemap: &mut Tilemap, fid: u32, elevation: u32) {
+        if !tilemap.face_grid().is_id_in_grid(fid) {
+            return;
+        }
+        let elevation = u32::min(elevation, self.max_elevation);
+        for vid in tilemap.face_id_to_vert_ids(fid) {
+            let (lowest, highest) = tilemap
                 .vert_neighbor_elevations(vid)
-                .fold(u32::MIN, |acc, x| u32::max(acc, x));
+                .fold((u32::MAX, u32::MIN), |(lo, hi), x| (lo.min(x), hi.max(x)));
             let vert = &mut tilemap.vert_data_mut()[vid as usize];
-            vert.elevation = u32::max(
-                u32::saturating_sub(vert.elevation, amount),
-                u32::saturating_sub(slope, self.max_elevation_slope),
+            vert.elevation = u32::min(
+                u32::max(
+                    elevation,
+                    u32::saturating_sub(highest, self.max_elevation_slope),
+                ),
+                lowest + self.max_elevation_slope,
             );
         }
     }
@@ -84,3 +137,25 @@ impl TilemapControls {
         }
     }
 }
+
+#[test]
+fn test_flatten_face_elevation() {
+    use bevy::math::UVec2;
+
+    let controls = TilemapControls::new(32, 2);
+    let mut tilemap = Tilemap::new(UVec2::new(3, 3), 4).unwrap();
+    let center = tilemap.face_grid().coord_to_id(UVec2::new(1, 1));
+
+    controls.flatten_face_elevation(&mut tilemap, center, 5);
+    for vid in tilemap.face_id_to_vert_ids(center) {
+        assert_eq!(tilemap.vert_data()[vid as usize].elevation, 5);
+    }
+
+    // Surrounded by elevation 4, so it can't go lower than 2. The face's own vertices
+    // hold each other back too, which takes a second pass while the mouse is held.
+    controls.flatten_face_elevation(&mut tilemap, center, 0);
+    controls.flatten_face_elevation(&mut tilemap, center, 0);
+    for vid in tilemap.face_id_to_vert_ids(center) {
+        assert_eq!(tilemap.vert_data()[vid as usize].elevation, 2);
+    }
+}
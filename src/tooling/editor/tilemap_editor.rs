@@ -1,16 +1,29 @@
 use crate::{
     framework::{
         global_ui_state::GlobalUiState,
+        level_asset::metadata::LevelMetadata,
         logical_cursor::LogicalCursor,
-        tilemap::{Tilemap, SLOPE_HEIGHT, WALL_HEIGHT},
+        tilemap::{TileFlags, Tilemap, SLOPE_HEIGHT, WALL_HEIGHT},
         tileset::{Tileset, TILESET_TILE_NUM},
-        Pnormal3,
+        Pnormal2, Pnormal3,
     },
     game::{
         collision_groups::{GROUND_GROUP, WALL_GROUP},
         common, objects,
     },
-    tooling::editor::{object_def_builder::ObjectDefBuilder, tilemap_controls::TilemapControls},
+    tooling::{
+        editor::{
+            brush::{Brush, BrushStroke, MAX_BRUSH_RADIUS},
+            history::{CommitEdit, Document, EditHistory, HistoryAction, Touched},
+            stamp::TilemapStamp,
+            tilemap_controls::TilemapControls,
+        },
+        object_def_builder::ObjectDefBuilder,
+        tilemap_asset::{
+            GroundOrnamentalMesh, OrnamentalMeshBuilder, WallSideOrnamentalMesh,
+            WallTopOrnamentalMesh,
+        },
+    },
 };
 use bevy::{
     color::palettes::tailwind::{self, *},
@@ -22,6 +35,7 @@ use std::f32::consts::PI;
 
 const DEFAULT_EDITOR_SAVE_PATH: &str = "./level_editor_scenes";
 const DEFAULT_EDITOR_EXPORT_PATH: &str = "./assets/level";
+const DEFAULT_EDITOR_STAMP_PATH: &str = "./level_editor_scenes/stamps";
 const START_ELEVATION: u32 = 6;
 
 #[derive(Component, Reflect)]
@@ -32,6 +46,7 @@ pub struct TilemapWallMesh;
 
 #[derive(Resource)]
 pub struct EditorState {
+    metadata: LevelMetadata,
     tilemap: Tilemap,
     tileset: Tileset,
     hovered_ground_face: Option<u32>,
@@ -40,6 +55,40 @@ pub struct EditorState {
     hovered_wall_normal: Option<Pnormal3>,
     selected_tileset_coords: Option<UVec2>,
     hovered_map_coord: Option<UVec2>,
+    /// Elevation sampled when a flatten stroke started
+    flatten_elevation: u32,
+    brush: Brush,
+    stroke: BrushStroke,
+    /// Corners of the selected face rectangle, in the order they were dragged
+    selection: Option<(UVec2, UVec2)>,
+    clipboard: Option<TilemapStamp>,
+    /// The clipboard follows the cursor and gets pasted on click
+    pasting: bool,
+    /// Flag set or cleared when painting tile properties
+    painted_flag: TileFlags,
+}
+
+impl EditorState {
+    /// Start and dims of the selected face rectangle
+    fn selection_rect(&self) -> Option<(UVec2, UVec2)> {
+        let (a, b) = self.selection?;
+        Some((a.min(b), (a.max(b) - a.min(b)) + UVec2::ONE))
+    }
+
+    fn copy_selection(&mut self, objects: &[ObjectDefBuilder]) {
+        let Some((start, dims)) = self.selection_rect() else {
+            return;
+        };
+        self.clipboard = TilemapStamp::copy(&self.tilemap, objects, start, dims);
+    }
+
+    /// Where the clipboard lands when pasted with its center on the hovered face
+    fn paste_start(&self) -> Option<IVec2> {
+        let clipboard = self.clipboard.as_ref()?;
+        let fid = self.hovered_ground_face?;
+        let coord = self.tilemap.face_grid().id_to_coord(fid);
+        Some(coord.as_ivec2() - (clipboard.dims() / 2).as_ivec2())
+    }
 }
 
 #[derive(Resource)]
@@ -55,7 +104,10 @@ pub enum ControlMode {
     Paint2D,
     PaintTerrain3D,
     PaintWalls3D,
+    PaintProperties,
     PlaceGameObjects,
+    PlaceOrnaments,
+    Select,
     #[default]
     AdminStuff,
 }
@@ -66,17 +118,23 @@ impl Plugin for TilemapEditorPlugin {
     fn build(&self, app: &mut App) {
         let tilemap = Tilemap::new(UVec2::new(32, 32), START_ELEVATION).unwrap();
         let tileset = Tileset::new(UVec2::new(TILESET_TILE_NUM[0], TILESET_TILE_NUM[1])).unwrap();
+        let history = EditHistory::new(&tilemap, &[], &[]);
 
         app.init_resource::<oneshot::Systems>()
             .init_state::<ControlMode>()
             .init_resource::<oneshot::ExportLevelScenePath>()
             .init_resource::<ObjectMarkerData>()
             .init_resource::<ObjectDefStorage>()
+            .init_resource::<OrnamentStorage>()
             .init_resource::<EguiUserTextures>()
             .add_event::<SelectedObjectChanged>()
             .add_event::<DespawnObject>()
             .add_event::<SpawnObject>()
+            .add_event::<CommitEdit>()
+            .add_event::<HistoryAction>()
+            .insert_resource(history)
             .insert_resource(EditorState {
+                metadata: LevelMetadata::default(),
                 tilemap,
                 tileset,
                 hovered_ground_face: None,
@@ -84,7 +142,14 @@ impl Plugin for TilemapEditorPlugin {
                 hovered_wall_height: None,
                 hovered_wall_normal: None,
                 hovered_map_coord: None,
+                flatten_elevation: START_ELEVATION,
                 selected_tileset_coords: None,
+                brush: Brush::default(),
+                stroke: BrushStroke::default(),
+                selection: None,
+                clipboard: None,
+                pasting: false,
+                painted_flag: TileFlags::HAZARD,
             })
             .init_resource::<ui::EguiState>()
             .insert_resource(EditorControls {
@@ -101,11 +166,19 @@ impl Plugin for TilemapEditorPlugin {
                     ui::render_egui,
                     // _draw_vert_gizmos,
                     draw_hovered_tile_gizmo,
+                    draw_selected_ornament_gizmo,
+                    draw_selection_gizmo,
+                    draw_tile_flags_gizmo,
                     ui::update_info_text,
                     ui::check_open_file_dialog,
                     ui::update_object_def_ui,
+                    ui::update_ornament_ui,
+                    ui::update_history_ui,
+                    ui::update_puzzle_report.run_if(in_state(ControlMode::AdminStuff)),
+                    history_shortcuts,
                 ),
             )
+            .add_systems(PostUpdate, (commit_edits, apply_history_actions).chain())
             .add_systems(
                 PreUpdate,
                 (
@@ -124,20 +197,24 @@ impl Plugin for TilemapEditorPlugin {
 fn setup(mut cmd: Commands, sys: Res<oneshot::Systems>) {
     cmd.run_system(sys.recreate_scene);
     cmd.run_system(sys.recreate_object_markers);
+    cmd.run_system(sys.recreate_ornaments);
 }
 
 mod oneshot {
     use super::{
-        ui, DespawnObject, EditorState, ObjectDefStorage, ObjectMarker, SpawnObject,
-        TilemapGroundMesh, TilemapWallMesh,
+        ui, DespawnObject, EditorState, ObjectDefStorage, ObjectMarker, OrnamentMarker,
+        OrnamentStorage, SpawnObject, TilemapGroundMesh, TilemapWallMesh,
     };
     use crate::{
-        framework::level_asset::{BakedWallData, LevelAsset, LevelAssetData},
         game::{
             collision_groups::{ACTOR_GROUP, GROUND_GROUP, TARGET_GROUP, WALL_GROUP},
             objects::assets::GameObjectAssets,
         },
-        tooling::editor::tilemap_mesh_builder::{self, RawMeshBuilder},
+        tooling::{
+            level_baker,
+            tilemap_asset::TilemapRon,
+            tilemap_mesh_builder::{self, RawMeshBuilder},
+        },
     };
     use bevy::{ecs::system::SystemId, prelude::*};
     use bevy_rapier3d::prelude::*;
@@ -146,6 +223,7 @@ mod oneshot {
     pub(super) struct Systems {
         pub(super) recreate_scene: SystemId,
         pub(super) recreate_object_markers: SystemId,
+        pub(super) recreate_ornaments: SystemId,
         pub(super) export_level_scene: SystemId,
     }
 
@@ -154,6 +232,7 @@ mod oneshot {
             Self {
                 recreate_scene: world.register_system(recreate_scene),
                 recreate_object_markers: world.register_system(recreate_object_markers),
+                recreate_ornaments: world.register_system(recreate_ornaments),
                 export_level_scene: world.register_system(export_level_scene),
             }
         }
@@ -245,45 +324,51 @@ mod oneshot {
         }
     }
 
+    fn recreate_ornaments(
+        mut cmd: Commands,
+        state: Res<EditorState>,
+        ornaments: Res<OrnamentStorage>,
+        markers: Query<Entity, With<OrnamentMarker>>,
+        ass: Res<AssetServer>,
+    ) {
+        for ex in markers.iter() {
+            cmd.entity(ex).despawn_recursive();
+        }
+
+        for (id, ornament) in ornaments.storage.iter().enumerate() {
+            if ornament.mesh_path().is_empty() {
+                continue;
+            }
+            let Some(mesh) = ornament.build(&state.tilemap) else {
+                continue;
+            };
+            cmd.spawn((
+                SceneBundle {
+                    scene: ass.load(mesh.asset_path.clone()),
+                    transform: mesh.transform(),
+                    ..default()
+                },
+                OrnamentMarker { id: id as u32 },
+            ));
+        }
+    }
+
     #[derive(Resource, Default)]
     pub struct ExportLevelScenePath(pub String);
 
     fn export_level_scene(world: &mut World) {
-        // let mut ass = world.resource_mut::<AssetServer>();
         let state = world.resource::<EditorState>();
         let path = world.resource::<ExportLevelScenePath>().0.clone();
-
-        let builder = RawMeshBuilder::new(&state.tilemap);
-        let mesh = builder.make_ground_mesh(&state.tileset);
-        let collider =
-            tilemap_mesh_builder::build_rapier_convex_collider_for_preview(&mesh.clone().into());
-
-        let walls = builder
-            .make_wall_meshes(&state.tileset)
-            .into_iter()
-            .map(|mesh| {
-                let collider = tilemap_mesh_builder::build_rapier_convex_collider_for_preview(
-                    &mesh.clone().into(),
-                );
-                BakedWallData { mesh, collider }
-            })
-            .collect();
-
         let object_defs = world.resource::<ObjectDefStorage>();
-        let objects = object_defs
-            .storage
-            .iter()
-            .map(|d| d.build(&state.tilemap))
-            .collect();
-
-        let level_asset = LevelAsset::new(LevelAssetData {
-            tilemap: state.tilemap.clone(),
-            baked_ground_collider: collider,
-            baked_ground_mesh: mesh,
-            baked_walls: walls,
-            objects,
-            meshes: vec![], // TODO
-        });
+        let ornaments = world.resource::<OrnamentStorage>();
+
+        let scene = TilemapRon::new(
+            state.metadata.clone(),
+            state.tilemap.clone(),
+            object_defs.storage.clone(),
+            ornaments.storage.clone(),
+        );
+        let level_asset = level_baker::bake_level(&scene, &state.tileset);
 
         match level_asset.save(path.as_str()) {
             Ok(()) => info!("Export successful!"),
@@ -430,6 +515,198 @@ fn update_selected_object_marker_material(
     }
 }
 
+#[derive(Resource, Default)]
+pub struct OrnamentStorage {
+    storage: Vec<OrnamentalMeshBuilder>,
+    selected_id: Option<u32>,
+}
+
+#[derive(Component)]
+pub struct OrnamentMarker {
+    #[allow(dead_code)]
+    id: u32,
+}
+
+fn draw_selected_ornament_gizmo(
+    mut gizmos: Gizmos,
+    state: Res<EditorState>,
+    ornaments: Res<OrnamentStorage>,
+    control_mode: Res<State<ControlMode>>,
+) {
+    if *control_mode.get() != ControlMode::PlaceOrnaments {
+        return;
+    }
+    let Some(ornament) = ornaments
+        .selected_id
+        .and_then(|id| ornaments.storage.get(id as usize))
+    else {
+        return;
+    };
+    let Some(mesh) = ornament.build(&state.tilemap) else {
+        return;
+    };
+    let transform = mesh.transform();
+    gizmos.cuboid(transform.with_scale(Vec3::splat(0.5)), RED_400);
+}
+
+fn draw_selection_gizmo(
+    mut gizmos: Gizmos,
+    state: Res<EditorState>,
+    transform: Query<&Transform, With<TilemapGroundMesh>>,
+    control_mode: Res<State<ControlMode>>,
+) {
+    if *control_mode.get() != ControlMode::Select {
+        return;
+    }
+    let offset = transform.single().translation;
+    if let Some((start, dims)) = state.selection_rect() {
+        draw_region_outline(
+            &mut gizmos,
+            &state.tilemap,
+            offset,
+            start.as_ivec2(),
+            dims,
+            SKY_400,
+        );
+    }
+    if let (true, Some(start), Some(clipboard)) =
+        (state.pasting, state.paste_start(), &state.clipboard)
+    {
+        draw_region_outline(
+            &mut gizmos,
+            &state.tilemap,
+            offset,
+            start,
+            clipboard.dims(),
+            AMBER_400,
+        );
+    }
+}
+
+fn draw_tile_flags_gizmo(
+    mut gizmos: Gizmos,
+    state: Res<EditorState>,
+    transform: Query<&Transform, With<TilemapGroundMesh>>,
+    control_mode: Res<State<ControlMode>>,
+) {
+    if *control_mode.get() != ControlMode::PaintProperties {
+        return;
+    }
+    let offset = transform.single().translation;
+    let map = &state.tilemap;
+    for (fid, face) in map.face_data().iter().enumerate() {
+        if face.flags == TileFlags::NONE {
+            continue;
+        }
+        let Some(pos) = map.face_id_to_center_pos_3d(fid as u32) else {
+            continue;
+        };
+        // One nested square per flag
+        let flags = TileFlags::ALL
+            .into_iter()
+            .filter(|f| face.flags.contains(*f));
+        for (i, flag) in flags.enumerate() {
+            gizmos.rect(
+                pos + offset + Vec3::Y * 0.01,
+                Quat::from_rotation_x(PI * 0.5),
+                Vec2::splat(0.85 - 0.15 * i as f32),
+                tile_flag_color(flag),
+            );
+        }
+    }
+}
+
+pub(super) const fn tile_flag_color(flag: TileFlags) -> Srgba {
+    match flag {
+        TileFlags::HAZARD => RED_500,
+        TileFlags::SLOW => AMBER_400,
+        TileFlags::NO_THROW => FUCHSIA_400,
+        TileFlags::MINION_ONLY => SKY_400,
+        TileFlags::PLAYER_ONLY => EMERALD_400,
+        _ => GRAY_400,
+    }
+}
+
+/// Outlines a face rectangle along the terrain, clipped to the map
+fn draw_region_outline(
+    gizmos: &mut Gizmos,
+    tilemap: &Tilemap,
+    offset: Vec3,
+    start: IVec2,
+    dims: UVec2,
+    color: Srgba,
+) {
+    let max = tilemap.vert_grid().dims().as_ivec2() - IVec2::ONE;
+    let min = start.clamp(IVec2::ZERO, max);
+    let end = (start + dims.as_ivec2()).clamp(IVec2::ZERO, max);
+    if min.cmpge(end).any() {
+        return;
+    }
+    let half_size = tilemap.size() * 0.5;
+    let point = |x: i32, y: i32| {
+        let vid = tilemap
+            .vert_grid()
+            .coord_to_id(UVec2::new(x as u32, y as u32));
+        let elevation = tilemap.vert_data()[vid as usize].elevation as f32;
+        Vec3::new(
+            x as f32 - half_size.x,
+            elevation * SLOPE_HEIGHT + 0.05,
+            y as f32 - half_size.y,
+        ) + offset
+    };
+    let top = (min.x..=end.x).map(|x| point(x, min.y));
+    let right = (min.y..=end.y).map(|y| point(end.x, y));
+    let bottom = (min.x..=end.x).rev().map(|x| point(x, end.y));
+    let left = (min.y..=end.y).rev().map(|y| point(min.x, y));
+    gizmos.linestrip(top.chain(right).chain(bottom).chain(left), color);
+}
+
+/// The spot under the cursor an ornament could be placed on, with placeholder mesh settings
+fn hovered_ornament_placement(state: &EditorState) -> Option<OrnamentalMeshBuilder> {
+    let grid = state.tilemap.face_grid();
+    let mesh_path = String::new();
+    let (rot, collider) = (Default::default(), false);
+
+    if let (Some(fid), Some(normal), Some(height)) = (
+        state.hovered_wall_ground,
+        state.hovered_wall_normal,
+        state.hovered_wall_height,
+    ) {
+        let coord = grid.id_to_coord(fid);
+        let side = match normal {
+            Pnormal3::OPO => {
+                return Some(OrnamentalMeshBuilder::Top(WallTopOrnamentalMesh {
+                    mesh_path,
+                    coord,
+                    rot,
+                    collider,
+                }))
+            }
+            Pnormal3::ONO => return None,
+            Pnormal3::NOO => Pnormal2::NO,
+            Pnormal3::OON => Pnormal2::ON,
+            Pnormal3::POO => Pnormal2::PO,
+            Pnormal3::OOP => Pnormal2::OP,
+        };
+        return Some(OrnamentalMeshBuilder::Side(WallSideOrnamentalMesh {
+            mesh_path,
+            coord,
+            side,
+            height,
+            rot,
+            collider,
+        }));
+    }
+
+    let fid = state.hovered_ground_face?;
+    Some(OrnamentalMeshBuilder::Ground(GroundOrnamentalMesh {
+        mesh_path,
+        coord: grid.id_to_coord(fid),
+        rot,
+        collider,
+    }))
+}
+
 fn _draw_vert_gizmos(
     mut gizmos: Gizmos,
     tilemap: Res<EditorState>,
@@ -508,9 +785,7 @@ fn update_hovered_states(
                     state.hovered_wall_ground = Some(fid);
                     state.hovered_wall_normal = Some(normal);
 
-                    let y_inc = ((state.tilemap.face_base_elevation(fid) as f32 * SLOPE_HEIGHT)
-                        / WALL_HEIGHT) as u32;
-                    let base_y = y_inc as f32 * WALL_HEIGHT;
+                    let base_y = state.tilemap.face_wall_base_height(fid);
                     state.hovered_wall_height = Some(((poi.y - base_y) / WALL_HEIGHT) as u32);
                 }
             } else {
@@ -544,19 +819,30 @@ fn change_control_mode(
         keys.just_pressed(KeyCode::Digit2),
         keys.just_pressed(KeyCode::Digit3),
         keys.just_pressed(KeyCode::Digit4),
+        keys.just_pressed(KeyCode::Digit5),
+        keys.just_pressed(KeyCode::Digit6),
+        keys.just_pressed(KeyCode::Digit7),
+        keys.just_pressed(KeyCode::Digit8),
+        keys.just_pressed(KeyCode::Digit9),
         keys.just_pressed(KeyCode::Digit0),
+        keys.just_pressed(KeyCode::Minus),
     ) {
-        (X, _, _, _, _) => next_mode.set(ControlMode::ShapeTerrain),
-        (_, X, _, _, _) => next_mode.set(ControlMode::ShapeWalls),
-        (_, _, X, _, _) => next_mode.set(ControlMode::Paint2D),
-        (_, _, _, X, _) => next_mode.set(ControlMode::PlaceGameObjects),
-        (_, _, _, _, X) => next_mode.set(ControlMode::AdminStuff),
+        (X, _, _, _, _, _, _, _, _, _, _) => next_mode.set(ControlMode::ShapeTerrain),
+        (_, X, _, _, _, _, _, _, _, _, _) => next_mode.set(ControlMode::ShapeWalls),
+        (_, _, X, _, _, _, _, _, _, _, _) => next_mode.set(ControlMode::Paint2D),
+        (_, _, _, X, _, _, _, _, _, _, _) => next_mode.set(ControlMode::PlaceGameObjects),
+        (_, _, _, _, X, _, _, _, _, _, _) => next_mode.set(ControlMode::PlaceOrnaments),
+        (_, _, _, _, _, X, _, _, _, _, _) => next_mode.set(ControlMode::FlattenTerrain),
+        (_, _, _, _, _, _, X, _, _, _, _) => next_mode.set(ControlMode::PaintTerrain3D),
+        (_, _, _, _, _, _, _, X, _, _, _) => next_mode.set(ControlMode::PaintWalls3D),
+        (_, _, _, _, _, _, _, _, X, _, _) => next_mode.set(ControlMode::Select),
+        (_, _, _, _, _, _, _, _, _, X, _) => next_mode.set(ControlMode::AdminStuff),
+        (_, _, _, _, _, _, _, _, _, _, X) => next_mode.set(ControlMode::PaintProperties),
         _ => {}
     }
 }
 
 fn perform_click_actions(
-    mut cmd: Commands,
     mut state: ResMut<EditorState>,
     mut egui_state: ResMut<ui::EguiState>,
     controls: Res<EditorControls>,
@@ -564,31 +850,57 @@ fn perform_click_actions(
     global_ui_state: Res<GlobalUiState>,
     mouse: Res<ButtonInput<MouseButton>>,
     keys: Res<ButtonInput<KeyCode>>,
-    sys: Res<oneshot::Systems>,
     mut defs: ResMut<ObjectDefStorage>,
+    mut ornaments: ResMut<OrnamentStorage>,
+    mut commit: EventWriter<CommitEdit>,
+    mut spawn: EventWriter<SpawnObject>,
 ) {
     let over_ui = global_ui_state.is_pointer_over_ui || global_ui_state.is_egui_input_focused;
-    let mut is_dirty = false;
+    if !global_ui_state.is_egui_input_focused {
+        if keys.just_pressed(KeyCode::BracketLeft) {
+            state.brush.radius = state.brush.radius.saturating_sub(1);
+        }
+        if keys.just_pressed(KeyCode::BracketRight) {
+            state.brush.radius = u32::min(state.brush.radius + 1, MAX_BRUSH_RADIUS);
+        }
+    }
+    // Also when the press starts off the map, so a drag onto it doesn't skip what the
+    // previous stroke visited
+    if mouse.just_pressed(MouseButton::Left) || mouse.just_released(MouseButton::Left) {
+        state.stroke.begin();
+    }
+    let mut edit = None;
     match control_mode.get() {
         ControlMode::ShapeTerrain => {
             let Some(fid) = state.hovered_ground_face else {
                 return;
             };
-            if !over_ui && mouse.just_pressed(MouseButton::Left) {
-                match keys.pressed(KeyCode::ShiftLeft) || keys.pressed(KeyCode::ShiftRight) {
-                    false => {
-                        controls
-                            .tilemap
-                            .raise_face_elevation(&mut state.tilemap, fid, 1);
-                        is_dirty = true;
+            if !over_ui && mouse.pressed(MouseButton::Left) {
+                let lower = keys.pressed(KeyCode::ShiftLeft) || keys.pressed(KeyCode::ShiftRight);
+                let state = &mut *state;
+                let center = state.tilemap.face_grid().id_to_coord(fid);
+                for (vid, weight) in state.brush.verts(&state.tilemap, center) {
+                    let amount = state.brush.amount(weight);
+                    if amount == 0 || !state.stroke.visit(vid) {
+                        continue;
                     }
-                    true => {
-                        controls
-                            .tilemap
-                            .lower_face_elevation(&mut state.tilemap, fid, 1);
-                        is_dirty = true;
+                    match lower {
+                        false => {
+                            controls
+                                .tilemap
+                                .raise_vert_elevation(&mut state.tilemap, vid, amount)
+                        }
+                        true => {
+                            controls
+                                .tilemap
+                                .lower_vert_elevation(&mut state.tilemap, vid, amount)
+                        }
                     }
                 }
+                edit = Some(CommitEdit::merged(match lower {
+                    false => "Raise terrain",
+                    true => "Lower terrain",
+                }));
             }
         }
         ControlMode::ShapeWalls => {
@@ -600,21 +912,73 @@ fn perform_click_actions(
                 },
             };
 
-            if !over_ui && mouse.just_pressed(MouseButton::Left) {
-                match keys.pressed(KeyCode::ShiftLeft) || keys.pressed(KeyCode::ShiftRight) {
-                    false => {
-                        controls.tilemap.raise_wall_height(&mut state.tilemap, fid);
-                        is_dirty = true;
+            if !over_ui && mouse.pressed(MouseButton::Left) {
+                let lower = keys.pressed(KeyCode::ShiftLeft) || keys.pressed(KeyCode::ShiftRight);
+                let state = &mut *state;
+                let center = state.tilemap.face_grid().id_to_coord(fid);
+                for (fid, _) in state.brush.faces(state.tilemap.face_grid(), center) {
+                    if !state.stroke.visit(fid) {
+                        continue;
                     }
-                    true => {
-                        controls.tilemap.lower_wall_height(&mut state.tilemap, fid);
-                        is_dirty = true;
+                    match lower {
+                        false => controls.tilemap.raise_wall_height(&mut state.tilemap, fid),
+                        true => controls.tilemap.lower_wall_height(&mut state.tilemap, fid),
                     }
                 }
+                edit = Some(CommitEdit::merged(match lower {
+                    false => "Raise wall",
+                    true => "Lower wall",
+                }));
+            }
+        }
+        ControlMode::FlattenTerrain => {
+            let Some(fid) = state.hovered_ground_face else {
+                return;
+            };
+            if !over_ui && mouse.just_pressed(MouseButton::Left) {
+                let sum = state
+                    .tilemap
+                    .face_id_to_vert_ids(fid)
+                    .map(|vid| state.tilemap.vert_data()[vid as usize].elevation)
+                    .iter()
+                    .sum::<u32>();
+                state.flatten_elevation = (sum + 2) / 4;
+            }
+            if !over_ui && mouse.pressed(MouseButton::Left) {
+                let state = &mut *state;
+                let center = state.tilemap.face_grid().id_to_coord(fid);
+                for (fid, _) in state.brush.faces(state.tilemap.face_grid(), center) {
+                    controls.tilemap.flatten_face_elevation(
+                        &mut state.tilemap,
+                        fid,
+                        state.flatten_elevation,
+                    );
+                }
+                edit = Some(CommitEdit::merged("Flatten terrain"));
+            }
+        }
+        ControlMode::PaintTerrain3D => {
+            if state.hovered_wall_ground.is_some() {
+                return;
+            }
+            let Some(fid) = state.hovered_ground_face else {
+                return;
+            };
+            let Some(coord) = state.selected_tileset_coords else {
+                return;
+            };
+            if !over_ui && mouse.pressed(MouseButton::Left) {
+                let state = &mut *state;
+                let tid = state.tileset.grid().coord_to_id(coord);
+                let center = state.tilemap.face_grid().id_to_coord(fid);
+                for (fid, _) in state.brush.faces(state.tilemap.face_grid(), center) {
+                    controls
+                        .tilemap
+                        .paint_ground_face(&mut state.tilemap, fid, tid);
+                }
+                edit = Some(CommitEdit::merged("Paint ground"));
             }
         }
-        ControlMode::FlattenTerrain => todo!(),
-        ControlMode::PaintTerrain3D => todo!(),
         ControlMode::Paint2D => {
             if keys.just_pressed(KeyCode::Tab) {
                 egui_state.paint_widget_open = !egui_state.paint_widget_open;
@@ -639,7 +1003,7 @@ fn perform_click_actions(
                                 height,
                                 tid,
                             );
-                            is_dirty = true;
+                            edit = Some(CommitEdit::new("Paint wall"));
                         }
                     } else {
                         // paint terrain
@@ -652,13 +1016,50 @@ fn perform_click_actions(
                             controls
                                 .tilemap
                                 .paint_ground_face(&mut state.tilemap, fid, tid);
-                            is_dirty = true;
+                            edit = Some(CommitEdit::new("Paint ground"));
                         }
                     }
                 }
             }
         }
-        ControlMode::PaintWalls3D => todo!(),
+        ControlMode::PaintWalls3D => {
+            let (Some(fid), Some(normal), Some(height)) = (
+                state.hovered_wall_ground,
+                state.hovered_wall_normal,
+                state.hovered_wall_height,
+            ) else {
+                return;
+            };
+            let Some(coord) = state.selected_tileset_coords else {
+                return;
+            };
+            if !over_ui && mouse.pressed(MouseButton::Left) {
+                let tid = state.tileset.grid().coord_to_id(coord);
+                controls
+                    .tilemap
+                    .paint_wall_face(&mut state.tilemap, fid, normal, height, tid);
+                edit = Some(CommitEdit::merged("Paint wall"));
+            }
+        }
+        ControlMode::PaintProperties => {
+            let Some(fid) = state.hovered_ground_face else {
+                return;
+            };
+            if !over_ui && mouse.pressed(MouseButton::Left) {
+                let clear = keys.pressed(KeyCode::ShiftLeft) || keys.pressed(KeyCode::ShiftRight);
+                let state = &mut *state;
+                let center = state.tilemap.face_grid().id_to_coord(fid);
+                for (fid, _) in state.brush.faces(state.tilemap.face_grid(), center) {
+                    state.tilemap.face_data_mut()[fid as usize]
+                        .flags
+                        .set(state.painted_flag, !clear);
+                }
+                edit = Some(CommitEdit::merged(match clear {
+                    false => "Paint tile flag",
+                    true => "Clear tile flag",
+                }));
+            }
+        }
         ControlMode::PlaceGameObjects => {
             if !over_ui && mouse.just_pressed(MouseButton::Left) {
                 let Some(fid) = state.hovered_ground_face else {
@@ -676,12 +1077,215 @@ fn perform_click_actions(
                 }
             }
         }
+        ControlMode::PlaceOrnaments => {
+            if over_ui {
+                return;
+            }
+            let shift = keys.pressed(KeyCode::ShiftLeft) || keys.pressed(KeyCode::ShiftRight);
+            let ctrl = keys.pressed(KeyCode::ControlLeft) || keys.pressed(KeyCode::ControlRight);
+
+            if !ctrl && keys.just_pressed(KeyCode::KeyR) {
+                let steps = if shift { -1 } else { 1 };
+                if let Some(id) = ornaments.selected_id {
+                    let rot = ornaments.storage[id as usize].rot_mut();
+                    *rot = rot.rotated(steps);
+                    edit = Some(CommitEdit::new("Rotate ornament"));
+                } else {
+                    egui_state.ornament_widget.rot = egui_state.ornament_widget.rot.rotated(steps);
+                }
+            }
+
+            let placement = hovered_ornament_placement(&state);
+            if let (true, Some(placement)) = (mouse.just_pressed(MouseButton::Left), placement) {
+                let existing = ornaments
+                    .storage
+                    .iter()
+                    .position(|o| o.same_placement(&placement));
+
+                if shift {
+                    ornaments.storage.retain(|o| !o.same_placement(&placement));
+                    ornaments.selected_id = None;
+                    edit = Some(CommitEdit::new("Delete ornament"));
+                } else if let Some(id) = existing {
+                    ornaments.selected_id = Some(id as u32);
+                } else if egui_state.ornament_widget.mesh_path.is_empty() {
+                    warn!("Enter a mesh path before placing ornaments");
+                } else {
+                    let ornament = egui_state.ornament_widget.make_ornament(placement);
+                    ornaments.storage.push(ornament);
+                    ornaments.selected_id = Some(ornaments.storage.len() as u32 - 1);
+                    edit = Some(CommitEdit::new("Place ornament"));
+                }
+            }
+        }
+        ControlMode::Select => {
+            if global_ui_state.is_egui_input_focused {
+                return;
+            }
+            let ctrl = keys.pressed(KeyCode::ControlLeft) || keys.pressed(KeyCode::ControlRight);
+            if ctrl && keys.just_pressed(KeyCode::KeyC) {
+                state.copy_selection(&defs.storage);
+            }
+            if ctrl && keys.just_pressed(KeyCode::KeyV) && state.clipboard.is_some() {
+                state.pasting = true;
+            }
+
+            if !state.pasting {
+                if keys.just_pressed(KeyCode::Escape) {
+                    state.selection = None;
+                }
+                let Some(fid) = state.hovered_ground_face else {
+                    return;
+                };
+                let coord = state.tilemap.face_grid().id_to_coord(fid);
+                if !over_ui && mouse.just_pressed(MouseButton::Left) {
+                    state.selection = Some((coord, coord));
+                } else if let (true, Some((_, end))) =
+                    (mouse.pressed(MouseButton::Left), &mut state.selection)
+                {
+                    *end = coord;
+                }
+                return;
+            }
+
+            if keys.just_pressed(KeyCode::Escape) {
+                state.pasting = false;
+            }
+            let state = &mut *state;
+            let Some(clipboard) = &mut state.clipboard else {
+                return;
+            };
+            if keys.just_pressed(KeyCode::KeyR) {
+                *clipboard = clipboard.rotated();
+            }
+            if keys.just_pressed(KeyCode::KeyF) {
+                *clipboard = clipboard.mirrored();
+            }
+            if !over_ui && mouse.just_pressed(MouseButton::Left) {
+                let Some(start) = state.paste_start() else {
+                    return;
+                };
+                let Some(clipboard) = &state.clipboard else {
+                    return;
+                };
+                let first = defs.storage.len() as u32;
+                clipboard.paste(&mut state.tilemap, &mut defs.storage, start);
+                for id in first..defs.storage.len() as u32 {
+                    spawn.send(SpawnObject { id });
+                }
+                edit = Some(CommitEdit::new("Paste"));
+            }
+        }
         ControlMode::AdminStuff => {}
     }
-    if is_dirty {
+    if let Some(edit) = edit {
+        commit.send(edit);
+    }
+}
+
+fn history_shortcuts(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    global_ui_state: Res<GlobalUiState>,
+    mut history: ResMut<EditHistory>,
+    mut actions: EventWriter<HistoryAction>,
+) {
+    // A drag or click is over, the next edit starts a new step
+    if mouse.get_just_released().next().is_some() {
+        history.seal();
+    }
+
+    if global_ui_state.is_egui_input_focused {
+        return;
+    }
+    let ctrl = keys.pressed(KeyCode::ControlLeft) || keys.pressed(KeyCode::ControlRight);
+    let shift = keys.pressed(KeyCode::ShiftLeft) || keys.pressed(KeyCode::ShiftRight);
+    if ctrl && keys.just_pressed(KeyCode::KeyZ) {
+        actions.send(match shift {
+            false => HistoryAction::Undo,
+            true => HistoryAction::Redo,
+        });
+    }
+}
+
+fn commit_edits(
+    mut cmd: Commands,
+    mut evs: EventReader<CommitEdit>,
+    mut history: ResMut<EditHistory>,
+    mut state: ResMut<EditorState>,
+    mut defs: ResMut<ObjectDefStorage>,
+    mut ornaments: ResMut<OrnamentStorage>,
+    sys: Res<oneshot::Systems>,
+) {
+    for ev in evs.read() {
+        let doc = Document {
+            tilemap: &mut state.tilemap,
+            objects: &mut defs.storage,
+            ornaments: &mut ornaments.storage,
+        };
+        let Some(touched) = history.commit(&ev.label, ev.merge, doc) else {
+            continue;
+        };
+        // Object markers are kept up to date by the object editor itself
+        recreate_touched(
+            &mut cmd,
+            &sys,
+            Touched {
+                objects: false,
+                ..touched
+            },
+        );
+    }
+}
+
+fn apply_history_actions(
+    mut cmd: Commands,
+    mut evs: EventReader<HistoryAction>,
+    mut history: ResMut<EditHistory>,
+    mut state: ResMut<EditorState>,
+    mut defs: ResMut<ObjectDefStorage>,
+    mut ornaments: ResMut<OrnamentStorage>,
+    sys: Res<oneshot::Systems>,
+) {
+    for action in evs.read() {
+        let doc = Document {
+            tilemap: &mut state.tilemap,
+            objects: &mut defs.storage,
+            ornaments: &mut ornaments.storage,
+        };
+        let touched = match *action {
+            HistoryAction::Undo => history.undo(doc),
+            HistoryAction::Redo => history.redo(doc),
+            HistoryAction::JumpTo(position) => history.jump_to(position, doc),
+            HistoryAction::Clear => {
+                history.reset(doc.tilemap, doc.objects, doc.ornaments);
+                None
+            }
+        };
+        let Some(touched) = touched else {
+            continue;
+        };
+        if touched.objects {
+            defs.selected_id = None;
+        }
+        if touched.ornaments {
+            ornaments.selected_id = None;
+        }
+        recreate_touched(&mut cmd, &sys, touched);
+    }
+}
+
+fn recreate_touched(cmd: &mut Commands, sys: &oneshot::Systems, touched: Touched) {
+    if touched.tilemap {
         cmd.run_system(sys.recreate_scene);
+    }
+    // Both are placed relative to the terrain
+    if touched.tilemap || touched.objects {
         cmd.run_system(sys.recreate_object_markers);
     }
+    if touched.tilemap || touched.ornaments {
+        cmd.run_system(sys.recreate_ornaments);
+    }
 }
 
 fn draw_hovered_tile_gizmo(
@@ -689,6 +1293,7 @@ fn draw_hovered_tile_gizmo(
     tilemap: Res<EditorState>,
     transform: Query<&Transform, With<TilemapGroundMesh>>,
     state: Res<EditorState>,
+    control_mode: Res<State<ControlMode>>,
 ) {
     let offset = transform.single().translation;
     let map = &tilemap.tilemap;
@@ -705,6 +1310,29 @@ fn draw_hovered_tile_gizmo(
         LIME_300,
     );
 
+    if matches!(
+        control_mode.get(),
+        ControlMode::ShapeTerrain
+            | ControlMode::ShapeWalls
+            | ControlMode::FlattenTerrain
+            | ControlMode::PaintTerrain3D
+            | ControlMode::PaintProperties
+    ) && state.brush.radius > 0
+    {
+        let center = map.face_grid().id_to_coord(hovered);
+        for (fid, weight) in state.brush.faces(map.face_grid(), center) {
+            let Some(pos) = map.face_id_to_center_pos_3d(fid) else {
+                continue;
+            };
+            gizmos.rect(
+                pos + offset,
+                Quat::from_rotation_x(PI * 0.5),
+                Vec2::splat(0.25 + 0.5 * weight),
+                LIME_300.with_alpha(0.5),
+            );
+        }
+    }
+
     let from = Vec3::new(pos.x, pos.y + 0.5, pos.z) + offset;
     let to = Vec3::new(pos.x, pos.y, pos.z) + offset;
 
@@ -714,22 +1342,36 @@ fn draw_hovered_tile_gizmo(
 mod ui {
     use super::{
         oneshot::{ExportLevelScenePath, Systems},
-        ControlMode, DespawnObject, EditorState, ObjectDefStorage, SelectedObjectChanged,
-        SpawnObject, DEFAULT_EDITOR_EXPORT_PATH, DEFAULT_EDITOR_SAVE_PATH, START_ELEVATION,
+        tile_flag_color, ControlMode, DespawnObject, EditorControls, EditorState, ObjectDefStorage,
+        OrnamentStorage, SelectedObjectChanged, SpawnObject, DEFAULT_EDITOR_EXPORT_PATH,
+        DEFAULT_EDITOR_SAVE_PATH, DEFAULT_EDITOR_STAMP_PATH, START_ELEVATION,
     };
     use crate::{
-        framework::tileset::{TILESET_PATH_DIFFUSE, TILESET_TEXTURE_DIMS, TILESET_TILE_DIMS},
-        game::objects::definitions::ObjectDefKind,
-        tooling::editor::{
-            tilemap_asset::TilemapRon,
-            widgets::{
+        framework::{
+            tilemap::{TileFlags, Tilemap},
+            tileset::{TILESET_PATH_DIFFUSE, TILESET_TEXTURE_DIMS, TILESET_TILE_DIMS},
+        },
+        game::{minion::collector::MinionStorage, objects::definitions::ObjectDefKind},
+        tooling::{
+            editor::heightmap::Heightmap,
+            editor::history::{CommitEdit, EditHistory, HistoryAction},
+            editor::stamp::TilemapStamp,
+            editor::tilemap_controls::TilemapControls,
+            editor::widgets::{
+                brush::BrushWidget,
                 file_selector::{
                     FileSelectorWidget, FileSelectorWidgetResult, FileSelectorWidgetSettings,
                 },
+                generator::GeneratorWidget,
+                level_metadata::LevelMetadataWidget,
                 object_def::{ObjectDefResult, ObjectDefWidget},
-                tilemap_size::TilemapSizeWidget,
+                ornament::{OrnamentResult, OrnamentWidget},
+                tilemap_size::{TilemapSizeWidget, MAX_GRID_DIMS},
                 tileset::TilesetWidget,
             },
+            level_generator,
+            level_solver::PuzzleReport,
+            tilemap_asset::TilemapRon,
         },
     };
     use bevy::{prelude::*, utils::hashbrown::HashMap, window::PrimaryWindow};
@@ -749,6 +1391,14 @@ mod ui {
         pub file_widget: Option<FileWidget>,
         pub resize_widget: Option<TilemapSizeWidget>,
         pub object_def_widget: ObjectDefWidget,
+        pub metadata_widget: LevelMetadataWidget,
+        pub ornament_widget: OrnamentWidget,
+        pub brush_widget: BrushWidget,
+        /// Imported heightmap waiting for the tilemap to be resized to fit it
+        pub pending_heightmap: Option<Heightmap>,
+        pub generator_widget: GeneratorWidget,
+        /// Solvability of the level's color puzzles, updated after each edit
+        pub puzzle_report: Option<PuzzleReport>,
     }
 
     impl FromWorld for EguiState {
@@ -787,6 +1437,12 @@ mod ui {
                 file_widget: None,
                 resize_widget: None,
                 object_def_widget,
+                metadata_widget: LevelMetadataWidget,
+                ornament_widget: OrnamentWidget::default(),
+                brush_widget: BrushWidget,
+                pending_heightmap: None,
+                generator_widget: GeneratorWidget::default(),
+                puzzle_report: None,
             }
         }
     }
@@ -840,12 +1496,60 @@ mod ui {
                 ),
             }
         }
+        pub fn save_stamp() -> Self {
+            Self {
+                mode: FileWidgetMode::SaveStamp,
+                widget: FileSelectorWidget::new(
+                    DEFAULT_EDITOR_STAMP_PATH,
+                    FileSelectorWidgetSettings::SAVE,
+                ),
+            }
+        }
+        pub fn load_stamp() -> Self {
+            Self {
+                mode: FileWidgetMode::LoadStamp,
+                widget: FileSelectorWidget::new(
+                    DEFAULT_EDITOR_STAMP_PATH,
+                    FileSelectorWidgetSettings::LOAD,
+                ),
+            }
+        }
+        pub fn import_heightmap() -> Self {
+            Self {
+                mode: FileWidgetMode::ImportHeightmap,
+                widget: FileSelectorWidget::new(
+                    DEFAULT_EDITOR_SAVE_PATH,
+                    FileSelectorWidgetSettings {
+                        select_text: "Import",
+                        file_extension: "png",
+                        ..FileSelectorWidgetSettings::LOAD
+                    },
+                ),
+            }
+        }
+        pub fn export_heightmap() -> Self {
+            Self {
+                mode: FileWidgetMode::ExportHeightmap,
+                widget: FileSelectorWidget::new(
+                    DEFAULT_EDITOR_SAVE_PATH,
+                    FileSelectorWidgetSettings {
+                        select_text: "Export",
+                        file_extension: "png",
+                        ..FileSelectorWidgetSettings::SAVE
+                    },
+                ),
+            }
+        }
     }
 
     pub(super) enum FileWidgetMode {
         LoadTilemap,
         SaveTilemap,
         ExportLevel,
+        LoadStamp,
+        SaveStamp,
+        ImportHeightmap,
+        ExportHeightmap,
     }
 
     #[rustfmt::skip]
@@ -858,15 +1562,9 @@ mod ui {
             // ObjectDefKind::ControlPanel    => "editor-only/404.png",
             // ObjectDefKind::PressurePlate   => "editor-only/404.png",
             // ObjectDefKind::Key             => "editor-only/404.png",
-            // ObjectDefKind::KeyDoor         => "editor-only/404.png",
-            // ObjectDefKind::Barrier         => "editor-only/404.png",
-            // ObjectDefKind::PowerOutlet     => "editor-only/404.png",
             // ObjectDefKind::EmptySocket     => "editor-only/404.png",
-            // ObjectDefKind::ExplosiveBarrel => "editor-only/404.png",
             // ObjectDefKind::Anglerfish      => "editor-only/404.png",
-            // ObjectDefKind::Ventilation     => "editor-only/404.png",
             // ObjectDefKind::Well            => "editor-only/404.png",
-            // ObjectDefKind::BigHole         => "editor-only/404.png",
             // ObjectDefKind::LaserDrill      => "editor-only/404.png",
             // ObjectDefKind::VaultDoor       => "editor-only/404.png",
             // ObjectDefKind::Painting        => "editor-only/404.png",
@@ -878,6 +1576,14 @@ mod ui {
             // ObjectDefKind::WineBottle      => "editor-only/404.png",
             ObjectDefKind::DestructibleTargetTest => "editor-only/404.png",
             ObjectDefKind::PhysicsCubesTest       => "editor-only/404.png",
+            ObjectDefKind::LedgeDrop       => "editor-only/404.png",
+            ObjectDefKind::Ventilation     => "editor-only/404.png",
+            ObjectDefKind::BigHole         => "editor-only/404.png",
+            ObjectDefKind::PowerOutlet     => "editor-only/404.png",
+            ObjectDefKind::KeyDoor         => "editor-only/404.png",
+            ObjectDefKind::Barrier         => "editor-only/404.png",
+            ObjectDefKind::HeavyCrate      => "editor-only/404.png",
+            ObjectDefKind::ExplosiveBarrel => "editor-only/404.png",
         }
     }
 
@@ -939,10 +1645,15 @@ mod ui {
         sys: Res<Systems>,
         mut export_level_scene_path: ResMut<ExportLevelScenePath>,
         mut defs: ResMut<ObjectDefStorage>,
+        mut ornaments: ResMut<OrnamentStorage>,
+        mut commit: EventWriter<CommitEdit>,
+        mut history_actions: EventWriter<HistoryAction>,
+        controls: Res<EditorControls>,
     ) {
         let win = win.single();
         let ctx = ctxs.ctx_for_window_mut(win);
 
+        let mut heightmap_import = None;
         let mut file_select_open = true;
         match &mut state.file_widget {
             Some(widget) => {
@@ -951,6 +1662,10 @@ mod ui {
                     FileWidgetMode::LoadTilemap => "Load Tilemap",
                     FileWidgetMode::SaveTilemap => "Save Tilemap",
                     FileWidgetMode::ExportLevel => "Export Tilemap",
+                    FileWidgetMode::LoadStamp => "Load Stamp",
+                    FileWidgetMode::SaveStamp => "Save Stamp",
+                    FileWidgetMode::ImportHeightmap => "Import Heightmap",
+                    FileWidgetMode::ExportHeightmap => "Export Heightmap",
                 };
                 egui::Window::new(title)
                     .id("file_selector_widget_window".into())
@@ -964,10 +1679,15 @@ mod ui {
                         match widget.mode {
                             FileWidgetMode::LoadTilemap => match TilemapRon::read(&path) {
                                 Ok(ron) => {
+                                    editor_state.metadata = ron.metadata;
                                     editor_state.tilemap = ron.tilemap;
                                     cmd.run_system(sys.recreate_scene);
                                     defs.storage = ron.objects;
                                     cmd.run_system(sys.recreate_object_markers);
+                                    ornaments.storage = ron.meshes;
+                                    ornaments.selected_id = None;
+                                    cmd.run_system(sys.recreate_ornaments);
+                                    history_actions.send(HistoryAction::Clear);
                                 }
                                 Err(e) => {
                                     error!("Failed to load tilemap {path:?}: {e:?}",)
@@ -975,9 +1695,10 @@ mod ui {
                             },
                             FileWidgetMode::SaveTilemap => {
                                 let ron = TilemapRon::new(
+                                    editor_state.metadata.clone(),
                                     editor_state.tilemap.clone(),
                                     defs.storage.to_owned(),
-                                    vec![], // Todo!
+                                    ornaments.storage.to_owned(),
                                 );
                                 if let Err(e) = ron.write(&path) {
                                     error!("Failed to save tilemap to {path:?}. {e:?}",);
@@ -987,6 +1708,38 @@ mod ui {
                                 export_level_scene_path.0 = path.to_string_lossy().into();
                                 cmd.run_system(sys.export_level_scene);
                             }
+                            FileWidgetMode::LoadStamp => match TilemapStamp::read(&path) {
+                                Ok(stamp) => {
+                                    editor_state.clipboard = Some(stamp);
+                                    editor_state.pasting = true;
+                                }
+                                Err(e) => {
+                                    error!("Failed to load stamp {path:?}: {e:?}",)
+                                }
+                            },
+                            FileWidgetMode::SaveStamp => {
+                                if let Some(stamp) = &editor_state.clipboard {
+                                    if let Err(e) = stamp.write(&path) {
+                                        error!("Failed to save stamp to {path:?}. {e:?}",);
+                                    }
+                                }
+                            }
+                            FileWidgetMode::ImportHeightmap => {
+                                let max_elevation = controls.tilemap.max_elevation();
+                                match Heightmap::read(&path, max_elevation) {
+                                    Ok(heightmap) => heightmap_import = Some(heightmap),
+                                    Err(e) => {
+                                        error!("Failed to import heightmap {path:?}: {e:?}",)
+                                    }
+                                }
+                            }
+                            FileWidgetMode::ExportHeightmap => {
+                                let max_elevation = controls.tilemap.max_elevation();
+                                let heightmap = Heightmap::from_tilemap(&editor_state.tilemap);
+                                if let Err(e) = heightmap.write(&path, max_elevation) {
+                                    error!("Failed to export heightmap to {path:?}. {e:?}",);
+                                }
+                            }
                         }
                         file_select_open = false;
                     }
@@ -1005,6 +1758,26 @@ mod ui {
             let _ = state.file_widget.take();
         }
 
+        // A heightmap of a different size goes through the resize widget first
+        if let Some(heightmap) = heightmap_import {
+            let dims = editor_state.tilemap.dims();
+            if heightmap.dims() == dims {
+                apply_heightmap(&heightmap, &mut editor_state.tilemap, &controls.tilemap);
+                commit.send(CommitEdit::new("Import heightmap"));
+            } else if heightmap.dims().cmpgt(MAX_GRID_DIMS).any() {
+                error!(
+                    "Heightmap fits a {} tilemap, which is larger than the max of {}",
+                    heightmap.dims(),
+                    MAX_GRID_DIMS
+                );
+            } else {
+                state.resize_widget = Some(
+                    TilemapSizeWidget::new(dims, START_ELEVATION).with_target(heightmap.dims()),
+                );
+                state.pending_heightmap = Some(heightmap);
+            }
+        }
+
         let mut resize_widget_open = true;
         if let Some(widget) = &mut state.resize_widget {
             let mut resize = None;
@@ -1018,6 +1791,7 @@ mod ui {
                 let mut grid = editor_state.tilemap.face_grid().clone();
 
                 let mut coord_remap = HashMap::<u32, UVec2>::new();
+                let mut ornament_coord_remap = HashMap::<u32, UVec2>::new();
 
                 for (dst, src) in grid.resize_anchored(dims, anchor).enumerate() {
                     let dst_coord = grid.id_to_coord(dst as u32);
@@ -1028,18 +1802,42 @@ mod ui {
                                 coord_remap.insert(id as u32, dst_coord);
                             }
                         }
+                        for (id, ornament) in ornaments.storage.iter().enumerate() {
+                            if ornament.coord() == src_coord {
+                                ornament_coord_remap.insert(id as u32, dst_coord);
+                            }
+                        }
                     }
                 }
                 for (id, coord) in coord_remap {
                     defs.storage[id as usize].coord = coord;
                 }
+                // Ornaments on faces cut off by the resize go away with them
+                let storage = std::mem::take(&mut ornaments.storage);
+                ornaments.storage = storage
+                    .into_iter()
+                    .enumerate()
+                    .filter_map(|(id, mut ornament)| {
+                        *ornament.coord_mut() = *ornament_coord_remap.get(&(id as u32))?;
+                        Some(ornament)
+                    })
+                    .collect();
+                ornaments.selected_id = None;
 
                 editor_state
                     .tilemap
                     .resize_anchored(dims, anchor, elevation);
 
-                cmd.run_system(sys.recreate_scene);
-                cmd.run_system(sys.recreate_object_markers);
+                match state.pending_heightmap.take() {
+                    Some(heightmap) if heightmap.dims() == dims => {
+                        apply_heightmap(&heightmap, &mut editor_state.tilemap, &controls.tilemap);
+                        commit.send(CommitEdit::new("Import heightmap"));
+                        resize_widget_open = false;
+                    }
+                    _ => {
+                        commit.send(CommitEdit::new("Resize tilemap"));
+                    }
+                }
             }
             if keys.just_pressed(KeyCode::Escape) {
                 resize_widget_open = false;
@@ -1047,6 +1845,7 @@ mod ui {
         }
         if !resize_widget_open {
             let _ = state.resize_widget.take();
+            let _ = state.pending_heightmap.take();
         }
 
         match editor_mode.get() {
@@ -1067,10 +1866,178 @@ mod ui {
                     });
                 }
             }
+            ControlMode::PaintTerrain3D | ControlMode::PaintWalls3D => {
+                egui::SidePanel::left("left_side").show(ctx, |ui| {
+                    if *editor_mode.get() == ControlMode::PaintTerrain3D {
+                        state.brush_widget.show(ui, &mut editor_state.brush, false);
+                        ui.separator();
+                    }
+                    if let Some(new_tile_coord) = state.paint_widget.show(ui) {
+                        editor_state.selected_tileset_coords = Some(new_tile_coord);
+                    }
+                });
+            }
+            ControlMode::ShapeTerrain | ControlMode::ShapeWalls | ControlMode::FlattenTerrain => {
+                let elevation = *editor_mode.get() == ControlMode::ShapeTerrain;
+                egui::SidePanel::left("left_side").show(ctx, |ui| {
+                    state
+                        .brush_widget
+                        .show(ui, &mut editor_state.brush, elevation);
+                });
+            }
+            ControlMode::PaintProperties => {
+                egui::SidePanel::left("left_side").show(ctx, |ui| {
+                    state.brush_widget.show(ui, &mut editor_state.brush, false);
+                    ui.separator();
+                    ui.heading("Tile Flags");
+                    ui.label("Click: set, Shift+Click: clear");
+                    for flag in TileFlags::ALL {
+                        let color = tile_flag_color(flag).to_u8_array();
+                        let text = egui::RichText::new(flag.as_str())
+                            .color(egui::Color32::from_rgb(color[0], color[1], color[2]));
+                        ui.radio_value(&mut editor_state.painted_flag, flag, text);
+                    }
+                });
+            }
+            ControlMode::Select => {
+                egui::SidePanel::left("left_side").show(ctx, |ui| {
+                    ui.heading("Selection");
+                    ui.label("Drag: select, Ctrl+C: copy, Ctrl+V: paste");
+                    ui.label("While pasting R: rotate, F: mirror, Esc: cancel");
+                    match editor_state.selection_rect() {
+                        Some((start, dims)) => ui.label(format!(
+                            "{}x{} at {{{}:{}}}",
+                            dims.x, dims.y, start.x, start.y
+                        )),
+                        None => ui.label("Nothing selected"),
+                    };
+                    ui.horizontal(|ui| {
+                        if ui.button("Copy").clicked() {
+                            editor_state.copy_selection(&defs.storage);
+                        }
+                        let has_clipboard = editor_state.clipboard.is_some();
+                        if ui
+                            .add_enabled(has_clipboard, egui::Button::new("Paste"))
+                            .clicked()
+                        {
+                            editor_state.pasting = true;
+                        }
+                    });
+
+                    ui.separator();
+                    ui.heading("Clipboard");
+                    if let Some(clipboard) = &mut editor_state.clipboard {
+                        let dims = clipboard.dims();
+                        ui.label(format!(
+                            "{}x{}, {} objects",
+                            dims.x,
+                            dims.y,
+                            clipboard.objects.len()
+                        ));
+                        ui.horizontal(|ui| {
+                            if ui.button("Rotate").clicked() {
+                                *clipboard = clipboard.rotated();
+                            }
+                            if ui.button("Mirror").clicked() {
+                                *clipboard = clipboard.mirrored();
+                            }
+                        });
+                    } else {
+                        ui.label("Empty");
+                    }
+                    ui.horizontal(|ui| {
+                        let has_clipboard = editor_state.clipboard.is_some();
+                        if ui
+                            .add_enabled(has_clipboard, egui::Button::new("Save Stamp"))
+                            .clicked()
+                        {
+                            state.file_widget = Some(FileWidget::save_stamp());
+                        }
+                        if ui.button("Load Stamp").clicked() {
+                            state.file_widget = Some(FileWidget::load_stamp());
+                        }
+                    });
+                });
+            }
+            ControlMode::AdminStuff => {
+                let mut generate = false;
+                egui::SidePanel::left("left_side").show(ctx, |ui| {
+                    state.metadata_widget.show(ui, &mut editor_state.metadata);
+                    ui.separator();
+                    ui.heading("Heightmap");
+                    ui.horizontal(|ui| {
+                        if ui.button("Import").clicked() {
+                            state.file_widget = Some(FileWidget::import_heightmap());
+                        }
+                        if ui.button("Export").clicked() {
+                            state.file_widget = Some(FileWidget::export_heightmap());
+                        }
+                    });
+                    ui.separator();
+                    ui.heading("Puzzle");
+                    match &state.puzzle_report {
+                        Some(report) if report.is_solvable() => {
+                            ui.label(format!("Solvable, {} targets", report.targets.len()));
+                            for warning in report.warnings.iter() {
+                                ui.label(warning);
+                            }
+                        }
+                        Some(report) => {
+                            for error in report.errors.iter() {
+                                ui.colored_label(ui.visuals().warn_fg_color, error);
+                            }
+                        }
+                        None => {}
+                    }
+                    ui.separator();
+                    generate = state.generator_widget.show(ui);
+                });
+                if generate {
+                    let params = &state.generator_widget.params;
+                    match level_generator::generate_level(params, &editor_state.tileset) {
+                        Ok((scene, _)) => {
+                            editor_state.metadata = scene.metadata;
+                            editor_state.tilemap = scene.tilemap;
+                            defs.storage = scene.objects;
+                            defs.selected_id = None;
+                            cmd.run_system(sys.recreate_object_markers);
+                            ornaments.storage = scene.meshes;
+                            ornaments.selected_id = None;
+                            commit.send(CommitEdit::new("Generate level"));
+                        }
+                        Err(e) => error!("Failed to generate a level: {e:?}"),
+                    }
+                }
+            }
             _ => {}
         }
     }
 
+    pub(super) fn update_puzzle_report(
+        mut state: ResMut<EguiState>,
+        editor_state: Res<EditorState>,
+        defs: Res<ObjectDefStorage>,
+        history: Res<EditHistory>,
+    ) {
+        if state.puzzle_report.is_some() && !history.is_changed() {
+            return;
+        }
+        let tilemap = &editor_state.tilemap;
+        let objects = defs
+            .storage
+            .iter()
+            .map(|def| def.build(tilemap))
+            .collect::<Vec<_>>();
+        let storage = MinionStorage::player_start();
+        state.puzzle_report = Some(PuzzleReport::new(tilemap, &objects, &storage));
+    }
+
+    fn apply_heightmap(heightmap: &Heightmap, tilemap: &mut Tilemap, controls: &TilemapControls) {
+        if let Err(e) = heightmap.apply(tilemap, controls) {
+            error!("Failed to apply heightmap: {e:?}");
+        }
+    }
+
     pub(super) fn update_object_def_ui(
         mut ctxs: EguiContexts,
         mut state: ResMut<EguiState>,
@@ -1082,6 +2049,7 @@ mod ui {
         mut evs: EventWriter<SelectedObjectChanged>,
         mut spawn: EventWriter<SpawnObject>,
         mut despawn: EventWriter<DespawnObject>,
+        mut commit: EventWriter<CommitEdit>,
     ) {
         if *editor_mode.get() != ControlMode::PlaceGameObjects {
             return;
@@ -1102,6 +2070,7 @@ mod ui {
                 ObjectDefResult::New(id) => {
                     spawn.send(SpawnObject { id });
                     evs.send(SelectedObjectChanged::Id(id));
+                    commit.send(CommitEdit::new("Add object"));
                 }
                 ObjectDefResult::SelectedChanged(id) => {
                     evs.send(SelectedObjectChanged::Id(id));
@@ -1109,15 +2078,103 @@ mod ui {
                 ObjectDefResult::ValueChanged(id) => {
                     despawn.send(DespawnObject { id });
                     spawn.send(SpawnObject { id });
+                    commit.send(CommitEdit::merged(format!("Edit object {id}")));
                 }
-                ObjectDefResult::Deleted(id) => {
+                ObjectDefResult::Deleted(_) => {
+                    // Ids past the deleted one shifted, so respawn all markers
                     defs.selected_id = None;
-                    despawn.send(DespawnObject { id });
+                    for id in 0..=defs.storage.len() as u32 {
+                        despawn.send(DespawnObject { id });
+                    }
+                    for id in 0..defs.storage.len() as u32 {
+                        spawn.send(SpawnObject { id });
+                    }
+                    commit.send(CommitEdit::new("Delete object"));
                 }
             }
         });
     }
 
+    pub(super) fn update_ornament_ui(
+        mut ctxs: EguiContexts,
+        mut state: ResMut<EguiState>,
+        editor_state: Res<EditorState>,
+        editor_mode: Res<State<ControlMode>>,
+        mut ornaments: ResMut<OrnamentStorage>,
+        mut commit: EventWriter<CommitEdit>,
+    ) {
+        if *editor_mode.get() != ControlMode::PlaceOrnaments {
+            return;
+        }
+        let ctx = ctxs.ctx_mut();
+
+        egui::SidePanel::left("left_side").show(ctx, |ui| {
+            let selected_id = ornaments.selected_id;
+            match state.ornament_widget.show(
+                ui,
+                &mut ornaments.storage,
+                selected_id,
+                &editor_state.tilemap,
+            ) {
+                OrnamentResult::Ok => {}
+                OrnamentResult::SelectedChanged(id) => {
+                    ornaments.selected_id = Some(id);
+                }
+                OrnamentResult::ValueChanged => {
+                    commit.send(CommitEdit::merged("Edit ornament"));
+                }
+                OrnamentResult::Deleted(_) => {
+                    ornaments.selected_id = None;
+                    commit.send(CommitEdit::new("Delete ornament"));
+                }
+            }
+        });
+    }
+
+    pub(super) fn update_history_ui(
+        mut ctxs: EguiContexts,
+        win: Query<Entity, With<PrimaryWindow>>,
+        history: Res<EditHistory>,
+        mut actions: EventWriter<HistoryAction>,
+    ) {
+        let win = win.single();
+        let ctx = ctxs.ctx_for_window_mut(win);
+
+        egui::Window::new("History")
+            .default_open(false)
+            .anchor(egui::Align2::RIGHT_BOTTOM, [-10.0, -10.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("Undo").clicked() {
+                        actions.send(HistoryAction::Undo);
+                    }
+                    if ui.button("Redo").clicked() {
+                        actions.send(HistoryAction::Redo);
+                    }
+                });
+                ui.separator();
+
+                let position = history.position();
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        if ui.selectable_label(position == 0, "(Start)").clicked() {
+                            actions.send(HistoryAction::JumpTo(0));
+                        }
+                        for (i, label) in history.labels().enumerate() {
+                            let text = match i < position {
+                                true => egui::RichText::new(label),
+                                false => egui::RichText::new(label).weak(),
+                            };
+                            if ui.selectable_label(i + 1 == position, text).clicked() {
+                                actions.send(HistoryAction::JumpTo(i + 1));
+                            }
+                        }
+                    });
+            });
+    }
+
     #[rustfmt::skip]
     pub(super) fn update_info_text(
         mode: Res<State<ControlMode>>,
@@ -1137,8 +2194,11 @@ mod ui {
             ControlMode::ShapeWalls       => *text = ["Shape Walls",        &coords].join("\n"),
             ControlMode::Paint2D          => *text = ["Paint 2D",           &coords].join("\n"),
             ControlMode::PaintTerrain3D   => *text = ["Paint Terrain 3D",   &coords].join("\n"),
-            ControlMode::PaintWalls3D     => *text = ["Shape Walls 3D",     &coords].join("\n"),
+            ControlMode::PaintWalls3D     => *text = ["Paint Walls 3D",     &coords].join("\n"),
+            ControlMode::PaintProperties  => *text = ["Paint Properties",   &coords].join("\n"),
             ControlMode::PlaceGameObjects => *text = ["Place Game Objects", &coords].join("\n"),
+            ControlMode::PlaceOrnaments   => *text = ["Place Ornaments",    &coords].join("\n"),
+            ControlMode::Select           => *text = ["Select",             &coords].join("\n"),
         }
     }
 }
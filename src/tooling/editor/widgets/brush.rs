@@ -0,0 +1,45 @@
+use crate::tooling::editor::brush::{Brush, BrushFalloff, BrushShape, MAX_BRUSH_RADIUS};
+use bevy_egui::egui::{self, Ui};
+
+pub struct BrushWidget;
+
+impl BrushWidget {
+    /// Falloff and strength only matter for elevation brushes, `elevation` toggles them
+    pub fn show(&mut self, ui: &mut Ui, brush: &mut Brush, elevation: bool) {
+        ui.heading("Brush");
+        ui.label("[ / ]: shrink/grow");
+        egui::Grid::new("brush_widget_grid")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Shape");
+                egui::ComboBox::from_id_source("brush_shape")
+                    .selected_text(brush.shape.as_str())
+                    .show_ui(ui, |ui| {
+                        for shape in BrushShape::ALL {
+                            ui.selectable_value(&mut brush.shape, shape, shape.as_str());
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Radius");
+                ui.add(egui::DragValue::new(&mut brush.radius).range(0..=MAX_BRUSH_RADIUS));
+                ui.end_row();
+
+                if elevation {
+                    ui.label("Falloff");
+                    egui::ComboBox::from_id_source("brush_falloff")
+                        .selected_text(brush.falloff.as_str())
+                        .show_ui(ui, |ui| {
+                            for falloff in BrushFalloff::ALL {
+                                ui.selectable_value(&mut brush.falloff, falloff, falloff.as_str());
+                            }
+                        });
+                    ui.end_row();
+
+                    ui.label("Strength");
+                    ui.add(egui::DragValue::new(&mut brush.strength).range(1..=8));
+                    ui.end_row();
+                }
+            });
+    }
+}
@@ -0,0 +1,60 @@
+use crate::{
+    game::objects::definitions::ColorDef,
+    tooling::{editor::widgets::tilemap_size::MAX_GRID_DIMS, level_generator::GeneratorParams},
+};
+use bevy_egui::egui::{self, Ui};
+
+#[derive(Default)]
+pub struct GeneratorWidget {
+    pub params: GeneratorParams,
+}
+
+impl GeneratorWidget {
+    /// Returns true when a level should be generated
+    pub fn show(&mut self, ui: &mut Ui) -> bool {
+        let params = &mut self.params;
+        ui.heading("Generator");
+        egui::Grid::new("generator_widget_grid")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Seed");
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut params.seed));
+                    if ui.button("Next").clicked() {
+                        params.seed = params.seed.wrapping_add(1);
+                    }
+                });
+                ui.end_row();
+
+                ui.label("Size");
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut params.dims.x).range(12..=MAX_GRID_DIMS.x));
+                    ui.add(egui::DragValue::new(&mut params.dims.y).range(12..=MAX_GRID_DIMS.y));
+                });
+                ui.end_row();
+
+                ui.label("Rooms");
+                ui.add(egui::DragValue::new(&mut params.rooms).range(1..=32));
+                ui.end_row();
+
+                ui.label("Cameras");
+                ui.add(egui::DragValue::new(&mut params.cameras).range(0..=16));
+                ui.end_row();
+            });
+
+        ui.label("Required Colors");
+        ui.horizontal_wrapped(|ui| {
+            for color in &ColorDef::VARIANTS[1..] {
+                let mut required = params.required_colors.contains(color);
+                if ui.checkbox(&mut required, color.as_str()).changed() {
+                    match required {
+                        true => params.required_colors.push(*color),
+                        false => params.required_colors.retain(|c| c != color),
+                    }
+                }
+            }
+        });
+
+        ui.button("Generate").clicked()
+    }
+}
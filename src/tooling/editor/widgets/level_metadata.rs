@@ -0,0 +1,91 @@
+use crate::framework::level_asset::metadata::LevelMetadata;
+use bevy::math::Vec3;
+use bevy_egui::egui::{self, ScrollArea, Ui};
+
+pub struct LevelMetadataWidget;
+
+impl LevelMetadataWidget {
+    pub fn show(&mut self, ui: &mut Ui, metadata: &mut LevelMetadata) {
+        ScrollArea::both()
+            .id_source("level_metadata_widget_scroll")
+            .show_viewport(ui, |ui, _viewport| {
+                ui.heading("Level");
+                egui::Grid::new("level_metadata_grid")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Name");
+                        ui.text_edit_singleline(&mut metadata.name);
+                        ui.end_row();
+
+                        ui.label("Author");
+                        ui.text_edit_singleline(&mut metadata.author);
+                        ui.end_row();
+
+                        ui.label("Description");
+                        ui.text_edit_multiline(&mut metadata.description);
+                        ui.end_row();
+
+                        ui.label("Par Time (s)");
+                        ui.add(
+                            egui::DragValue::new(&mut metadata.par_time)
+                                .range(0.0..=3600.0)
+                                .speed(1.0),
+                        );
+                        ui.end_row();
+
+                        ui.label("Music");
+                        ui.text_edit_singleline(&mut metadata.bgm_path);
+                        ui.end_row();
+                    });
+
+                ui.separator();
+                ui.heading("Objective");
+                let objective = &mut metadata.objective;
+                ui.horizontal(|ui| {
+                    ui.label("Required Loot");
+                    ui.add(egui::DragValue::new(&mut objective.required_loot).range(0..=99));
+                });
+                ui.checkbox(&mut objective.return_to_spawn, "Return To Spawn");
+
+                ui.separator();
+                ui.heading("Ambient Light");
+                let ambient = &mut metadata.ambient_light;
+                ui.horizontal(|ui| {
+                    ui.label("Color");
+                    ui.color_edit_button_rgb(&mut ambient.color);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Brightness");
+                    ui.add(egui::DragValue::new(&mut ambient.brightness).range(0.0..=10_000.0));
+                });
+
+                ui.separator();
+                ui.heading("Directional Light");
+                let directional = &mut metadata.directional_light;
+                ui.horizontal(|ui| {
+                    ui.label("Color");
+                    ui.color_edit_button_rgb(&mut directional.color);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Illuminance");
+                    ui.add(
+                        egui::DragValue::new(&mut directional.illuminance)
+                            .range(0.0..=100_000.0)
+                            .speed(10.0),
+                    );
+                });
+                ui.checkbox(&mut directional.shadows_enabled, "Shadows");
+                vec3_row(ui, "Position", &mut directional.position);
+                vec3_row(ui, "Look At", &mut directional.look_at);
+            });
+    }
+}
+
+fn vec3_row(ui: &mut Ui, label: &str, value: &mut Vec3) {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        ui.add(egui::DragValue::new(&mut value.x).speed(0.1));
+        ui.add(egui::DragValue::new(&mut value.y).speed(0.1));
+        ui.add(egui::DragValue::new(&mut value.z).speed(0.1));
+    });
+}
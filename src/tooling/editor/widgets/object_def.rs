@@ -1,7 +1,7 @@
 use crate::{
     framework::tilemap::Tilemap,
     game::objects::definitions::{ColorDef, ObjectDefKind},
-    tooling::editor::object_def_builder::{ObjectDefBuilder, Rot8},
+    tooling::object_def_builder::{ObjectDefBuilder, Rot8},
 };
 use bevy::math::UVec2;
 use bevy_egui::egui::{self, Color32, ScrollArea, Sense, Stroke, TextureId, Ui};
@@ -63,9 +63,7 @@ impl ObjectDefWidget {
                 }
                 if let Some(delete) = delete {
                     defs.remove(delete as usize);
-                    if Some(delete) == selected_id {
-                        result = ObjectDefResult::Deleted(delete);
-                    }
+                    result = ObjectDefResult::Deleted(delete);
                 }
 
                 if ui.button("Add New").clicked() {
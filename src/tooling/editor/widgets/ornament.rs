@@ -0,0 +1,189 @@
+use crate::{
+    framework::tilemap::{Tilemap, MAX_NUM_WALLS},
+    tooling::{
+        object_def_builder::Rot8,
+        tilemap_asset::{
+            GroundOrnamentalMesh, OrnamentalMeshBuilder, WallSideOrnamentalMesh,
+            WallTopOrnamentalMesh,
+        },
+    },
+};
+use bevy_egui::egui::{self, ScrollArea, Ui};
+
+/// Ornament list plus the settings new ornaments are placed with
+pub struct OrnamentWidget {
+    pub mesh_path: String,
+    pub rot: Rot8,
+    pub collider: bool,
+}
+
+impl Default for OrnamentWidget {
+    fn default() -> Self {
+        Self {
+            mesh_path: String::new(),
+            rot: Rot8::D0,
+            collider: true,
+        }
+    }
+}
+
+impl OrnamentWidget {
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        ornaments: &mut Vec<OrnamentalMeshBuilder>,
+        selected_id: Option<u32>,
+        tilemap: &Tilemap,
+    ) -> OrnamentResult {
+        let mut result = OrnamentResult::Ok;
+
+        ScrollArea::both()
+            .id_source("ornament_widget_scroll")
+            .show_viewport(ui, |ui, _viewport| {
+                ui.heading("Ornaments");
+                ui.label("Click: place/select, Shift+Click: delete, R: rotate");
+
+                ui.horizontal(|ui| {
+                    ui.label("Mesh");
+                    ui.text_edit_singleline(&mut self.mesh_path);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Rotation");
+                    rot_combo(ui, "new_ornament_rot", &mut self.rot);
+                });
+                ui.checkbox(&mut self.collider, "Collider");
+
+                ui.separator();
+
+                if let Some(id) = selected_id {
+                    let mut ornament = ornaments[id as usize].clone();
+                    show_ornament(ui, &mut ornament, tilemap);
+                    if ornaments[id as usize] != ornament {
+                        ornaments[id as usize] = ornament;
+                        result = OrnamentResult::ValueChanged;
+                    }
+                }
+
+                ui.separator();
+
+                let mut delete = None;
+                for (i, ornament) in ornaments.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui.button("[X]").clicked() {
+                            delete = Some(i as u32);
+                        }
+                        let coord = ornament.coord();
+                        if ui
+                            .add(egui::SelectableLabel::new(
+                                Some(i as u32) == selected_id,
+                                format!(
+                                    "{:03}: {} {} at {{{}:{}}}",
+                                    i,
+                                    placement_name(ornament),
+                                    ornament.mesh_path(),
+                                    coord.x,
+                                    coord.y
+                                ),
+                            ))
+                            .clicked()
+                        {
+                            result = OrnamentResult::SelectedChanged(i as u32);
+                        }
+                    });
+                }
+                if let Some(delete) = delete {
+                    ornaments.remove(delete as usize);
+                    result = OrnamentResult::Deleted(delete);
+                }
+            });
+        result
+    }
+
+    /// An ornament with the current settings at `placement`
+    pub fn make_ornament(&self, placement: OrnamentalMeshBuilder) -> OrnamentalMeshBuilder {
+        let (mesh_path, rot, collider) = (self.mesh_path.clone(), self.rot, self.collider);
+        match placement {
+            OrnamentalMeshBuilder::Ground(m) => {
+                OrnamentalMeshBuilder::Ground(GroundOrnamentalMesh {
+                    mesh_path,
+                    rot,
+                    collider,
+                    ..m
+                })
+            }
+            OrnamentalMeshBuilder::Side(m) => OrnamentalMeshBuilder::Side(WallSideOrnamentalMesh {
+                mesh_path,
+                rot,
+                collider,
+                ..m
+            }),
+            OrnamentalMeshBuilder::Top(m) => OrnamentalMeshBuilder::Top(WallTopOrnamentalMesh {
+                mesh_path,
+                rot,
+                collider,
+                ..m
+            }),
+        }
+    }
+}
+
+fn show_ornament(ui: &mut Ui, ornament: &mut OrnamentalMeshBuilder, tilemap: &Tilemap) {
+    ui.heading(placement_name(ornament));
+
+    let dims = tilemap.face_grid().dims();
+    let (mesh_path, coord, rot, collider) = match ornament {
+        OrnamentalMeshBuilder::Ground(m) => {
+            (&mut m.mesh_path, &mut m.coord, &mut m.rot, &mut m.collider)
+        }
+        OrnamentalMeshBuilder::Side(m) => {
+            ui.horizontal(|ui| {
+                ui.label("Height");
+                ui.add(egui::DragValue::new(&mut m.height).range(0..=MAX_NUM_WALLS as u32 - 1));
+            });
+            (&mut m.mesh_path, &mut m.coord, &mut m.rot, &mut m.collider)
+        }
+        OrnamentalMeshBuilder::Top(m) => {
+            (&mut m.mesh_path, &mut m.coord, &mut m.rot, &mut m.collider)
+        }
+    };
+
+    ui.horizontal(|ui| {
+        ui.label("Mesh");
+        ui.text_edit_singleline(mesh_path);
+    });
+    ui.horizontal(|ui| {
+        ui.label("Coord (Position)");
+        ui.add(egui::DragValue::new(&mut coord.x).range(0..=dims.x - 1));
+        ui.add(egui::DragValue::new(&mut coord.y).range(0..=dims.y - 1));
+    });
+    ui.horizontal(|ui| {
+        ui.label("Rotation");
+        rot_combo(ui, "selected_ornament_rot", rot);
+    });
+    ui.checkbox(collider, "Collider");
+}
+
+fn rot_combo(ui: &mut Ui, id: &str, rot: &mut Rot8) {
+    egui::ComboBox::from_id_source(id)
+        .selected_text(rot.as_str())
+        .show_ui(ui, |ui| {
+            for value in Rot8::ALL {
+                ui.selectable_value(rot, value, value.as_str());
+            }
+        });
+}
+
+fn placement_name(ornament: &OrnamentalMeshBuilder) -> &'static str {
+    match ornament {
+        OrnamentalMeshBuilder::Ground(_) => "Ground",
+        OrnamentalMeshBuilder::Side(_) => "Wall Side",
+        OrnamentalMeshBuilder::Top(_) => "Wall Top",
+    }
+}
+
+pub enum OrnamentResult {
+    Ok,
+    SelectedChanged(u32),
+    ValueChanged,
+    Deleted(u32),
+}
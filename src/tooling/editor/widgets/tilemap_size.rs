@@ -95,6 +95,9 @@ impl TilemapSizeWidget {
                     ui.label("Elevation");
                     ui.add(egui::Slider::new(&mut self.elevation, 0..=32));
                 });
+                if self.old_dims != self.sliders && ui.button("Apply").clicked() {
+                    update = true;
+                }
                 if update {
                     if self.old_dims != self.sliders {
                         self.old_dims = self.sliders;
@@ -111,6 +114,11 @@ impl TilemapSizeWidget {
 
         result
     }
+    /// Starts out proposing `dims` instead of the current size
+    pub fn with_target(mut self, dims: UVec2) -> Self {
+        self.sliders = dims;
+        self
+    }
     pub fn set_dims(&mut self, dims: UVec2) {
         self.old_dims = dims;
         self.sliders = dims;
@@ -0,0 +1,72 @@
+//! Turns editor scenes into shippable `.level` files.
+//!
+//! Shared by the editor's export and the headless `bake` command, so it must not
+//! depend on a running `App`.
+
+use crate::{
+    framework::{
+        level_asset::{BakedWallData, LevelAsset, LevelAssetData},
+        tileset::{Tileset, TILESET_TILE_NUM},
+    },
+    tooling::{
+        tilemap_asset::TilemapRon,
+        tilemap_mesh_builder::{self, RawMeshBuilder},
+    },
+};
+use bevy::prelude::*;
+use std::path::Path;
+
+pub fn bake_level(scene: &TilemapRon, tileset: &Tileset) -> LevelAsset {
+    let tilemap = &scene.tilemap;
+    let builder = RawMeshBuilder::new(tilemap);
+    let mesh = builder.make_ground_mesh(tileset);
+    let collider =
+        tilemap_mesh_builder::build_rapier_convex_collider_for_preview(&mesh.clone().into());
+
+    let walls = builder
+        .make_wall_meshes(tileset)
+        .into_iter()
+        .map(|mesh| {
+            let collider = tilemap_mesh_builder::build_rapier_convex_collider_for_preview(
+                &mesh.clone().into(),
+            );
+            BakedWallData { mesh, collider }
+        })
+        .collect();
+
+    let objects = scene.objects.iter().map(|d| d.build(tilemap)).collect();
+    let meshes = scene
+        .meshes
+        .iter()
+        .filter_map(|m| {
+            let mesh = m.build(tilemap);
+            if mesh.is_none() {
+                warn!(
+                    "Ornament outside of the tilemap: {} at {}",
+                    m.mesh_path(),
+                    m.coord()
+                );
+            }
+            mesh
+        })
+        .collect();
+
+    LevelAsset::new(LevelAssetData {
+        metadata: scene.metadata.clone(),
+        tilemap: tilemap.clone(),
+        baked_ground_collider: collider,
+        baked_ground_mesh: mesh,
+        baked_walls: walls,
+        objects,
+        meshes,
+    })
+}
+
+/// Reads a `TilemapRon` scene from `input` and writes the baked level to `output`
+pub fn bake_file(input: &Path, output: &Path) -> anyhow::Result<()> {
+    let ron = TilemapRon::read(input)?;
+    let tileset = Tileset::new(UVec2::new(TILESET_TILE_NUM[0], TILESET_TILE_NUM[1])).unwrap();
+
+    let level = bake_level(&ron, &tileset);
+    level.save(&output.to_string_lossy())
+}
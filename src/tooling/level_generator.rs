@@ -0,0 +1,362 @@
+//! Procedural levels: rooms joined by corridors, ramps between room elevations,
+//! a player spawn, tinting cauldrons for the colors the level asks for and patrolling cameras.
+//!
+//! Generated scenes go through [`level_baker::bake_level`] like exported ones and are
+//! rejected unless the baked level passes the inspector and can be solved.
+
+use crate::{
+    framework::{
+        level_asset::{metadata::LevelMetadata, LevelAsset, LevelAssetData},
+        tilemap::Tilemap,
+        tileset::Tileset,
+    },
+    game::objects::definitions::{ColorDef, ObjectDefKind},
+    tooling::{
+        level_baker,
+        level_inspector::LevelReport,
+        level_solver::ColorGraph,
+        object_def_builder::{ObjectDefBuilder, Rot8},
+        tilemap_asset::TilemapRon,
+    },
+};
+use anyhow::{bail, Context};
+use bevy::prelude::*;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use std::collections::HashSet;
+
+/// Height of the solid walls between rooms
+const WALL_HEIGHT: u32 = 2;
+/// Elevation of the spawn room, other rooms ramp up and down from there
+const BASE_ELEVATION: u32 = 6;
+const MAX_ELEVATION: u32 = 12;
+/// Most elevation steps between two connected rooms
+const MAX_RAMP: i32 = 3;
+/// Room sizes in faces, including the walkable border
+const ROOM_SIZE: (u32, u32) = (5, 10);
+/// Corridors are this many faces wide
+const CORRIDOR_WIDTH: u32 = 2;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratorParams {
+    pub seed: u64,
+    pub dims: UVec2,
+    pub rooms: u32,
+    pub cameras: u32,
+    /// Colors minions have to be tinted to, mixed from the level's cauldrons
+    pub required_colors: Vec<ColorDef>,
+}
+
+impl Default for GeneratorParams {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            dims: UVec2::new(48, 48),
+            rooms: 6,
+            cameras: 2,
+            required_colors: vec![ColorDef::Yellow],
+        }
+    }
+}
+
+struct Room {
+    min: UVec2,
+    size: UVec2,
+    elevation: u32,
+}
+
+impl Room {
+    fn center(&self) -> UVec2 {
+        self.min + self.size / 2
+    }
+    fn contains(&self, coord: UVec2) -> bool {
+        coord.cmpge(self.min).all() && coord.cmplt(self.min + self.size).all()
+    }
+    /// Keeps a margin of walls between rooms
+    fn overlaps(&self, other: &Room) -> bool {
+        let margin = UVec2::splat(2);
+        (self.min + self.size + margin).cmpgt(other.min).all()
+            && (other.min + other.size + margin).cmpgt(self.min).all()
+    }
+    /// Faces away from the room's edge, objects go there
+    fn interior(&self) -> impl Iterator<Item = UVec2> + '_ {
+        (self.min.y + 1..self.min.y + self.size.y - 1).flat_map(move |y| {
+            (self.min.x + 1..self.min.x + self.size.x - 1).map(move |x| UVec2::new(x, y))
+        })
+    }
+}
+
+/// Generates the editor scene for `params`. Same params, same scene.
+pub fn generate(params: &GeneratorParams) -> anyhow::Result<TilemapRon> {
+    let mut rng = StdRng::seed_from_u64(params.seed);
+    let dims = params.dims;
+    if params.rooms == 0 {
+        bail!("A level needs at least one room");
+    }
+    if dims.min_element() < ROOM_SIZE.1 + 2 {
+        bail!("The tilemap must be at least {0}x{0}", ROOM_SIZE.1 + 2);
+    }
+
+    let mut rooms: Vec<Room> = vec![];
+    for _ in 0..params.rooms * 32 {
+        if rooms.len() == params.rooms as usize {
+            break;
+        }
+        let size = UVec2::new(
+            rng.gen_range(ROOM_SIZE.0..=ROOM_SIZE.1),
+            rng.gen_range(ROOM_SIZE.0..=ROOM_SIZE.1),
+        );
+        let min = UVec2::new(
+            rng.gen_range(1..dims.x - size.x),
+            rng.gen_range(1..dims.y - size.y),
+        );
+        let room = Room {
+            min,
+            size,
+            elevation: BASE_ELEVATION,
+        };
+        if !rooms.iter().any(|r| r.overlaps(&room)) {
+            rooms.push(room);
+        }
+    }
+    if rooms.len() < params.rooms as usize {
+        bail!(
+            "Only fit {} of {} rooms, use a bigger tilemap or fewer rooms",
+            rooms.len(),
+            params.rooms
+        );
+    }
+
+    // Prim's minimum spanning tree over the room centers keeps corridors short
+    let mut corridors = vec![];
+    let mut connected = vec![0];
+    while connected.len() < rooms.len() {
+        let (from, to) = connected
+            .iter()
+            .flat_map(|a| (0..rooms.len()).map(move |b| (*a, b)))
+            .filter(|(_, b)| !connected.contains(b))
+            .min_by_key(|(a, b)| {
+                let d = rooms[*a].center().as_ivec2() - rooms[*b].center().as_ivec2();
+                d.x.abs() + d.y.abs()
+            })
+            .unwrap();
+        let ramp = rng.gen_range(-MAX_RAMP..=MAX_RAMP);
+        rooms[to].elevation =
+            (rooms[from].elevation as i32 + ramp).clamp(0, MAX_ELEVATION as i32) as u32;
+        corridors.push((from, to));
+        connected.push(to);
+    }
+
+    let mut tilemap = Tilemap::new(dims, BASE_ELEVATION).unwrap();
+    for face in tilemap.face_data_mut() {
+        face.wall_height = WALL_HEIGHT;
+    }
+    for (from, to) in corridors {
+        carve_corridor(&mut tilemap, &rooms[from], &rooms[to]);
+    }
+    for room in rooms.iter() {
+        for y in room.min.y..room.min.y + room.size.y {
+            for x in room.min.x..room.min.x + room.size.x {
+                set_face(&mut tilemap, UVec2::new(x, y), room.elevation);
+            }
+        }
+    }
+
+    let mut objects = vec![];
+    let mut occupied = HashSet::new();
+    let mut place = |rng: &mut StdRng, room: &Room| -> Option<UVec2> {
+        let free = room
+            .interior()
+            .filter(|c| !occupied.contains(c))
+            .collect::<Vec<_>>();
+        let coord = *free.choose(rng)?;
+        occupied.insert(coord);
+        Some(coord)
+    };
+
+    let spawn = place(&mut rng, &rooms[0]).context("The spawn room is too small")?;
+    objects.push(ObjectDefBuilder {
+        kind: ObjectDefKind::SpawnPoint,
+        coord: spawn,
+        number: 0,
+        ..Default::default()
+    });
+
+    // Away from the spawn when there's somewhere else to go
+    let other_rooms = match rooms.len() {
+        1 => &rooms[..],
+        _ => &rooms[1..],
+    };
+
+    // One cauldron per primary color the required colors are mixed from
+    let mix = params
+        .required_colors
+        .iter()
+        .fold(ColorDef::Void, |acc, c| acc + *c);
+    for primary in [ColorDef::Red, ColorDef::Green, ColorDef::Blue] {
+        if !mix.contains(primary) {
+            continue;
+        }
+        let room = other_rooms.choose(&mut rng).unwrap();
+        let coord = place(&mut rng, room).context("Ran out of space for cauldrons")?;
+        objects.push(ObjectDefBuilder {
+            kind: ObjectDefKind::Cauldron,
+            coord,
+            color: primary,
+            rotation: *Rot8::ALL.choose(&mut rng).unwrap(),
+            ..Default::default()
+        });
+    }
+
+    for _ in 0..params.cameras {
+        let room = other_rooms.choose(&mut rng).unwrap();
+        let Some(coord) = place(&mut rng, room) else {
+            continue;
+        };
+        let patrol = room.interior().filter(|c| *c != coord).collect::<Vec<_>>();
+        objects.push(ObjectDefBuilder {
+            kind: ObjectDefKind::Camera,
+            coord,
+            rotation: *Rot8::ALL.choose(&mut rng).unwrap(),
+            coord_refs: patrol.choose_multiple(&mut rng, 2).copied().collect(),
+            ..Default::default()
+        });
+    }
+
+    let metadata = LevelMetadata {
+        name: format!("Generated {}", params.seed),
+        description: format!(
+            "{} rooms, requires {}",
+            rooms.len(),
+            params
+                .required_colors
+                .iter()
+                .map(|c| c.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        author: "Level generator".to_owned(),
+        ..Default::default()
+    };
+
+    Ok(TilemapRon::new(metadata, tilemap, objects, vec![]))
+}
+
+/// Generates, bakes and checks a level
+pub fn generate_level(
+    params: &GeneratorParams,
+    tileset: &Tileset,
+) -> anyhow::Result<(TilemapRon, LevelAsset)> {
+    let scene = generate(params)?;
+    let level = level_baker::bake_level(&scene, tileset);
+
+    let report = LevelReport::new(level.data());
+    if !report.errors.is_empty() {
+        bail!("Generated level is broken: {}", report.errors.join(", "));
+    }
+    check_solvable(level.data(), &params.required_colors)?;
+    Ok((scene, level))
+}
+
+/// Whether every required color can be mixed from the cauldrons reachable from the
+/// player spawn. Minions start out void and each cauldron adds its color to them.
+pub fn check_solvable(data: &LevelAssetData, required_colors: &[ColorDef]) -> anyhow::Result<()> {
+    let tints = ColorGraph::for_level(&data.tilemap, &data.objects)?.tints(ColorDef::Void);
+    let missing = required_colors
+        .iter()
+        .filter(|color| !tints.contains(color))
+        .map(|c| c.as_str())
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        bail!(
+            "Can't mix {} from the reachable cauldrons",
+            missing.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Opens up the face and levels its vertices
+fn set_face(tilemap: &mut Tilemap, coord: UVec2, elevation: u32) {
+    let fid = tilemap.face_grid().coord_to_id(coord);
+    tilemap.face_data_mut()[fid as usize].wall_height = 0;
+    for vid in tilemap.face_id_to_vert_ids(fid) {
+        tilemap.vert_data_mut()[vid as usize].elevation = elevation;
+    }
+}
+
+/// L-shaped corridor between the room centers, ramping from one elevation to the other
+fn carve_corridor(tilemap: &mut Tilemap, from: &Room, to: &Room) {
+    let (a, b) = (from.center(), to.center());
+    let mut path = vec![];
+    for x in a.x.min(b.x)..=a.x.max(b.x) {
+        path.push(UVec2::new(x, a.y));
+    }
+    if b.x < a.x {
+        path.reverse();
+    }
+    let turn = path.len();
+    for y in a.y.min(b.y)..=a.y.max(b.y) {
+        path.push(UVec2::new(b.x, y));
+    }
+    if b.y < a.y {
+        path[turn..].reverse();
+    }
+
+    // Only the stretch outside of both rooms ramps, the rooms themselves stay flat
+    let outside = path
+        .iter()
+        .filter(|c| !from.contains(**c) && !to.contains(**c))
+        .count()
+        .max(1);
+    let mut step = 0;
+    let max = tilemap.dims() - UVec2::ONE;
+    for coord in path {
+        let t = step as f32 / outside as f32;
+        let elevation = from.elevation as f32 + (to.elevation as f32 - from.elevation as f32) * t;
+        if !from.contains(coord) && !to.contains(coord) {
+            step += 1;
+        }
+        for dy in 0..CORRIDOR_WIDTH {
+            for dx in 0..CORRIDOR_WIDTH {
+                let coord = (coord + UVec2::new(dx, dy)).min(max);
+                set_face(tilemap, coord, elevation.round() as u32);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_generate_level() {
+    use crate::framework::tileset::TILESET_TILE_NUM;
+
+    let tileset = Tileset::new(UVec2::new(TILESET_TILE_NUM[0], TILESET_TILE_NUM[1])).unwrap();
+    let params = GeneratorParams {
+        seed: 7,
+        dims: UVec2::new(32, 32),
+        rooms: 4,
+        cameras: 2,
+        required_colors: vec![ColorDef::Yellow, ColorDef::Magenta],
+    };
+    let (scene, level) = generate_level(&params, &tileset).unwrap();
+    assert_eq!(scene.tilemap.dims(), params.dims);
+    assert_eq!(generate(&params).unwrap().objects, scene.objects);
+
+    let count = |kind| scene.objects.iter().filter(|o| o.kind == kind).count();
+    assert_eq!(count(ObjectDefKind::SpawnPoint), 1);
+    assert_eq!(count(ObjectDefKind::Cauldron), 3);
+    assert_eq!(count(ObjectDefKind::Camera), 2);
+
+    // Without the blue cauldron magenta can't be mixed anymore
+    let mut data: LevelAssetData =
+        bincode::deserialize(&bincode::serialize(level.data()).unwrap()).unwrap();
+    data.objects
+        .retain(|o| o.kind != ObjectDefKind::Cauldron || o.color != ColorDef::Blue);
+    assert!(check_solvable(&data, &[ColorDef::Yellow]).is_ok());
+    assert!(check_solvable(&data, &[ColorDef::Magenta]).is_err());
+
+    let too_many = GeneratorParams {
+        rooms: 40,
+        ..params
+    };
+    assert!(generate(&too_many).is_err());
+}
@@ -0,0 +1,178 @@
+//! Summary and sanity checks for baked levels, used by the `inspect` command.
+
+use crate::{
+    framework::{
+        level_asset::LevelAssetData,
+        navmesh::{self, NavAgent},
+    },
+    game::objects::definitions::{ObjectDef, ObjectDefKind},
+};
+use bevy::prelude::*;
+use std::fmt;
+
+pub struct LevelReport {
+    pub name: String,
+    pub author: String,
+    pub dims: UVec2,
+    pub object_counts: Vec<(ObjectDefKind, usize)>,
+    pub spawn_points: Vec<(Vec3, u32)>,
+    pub ground_vertices: usize,
+    pub wall_meshes: usize,
+    pub wall_vertices: usize,
+    pub errors: Vec<String>,
+}
+
+impl LevelReport {
+    pub fn new(data: &LevelAssetData) -> Self {
+        let tilemap = &data.tilemap;
+        let objects = &data.objects;
+
+        let object_counts = ObjectDefKind::VARIANTS
+            .iter()
+            .map(|kind| (*kind, objects.iter().filter(|o| o.kind == *kind).count()))
+            .filter(|(_, count)| *count > 0)
+            .collect();
+
+        let spawn_points = objects
+            .iter()
+            .filter(|o| o.kind == ObjectDefKind::SpawnPoint)
+            .map(|o| (o.position, o.number))
+            .collect::<Vec<_>>();
+
+        let mut errors = vec![];
+        let player_spawn = spawn_points.iter().find(|(_, number)| *number == 0);
+        if player_spawn.is_none() {
+            errors.push("No player spawn point (SpawnPoint with number 0)".to_owned());
+        }
+
+        for (id, object) in objects.iter().enumerate() {
+            let name = format!("Object {id} ({})", object.kind.as_str());
+
+            let on_wall = tilemap
+                .pos_to_face_id(object.position.x, object.position.z)
+                .is_some_and(|fid| tilemap.face_data()[fid as usize].wall_height > 0);
+            if on_wall {
+                errors.push(format!("{name} is placed on a wall face"));
+            }
+
+            for obj_ref in object.obj_refs.iter() {
+                if *obj_ref as usize >= objects.len() {
+                    errors.push(format!(
+                        "{name} references object {obj_ref}, but there are only {}",
+                        objects.len()
+                    ));
+                }
+            }
+
+            if object.kind == ObjectDefKind::Camera && object.pos_refs.is_empty() {
+                errors.push(format!("{name} has no positions to look at"));
+            }
+            if object.kind.is_nav_link() && object.nav_link().is_none() {
+                errors.push(format!("{name} needs a start and an end position"));
+            }
+        }
+
+        let links = objects
+            .iter()
+            .filter_map(ObjectDef::nav_link)
+            .collect::<Vec<_>>();
+        match navmesh::create_level_navmesh(tilemap, &[], NavAgent::Minion) {
+            Ok((navmesh, elevation)) => {
+                if let Some((start, _)) = player_spawn {
+                    for (id, object) in objects.iter().enumerate() {
+                        // Cameras only need to see the level, nobody walks up to them.
+                        // Links are only markers, their ends are what minions walk to.
+                        if object.kind == ObjectDefKind::Camera || object.kind.is_nav_link() {
+                            continue;
+                        }
+                        let reachable = *start == object.position
+                            || navmesh::path_with_links(
+                                &navmesh,
+                                &elevation,
+                                &links,
+                                *start,
+                                object.position,
+                            )
+                            .is_some();
+                        if !reachable {
+                            errors.push(format!(
+                                "Object {id} ({}) can't be reached from the player spawn",
+                                object.kind.as_str()
+                            ));
+                        }
+                    }
+                }
+            }
+            Err(e) => errors.push(format!("Failed to create the navmesh: {e:?}")),
+        }
+
+        Self {
+            name: data.metadata.name.clone(),
+            author: data.metadata.author.clone(),
+            dims: tilemap.dims(),
+            object_counts,
+            spawn_points,
+            ground_vertices: data.baked_ground_mesh.vertices.len(),
+            wall_meshes: data.baked_walls.len(),
+            wall_vertices: data.baked_walls.iter().map(|w| w.mesh.vertices.len()).sum(),
+            errors,
+        }
+    }
+}
+
+impl fmt::Display for LevelReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Name: {}", self.name)?;
+        writeln!(f, "Author: {}", self.author)?;
+        writeln!(f, "Tilemap: {}x{}", self.dims.x, self.dims.y)?;
+        writeln!(f, "Ground vertices: {}", self.ground_vertices)?;
+        writeln!(
+            f,
+            "Wall vertices: {} in {} meshes",
+            self.wall_vertices, self.wall_meshes
+        )?;
+        writeln!(f, "Objects:")?;
+        for (kind, count) in self.object_counts.iter() {
+            writeln!(f, "  {}: {count}", kind.as_str())?;
+        }
+        writeln!(f, "Spawn points:")?;
+        for (position, number) in self.spawn_points.iter() {
+            writeln!(f, "  #{number} at {position}")?;
+        }
+        if self.errors.is_empty() {
+            writeln!(f, "No errors")?;
+        } else {
+            writeln!(f, "Errors:")?;
+            for error in self.errors.iter() {
+                writeln!(f, "  {error}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_level_report() {
+    use crate::framework::level_asset::LevelAsset;
+
+    let fixture = include_bytes!("../../tests/fixtures/level_v4.level");
+    let asset = LevelAsset::from_bytes(fixture).unwrap();
+    let report = LevelReport::new(asset.data());
+    assert_eq!(report.dims, UVec2::new(32, 32));
+    assert_eq!(report.spawn_points.len(), 1);
+    assert!(report.errors.is_empty());
+
+    let mut broken: LevelAssetData =
+        bincode::deserialize(&bincode::serialize(asset.data()).unwrap()).unwrap();
+    let camera = broken
+        .objects
+        .iter_mut()
+        .find(|o| o.kind == ObjectDefKind::Camera)
+        .unwrap();
+    camera.pos_refs.clear();
+    camera.obj_refs.push(1000);
+    broken
+        .objects
+        .retain(|o| o.kind != ObjectDefKind::SpawnPoint);
+    assert_eq!(LevelReport::new(&broken).errors.len(), 3);
+}
@@ -0,0 +1,572 @@
+//! Checks that the color puzzles of a level can be solved, used by the `solve` command
+//! and the editor.
+//!
+//! Minions are tinted by cauldrons, which add their color, and bleached by camera
+//! spotlights, which subtract theirs. Both only count when minions can walk up to them
+//! from the player spawn, around the laser grids. Every object with a
+//! [`MinionInteractionRequirement`] then has to be reachable and get enough minions of
+//! each kind and with each ability out of the player's starting storage. Requirements
+//! mixing kinds and abilities that only work out with minions counting for both are
+//! reported as warnings.
+
+use crate::{
+    framework::{
+        navmesh::{self, NavAgent, NavElevation, NavLink, ObjectObstacle},
+        tilemap::Tilemap,
+    },
+    game::{
+        minion::{
+            ability::Ability,
+            collector::{MinionInteractionRequirement, MinionStorage},
+            MinionKind,
+        },
+        objects::{
+            definitions::{ColorDef, ObjectDef, ObjectDefKind},
+            laser_grid::LASER_GRID_FOOTPRINT,
+        },
+    },
+};
+use anyhow::{anyhow, Context};
+use bevy::prelude::*;
+use std::{collections::VecDeque, fmt};
+use vleue_navigator::NavMesh;
+
+/// Walkable connections from the player spawn, across the links of the level. Laser grids
+/// block the way like they do in game.
+struct Reachability {
+    navmesh: NavMesh,
+    elevation: NavElevation,
+    links: Vec<NavLink>,
+    spawn: Vec3,
+}
+
+impl Reachability {
+    fn new(tilemap: &Tilemap, objects: &[ObjectDef]) -> anyhow::Result<Self> {
+        let obstacles = objects
+            .iter()
+            .filter(|o| o.kind == ObjectDefKind::LaserGrid)
+            .filter_map(|o| {
+                ObjectObstacle::from_footprint(tilemap, o.position, LASER_GRID_FOOTPRINT)
+            })
+            .collect::<Vec<_>>();
+        let (navmesh, elevation) =
+            navmesh::create_level_navmesh(tilemap, &obstacles, NavAgent::Minion)
+                .map_err(|e| anyhow!("Failed to create the navmesh: {e:?}"))?;
+        let spawn = objects
+            .iter()
+            .find(|o| o.kind == ObjectDefKind::SpawnPoint && o.number == 0)
+            .context("No player spawn point")?
+            .position;
+        let links = objects.iter().filter_map(ObjectDef::nav_link).collect();
+        Ok(Self {
+            navmesh,
+            elevation,
+            links,
+            spawn,
+        })
+    }
+
+    fn reaches(&self, position: Vec3) -> bool {
+        self.spawn == position
+            || navmesh::path_with_links(
+                &self.navmesh,
+                &self.elevation,
+                &self.links,
+                self.spawn,
+                position,
+            )
+            .is_some()
+    }
+}
+
+// Nodes of the flow network of ColorGraph::flow
+const N: usize = MinionKind::COUNT;
+const NODES: usize = 2 * N + Ability::COUNT + 2;
+const SOURCE: usize = NODES - 2;
+const SINK: usize = NODES - 1;
+
+/// How minion colors can be changed with the cauldrons and spotlights of a level
+#[derive(Debug, Clone, Default)]
+pub struct ColorGraph {
+    cauldrons: Vec<ColorDef>,
+    spotlights: Vec<ColorDef>,
+}
+
+impl ColorGraph {
+    pub fn new(cauldrons: Vec<ColorDef>, spotlights: Vec<ColorDef>) -> Self {
+        Self {
+            cauldrons,
+            spotlights,
+        }
+    }
+
+    /// Only takes the cauldrons and cameras minions can get to from the player spawn.
+    /// Cameras count when one of the positions they look at is reachable.
+    pub fn for_level(tilemap: &Tilemap, objects: &[ObjectDef]) -> anyhow::Result<Self> {
+        let reach = Reachability::new(tilemap, objects)?;
+        Ok(Self::reachable(&reach, objects))
+    }
+
+    fn reachable(reach: &Reachability, objects: &[ObjectDef]) -> Self {
+        let of_kind = |kind| objects.iter().filter(move |o| o.kind == kind);
+        Self {
+            cauldrons: of_kind(ObjectDefKind::Cauldron)
+                .filter(|o| reach.reaches(o.position))
+                .map(|o| o.color)
+                .collect(),
+            spotlights: of_kind(ObjectDefKind::Camera)
+                .filter(|o| o.pos_refs.iter().any(|p| reach.reaches(*p)))
+                .map(|o| o.color)
+                .collect(),
+        }
+    }
+
+    /// Every color a minion of color `from` can be turned into, `from` included
+    pub fn tints(&self, from: ColorDef) -> Vec<ColorDef> {
+        let mut seen = vec![from];
+        let mut queue = VecDeque::from([from]);
+        while let Some(color) = queue.pop_front() {
+            // Cauldrons send minions back untouched if they already have the color
+            let tinted = self
+                .cauldrons
+                .iter()
+                .filter(|c| !color.contains(**c))
+                .map(|c| color + *c);
+            // Stealthy minions aren't seen by the spotlights
+            let stealthy = MinionKind::from(color).has_ability(Ability::Stealthy);
+            let bleached = self
+                .spotlights
+                .iter()
+                .filter(|s| !stealthy && color.contains_any(**s))
+                .map(|s| color - *s);
+            for next in tinted.chain(bleached).collect::<Vec<_>>() {
+                if !seen.contains(&next) {
+                    seen.push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+        seen.sort_by_key(|c| ColorDef::VARIANTS.iter().position(|v| v == c));
+        seen
+    }
+
+    /// Minions of each kind the requirement can't get out of `storage`, empty if it can
+    /// be satisfied
+    pub fn missing(
+        &self,
+        storage: &MinionStorage,
+        requirement: &MinionInteractionRequirement,
+    ) -> Vec<(MinionKind, u32)> {
+        let capacity = self.flow(storage, requirement);
+        MinionKind::VARIANTS
+            .into_iter()
+            .enumerate()
+            .map(|(i, kind)| (kind, capacity[N + i][SINK]))
+            .filter(|(_, missing)| *missing > 0)
+            .collect()
+    }
+
+    /// Minions with each ability the requirement can't get out of `storage`, empty if it
+    /// can be satisfied
+    pub fn missing_abilities(
+        &self,
+        storage: &MinionStorage,
+        requirement: &MinionInteractionRequirement,
+    ) -> Vec<(Ability, u32)> {
+        let capacity = self.flow(storage, requirement);
+        Ability::VARIANTS
+            .into_iter()
+            .enumerate()
+            .map(|(i, ability)| (ability, capacity[2 * N + i][SINK]))
+            .filter(|(_, missing)| *missing > 0)
+            .collect()
+    }
+
+    /// Whether a requirement mixing kinds and abilities has enough minions for its kinds
+    /// and for its abilities on their own. It then comes down to minions counting for both
+    /// at once, which [`Self::missing`] doesn't model.
+    pub fn needs_overlap(
+        &self,
+        storage: &MinionStorage,
+        requirement: &MinionInteractionRequirement,
+    ) -> bool {
+        let kinds = MinionInteractionRequirement::new(requirement.counts.clone());
+        let abilities = MinionInteractionRequirement::with_abilities(requirement.abilities.clone());
+        !requirement.counts.is_empty()
+            && !requirement.abilities.is_empty()
+            && self.missing(storage, &kinds).is_empty()
+            && self.missing_abilities(storage, &abilities).is_empty()
+    }
+
+    /// Each minion is used once, so this is a max flow from the stored kinds to the
+    /// required kinds and abilities. In game a minion counts for its kind and its abilities
+    /// at once, so requirements mixing both can be satisfiable even with something left
+    /// missing here. Returns the residual capacities, what's left to the sink is missing.
+    fn flow(
+        &self,
+        storage: &MinionStorage,
+        requirement: &MinionInteractionRequirement,
+    ) -> [[u32; NODES]; NODES] {
+        // Stored kinds are nodes 0..N, required kinds N..2N, required abilities after them
+        let mut capacity = [[0; NODES]; NODES];
+        for (i, from) in MinionKind::VARIANTS.into_iter().enumerate() {
+            capacity[SOURCE][i] = storage.num_minions(from);
+            let tints = self.tints(ColorDef::from(from));
+            for (j, to) in MinionKind::VARIANTS.into_iter().enumerate() {
+                if tints.contains(&ColorDef::from(to)) {
+                    capacity[i][N + j] = u32::MAX;
+                }
+            }
+            for (j, ability) in Ability::VARIANTS.into_iter().enumerate() {
+                if tints
+                    .iter()
+                    .any(|c| MinionKind::from(*c).has_ability(ability))
+                {
+                    capacity[i][2 * N + j] = u32::MAX;
+                }
+            }
+            capacity[N + i][SINK] = requirement.counts.get(&from).copied().unwrap_or_default();
+        }
+        for (j, ability) in Ability::VARIANTS.into_iter().enumerate() {
+            capacity[2 * N + j][SINK] = requirement
+                .abilities
+                .get(&ability)
+                .copied()
+                .unwrap_or_default();
+        }
+
+        loop {
+            let mut prev = [None; NODES];
+            let mut queue = VecDeque::from([SOURCE]);
+            while let Some(node) = queue.pop_front() {
+                for next in 0..NODES {
+                    if next != SOURCE && prev[next].is_none() && capacity[node][next] > 0 {
+                        prev[next] = Some(node);
+                        queue.push_back(next);
+                    }
+                }
+            }
+            if prev[SINK].is_none() {
+                break;
+            }
+            let mut flow = u32::MAX;
+            let mut node = SINK;
+            while let Some(p) = prev[node] {
+                flow = flow.min(capacity[p][node]);
+                node = p;
+            }
+            let mut node = SINK;
+            while let Some(p) = prev[node] {
+                capacity[p][node] -= flow;
+                capacity[node][p] = capacity[node][p].saturating_add(flow);
+                node = p;
+            }
+        }
+        capacity
+    }
+}
+
+pub struct TargetReport {
+    /// Index into the level's objects
+    pub id: usize,
+    pub kind: ObjectDefKind,
+    pub requirement: Vec<(MinionKind, u32)>,
+    pub abilities: Vec<(Ability, u32)>,
+    pub reachable: bool,
+    pub missing: Vec<(MinionKind, u32)>,
+    pub missing_abilities: Vec<(Ability, u32)>,
+    /// Only satisfiable with minions counting for a required kind and ability at once,
+    /// which isn't checked
+    pub unchecked: bool,
+}
+
+impl TargetReport {
+    pub fn is_satisfiable(&self) -> bool {
+        self.reachable
+            && (self.unchecked || self.missing.is_empty() && self.missing_abilities.is_empty())
+    }
+}
+
+pub struct PuzzleReport {
+    pub minions: Vec<(MinionKind, u32)>,
+    /// Colors each stored minion kind can be turned into
+    pub tints: Vec<(MinionKind, Vec<ColorDef>)>,
+    pub targets: Vec<TargetReport>,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl PuzzleReport {
+    pub fn new(tilemap: &Tilemap, objects: &[ObjectDef], storage: &MinionStorage) -> Self {
+        let minions = MinionKind::VARIANTS
+            .into_iter()
+            .map(|kind| (kind, storage.num_minions(kind)))
+            .filter(|(_, count)| *count > 0)
+            .collect::<Vec<_>>();
+
+        let mut errors = vec![];
+        let mut warnings = vec![];
+        let reach = match Reachability::new(tilemap, objects) {
+            Ok(reach) => Some(reach),
+            Err(e) => {
+                errors.push(format!("{e:#}"));
+                None
+            }
+        };
+        let graph = reach
+            .as_ref()
+            .map(|reach| ColorGraph::reachable(reach, objects))
+            .unwrap_or_default();
+
+        let tints = minions
+            .iter()
+            .map(|(kind, _)| (*kind, graph.tints(ColorDef::from(*kind))))
+            .collect();
+
+        let mut targets = vec![];
+        for (id, object) in objects.iter().enumerate() {
+            let Some(requirement) = MinionInteractionRequirement::for_object(object) else {
+                continue;
+            };
+            let missing = graph.missing(storage, &requirement);
+            let missing_abilities = graph.missing_abilities(storage, &requirement);
+            let unchecked = (!missing.is_empty() || !missing_abilities.is_empty())
+                && graph.needs_overlap(storage, &requirement);
+            let target = TargetReport {
+                id,
+                kind: object.kind,
+                requirement: MinionKind::VARIANTS
+                    .into_iter()
+                    .filter_map(|kind| Some((kind, *requirement.counts.get(&kind)?)))
+                    .filter(|(_, count)| *count > 0)
+                    .collect(),
+                abilities: Ability::VARIANTS
+                    .into_iter()
+                    .filter_map(|ability| Some((ability, *requirement.abilities.get(&ability)?)))
+                    .filter(|(_, count)| *count > 0)
+                    .collect(),
+                reachable: reach.as_ref().is_some_and(|r| r.reaches(object.position)),
+                missing,
+                missing_abilities,
+                unchecked,
+            };
+            let name = format!("Object {id} ({})", object.kind.as_str());
+            if !target.reachable {
+                errors.push(format!("{name} can't be reached from the player spawn"));
+            }
+            let missing = both(&target.missing, &target.missing_abilities);
+            if target.unchecked {
+                warnings.push(format!(
+                    "{name} is missing {missing} unless minions count for their kind and ability at once"
+                ));
+            } else if !missing.is_empty() {
+                errors.push(format!("{name} is missing {missing}"));
+            }
+            targets.push(target);
+        }
+
+        Self {
+            minions,
+            tints,
+            targets,
+            errors,
+            warnings,
+        }
+    }
+
+    pub fn is_solvable(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+fn counts<T: AsRef<str>>(counts: &[(T, u32)]) -> String {
+    counts
+        .iter()
+        .map(|(kind, count)| format!("{count} {}", kind.as_ref()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Minion counts followed by ability counts
+fn both(kinds: &[(MinionKind, u32)], abilities: &[(Ability, u32)]) -> String {
+    [counts(kinds), counts(abilities)]
+        .into_iter()
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl fmt::Display for PuzzleReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Starting minions: {}", counts(&self.minions))?;
+        writeln!(f, "Tints:")?;
+        for (kind, tints) in self.tints.iter() {
+            let tints = tints.iter().map(|c| c.as_str()).collect::<Vec<_>>();
+            writeln!(f, "  {} -> {}", kind.as_str(), tints.join(", "))?;
+        }
+        writeln!(f, "Targets:")?;
+        for target in self.targets.iter() {
+            writeln!(
+                f,
+                "  Object {} ({}) needs {}: {}",
+                target.id,
+                target.kind.as_str(),
+                both(&target.requirement, &target.abilities),
+                match (target.is_satisfiable(), target.unchecked) {
+                    (true, false) => "ok",
+                    (true, true) => "unchecked",
+                    (false, _) => "unsatisfiable",
+                }
+            )?;
+        }
+        if !self.warnings.is_empty() {
+            writeln!(f, "Warnings:")?;
+            for warning in self.warnings.iter() {
+                writeln!(f, "  {warning}")?;
+            }
+        }
+        if self.errors.is_empty() {
+            writeln!(f, "Solvable")?;
+        } else {
+            writeln!(f, "Errors:")?;
+            for error in self.errors.iter() {
+                writeln!(f, "  {error}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_color_graph() {
+    let graph = ColorGraph::new(vec![ColorDef::Red, ColorDef::Green], vec![ColorDef::Red]);
+    let tints = graph.tints(ColorDef::Void);
+    assert_eq!(tints.len(), 4);
+    assert!(tints.contains(&ColorDef::Yellow));
+    assert!(!tints.contains(&ColorDef::Blue));
+    // Spotlights take colors away again
+    assert!(graph.tints(ColorDef::Magenta).contains(&ColorDef::Blue));
+    // But don't see stealthy minions
+    let graph = ColorGraph::new(vec![ColorDef::Red], vec![ColorDef::Red, ColorDef::Green]);
+    assert_eq!(
+        graph.tints(ColorDef::Cyan),
+        vec![ColorDef::Cyan, ColorDef::White]
+    );
+    let graph = ColorGraph::new(vec![ColorDef::Red, ColorDef::Green], vec![ColorDef::Red]);
+
+    let mut storage = MinionStorage::new();
+    for _ in 0..3 {
+        storage.add_minion(MinionKind::Void);
+    }
+    storage.add_minion(MinionKind::Blue);
+    let requirement = |counts: &[(MinionKind, u32)]| {
+        MinionInteractionRequirement::new(counts.iter().copied().collect())
+    };
+
+    // Every void minion can turn yellow, but there are only three of them
+    let yellow = requirement(&[(MinionKind::Yellow, 2), (MinionKind::Red, 1)]);
+    assert!(graph.missing(&storage, &yellow).is_empty());
+    let yellow = requirement(&[(MinionKind::Yellow, 2), (MinionKind::Red, 2)]);
+    let missing = graph.missing(&storage, &yellow);
+    assert_eq!(missing.iter().map(|(_, count)| count).sum::<u32>(), 1);
+    let blue = requirement(&[(MinionKind::Blue, 2)]);
+    assert_eq!(graph.missing(&storage, &blue), vec![(MinionKind::Blue, 1)]);
+
+    let full = ColorGraph::new(vec![ColorDef::Blue], vec![]);
+    assert!(full.missing(&storage, &blue).is_empty());
+}
+
+#[test]
+fn test_missing_abilities() {
+    let graph = ColorGraph::new(vec![ColorDef::Red], vec![]);
+    let mut storage = MinionStorage::new();
+    storage.add_minion(MinionKind::Void);
+    storage.add_minion(MinionKind::Blue);
+    let requirement = |abilities: &[(Ability, u32)]| {
+        MinionInteractionRequirement::with_abilities(abilities.iter().copied().collect())
+    };
+
+    // Red and magenta minions are both strong, neither is conductive
+    let strong = requirement(&[(Ability::Strong, 2)]);
+    assert!(graph.missing_abilities(&storage, &strong).is_empty());
+    let conductive = requirement(&[(Ability::Conductive, 1)]);
+    assert_eq!(
+        graph.missing_abilities(&storage, &conductive),
+        vec![(Ability::Conductive, 1)]
+    );
+    let mut mixed = requirement(&[(Ability::Strong, 1)]);
+    mixed.counts.insert(MinionKind::Red, 1);
+    assert!(graph.missing(&storage, &mixed).is_empty());
+    assert!(graph.missing_abilities(&storage, &mixed).is_empty());
+
+    // A single red minion is enough for both, but is only counted once
+    let mut storage = MinionStorage::new();
+    storage.add_minion(MinionKind::Red);
+    assert!(!graph.missing(&storage, &mixed).is_empty());
+    assert!(graph.needs_overlap(&storage, &mixed));
+    mixed.counts.insert(MinionKind::Red, 2);
+    assert!(!graph.needs_overlap(&storage, &mixed));
+    assert!(!graph.needs_overlap(&storage, &strong));
+}
+
+#[test]
+fn test_puzzle_report() {
+    use crate::framework::level_asset::LevelAsset;
+
+    let fixture = include_bytes!("../../tests/fixtures/level_v4.level");
+    let asset = LevelAsset::from_bytes(fixture).unwrap();
+    let data = asset.data();
+    let spawn = data
+        .objects
+        .iter()
+        .find(|o| o.kind == ObjectDefKind::SpawnPoint)
+        .unwrap();
+    let mut objects = data.objects.clone();
+    objects.push(ObjectDef {
+        kind: ObjectDefKind::DestructibleTargetTest,
+        ..spawn.clone()
+    });
+
+    let report = PuzzleReport::new(&data.tilemap, &objects, &MinionStorage::player_start());
+    assert_eq!(report.targets.len(), 1);
+    assert!(report.is_solvable(), "{report}");
+
+    let report = PuzzleReport::new(&data.tilemap, &objects, &MinionStorage::new());
+    assert!(!report.targets[0].is_satisfiable());
+    assert_eq!(report.errors.len(), 1);
+
+    objects.retain(|o| o.kind != ObjectDefKind::SpawnPoint);
+    let report = PuzzleReport::new(&data.tilemap, &objects, &MinionStorage::player_start());
+    assert!(!report.targets[0].reachable);
+    assert!(!report.is_solvable());
+}
+
+#[test]
+fn test_laser_grid_reachability() {
+    let mut tilemap = Tilemap::new(UVec2::new(9, 9), 0).unwrap();
+    // A wall across the level with a gap of a single face
+    for y in (0..9).filter(|y| *y != 2) {
+        tilemap.face_data_mut()[y * 9 + 4].wall_height = 1;
+    }
+    let object = |kind, position| ObjectDef {
+        kind,
+        position,
+        rotation: 0.0,
+        color: ColorDef::Void,
+        number: 0,
+        obj_refs: vec![],
+        pos_refs: vec![],
+        tags: vec![],
+    };
+    let mut objects = vec![object(ObjectDefKind::SpawnPoint, Vec3::new(-3.0, 0.0, 0.0))];
+    let right = Vec3::new(3.0, 0.0, 0.0);
+
+    let reach = Reachability::new(&tilemap, &objects).unwrap();
+    assert!(reach.reaches(right));
+
+    // Minions can't walk through laser grids
+    objects.push(object(ObjectDefKind::LaserGrid, Vec3::new(0.0, 0.0, -2.0)));
+    let reach = Reachability::new(&tilemap, &objects).unwrap();
+    assert!(!reach.reaches(right));
+    assert!(reach.reaches(Vec3::new(-3.0, 0.0, 3.0)));
+}
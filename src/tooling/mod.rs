@@ -1,6 +1,21 @@
 pub mod fps_counter;
 pub mod free_camera;
 
+#[cfg(not(target_family = "wasm"))]
+pub mod level_baker;
+#[cfg(not(target_family = "wasm"))]
+pub mod level_generator;
+#[cfg(not(target_family = "wasm"))]
+pub mod level_inspector;
+#[cfg(not(target_family = "wasm"))]
+pub mod level_solver;
+#[cfg(not(target_family = "wasm"))]
+pub mod object_def_builder;
+#[cfg(not(target_family = "wasm"))]
+pub mod tilemap_asset;
+#[cfg(not(target_family = "wasm"))]
+pub mod tilemap_mesh_builder;
+
 #[cfg(not(target_family = "wasm"))]
 #[cfg(feature = "editor")]
 pub mod editor;
@@ -33,6 +33,28 @@ impl Rot8 {
         }
     }
 }
+impl Rot8 {
+    pub const ALL: [Rot8; 8] = [
+        Rot8::D0,
+        Rot8::D45,
+        Rot8::D90,
+        Rot8::D135,
+        Rot8::D180,
+        Rot8::D225,
+        Rot8::D270,
+        Rot8::D315,
+    ];
+
+    /// Rotated by 45°, `steps` times. Negative steps rotate the other way
+    pub fn rotated(self, steps: i32) -> Self {
+        Self::ALL[(self as i32 + steps).rem_euclid(8) as usize]
+    }
+
+    /// Mirrored along the X axis
+    pub fn mirrored(self) -> Self {
+        Self::ALL[(8 - self as usize) % 8]
+    }
+}
 impl Into<f32> for Rot8 {
     fn into(self) -> f32 {
         match self {
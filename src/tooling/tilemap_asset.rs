@@ -0,0 +1,351 @@
+use crate::{
+    framework::{
+        level_asset::{metadata::LevelMetadata, OrnamentalMesh},
+        tilemap::{Tilemap, TILE_SIZE_X, WALL_HEIGHT},
+        Pnormal2,
+    },
+    tooling::object_def_builder::{ObjectDefBuilder, Rot8},
+};
+use anyhow::bail;
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+};
+use ron::ser::PrettyConfig;
+use serde::{de::IgnoredAny, Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{BufWriter, Read};
+use std::path::Path;
+
+pub struct TilemapAssetPlugin;
+
+impl Plugin for TilemapAssetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Tilemap>()
+            .register_asset_loader(TilemapLoader);
+    }
+}
+
+#[derive(Reflect, Serialize, Deserialize)]
+pub struct TilemapRon {
+    pub version: u32,
+    pub metadata: LevelMetadata,
+    pub tilemap: Tilemap,
+    pub objects: Vec<ObjectDefBuilder>,
+    pub meshes: Vec<OrnamentalMeshBuilder>,
+    // dependencies: Vec<String>,
+    // embedded_dependencies: Vec<String>,
+    // dependencies_with_settings: Vec<(String, ())>,
+}
+impl TilemapRon {
+    pub const CURRENT_VERSION: u32 = 5;
+    /// Oldest version [`TilemapRon::from_bytes`] can still upgrade
+    pub const OLDEST_SUPPORTED_VERSION: u32 = 3;
+
+    pub fn new(
+        metadata: LevelMetadata,
+        tilemap: Tilemap,
+        objects: Vec<ObjectDefBuilder>,
+        meshes: Vec<OrnamentalMeshBuilder>,
+    ) -> Self {
+        Self {
+            version: Self::CURRENT_VERSION,
+            metadata,
+            tilemap,
+            objects,
+            meshes,
+        }
+    }
+
+    pub fn read<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let mut bytes = Vec::new();
+        let mut file = OpenOptions::new().read(true).open(path)?;
+        // let mut reader = BufReader::new(file);
+        file.read_to_end(&mut bytes)?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Parses a scene of any supported version and migrates it to the current layout.
+    /// When the format changes, freeze the old shape in its own struct with a `From`
+    /// impl into the next version and add a match arm here.
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        #[derive(Deserialize)]
+        struct VersionProbe {
+            version: u32,
+        }
+        let probe: VersionProbe = ron::de::from_bytes(bytes)?;
+
+        match probe.version {
+            Self::CURRENT_VERSION => Ok(ron::de::from_bytes(bytes)?),
+            4 => Ok(ron::de::from_bytes::<TilemapRonV4>(bytes)?.into()),
+            3 => Ok(TilemapRonV4::from(ron::de::from_bytes::<TilemapRonV3>(bytes)?).into()),
+            v if v > Self::CURRENT_VERSION => bail!(
+                "Tilemap scene version {v} is newer than the newest supported version {}",
+                Self::CURRENT_VERSION
+            ),
+            v => bail!(
+                "Tilemap scene version {v} is too old, the oldest supported version is {}",
+                Self::OLDEST_SUPPORTED_VERSION
+            ),
+        }
+    }
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(path)?;
+        let writer = BufWriter::new(file);
+        ron::ser::to_writer_pretty(writer, self, PrettyConfig::new())?;
+        Ok(())
+    }
+}
+
+/// Scene layout before ornamental meshes could be placed.
+/// The editor never saved any, so the list is skipped over.
+#[derive(Deserialize)]
+struct TilemapRonV4 {
+    metadata: LevelMetadata,
+    tilemap: Tilemap,
+    objects: Vec<ObjectDefBuilder>,
+    #[allow(dead_code)]
+    meshes: Vec<IgnoredAny>,
+}
+
+impl From<TilemapRonV4> for TilemapRon {
+    fn from(ron: TilemapRonV4) -> Self {
+        Self::new(ron.metadata, ron.tilemap, ron.objects, vec![])
+    }
+}
+
+/// Scene layout before the metadata block
+#[derive(Deserialize)]
+struct TilemapRonV3 {
+    tilemap: Tilemap,
+    objects: Vec<ObjectDefBuilder>,
+    meshes: Vec<IgnoredAny>,
+}
+
+impl From<TilemapRonV3> for TilemapRonV4 {
+    fn from(ron: TilemapRonV3) -> Self {
+        Self {
+            metadata: LevelMetadata::default(),
+            tilemap: ron.tilemap,
+            objects: ron.objects,
+            meshes: ron.meshes,
+        }
+    }
+}
+
+#[derive(Default)]
+struct TilemapLoader;
+
+impl AssetLoader for TilemapLoader {
+    type Asset = Tilemap;
+    type Settings = ();
+    type Error = anyhow::Error;
+
+    fn extensions(&self) -> &[&str] {
+        &["txt"]
+    }
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a Self::Settings,
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(TilemapRon::from_bytes(&bytes)?.tilemap)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Reflect, Serialize, Deserialize)]
+pub struct GroundOrnamentalMesh {
+    pub mesh_path: String,
+    pub coord: UVec2,
+    pub rot: Rot8,
+    pub collider: bool,
+}
+
+/// Sits against the `side` face of the wall standing on `coord`, facing outwards.
+/// `rot` rolls it around the face normal.
+#[derive(Debug, Clone, PartialEq, Reflect, Serialize, Deserialize)]
+pub struct WallSideOrnamentalMesh {
+    pub mesh_path: String,
+    pub coord: UVec2,
+    pub side: Pnormal2,
+    pub height: u32,
+    pub rot: Rot8,
+    pub collider: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Reflect, Serialize, Deserialize)]
+pub struct WallTopOrnamentalMesh {
+    pub mesh_path: String,
+    pub coord: UVec2,
+    pub rot: Rot8,
+    pub collider: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Reflect, Serialize, Deserialize)]
+pub enum OrnamentalMeshBuilder {
+    Ground(GroundOrnamentalMesh),
+    Side(WallSideOrnamentalMesh),
+    Top(WallTopOrnamentalMesh),
+}
+
+impl OrnamentalMeshBuilder {
+    pub fn coord(&self) -> UVec2 {
+        match self {
+            OrnamentalMeshBuilder::Ground(m) => m.coord,
+            OrnamentalMeshBuilder::Side(m) => m.coord,
+            OrnamentalMeshBuilder::Top(m) => m.coord,
+        }
+    }
+
+    pub fn coord_mut(&mut self) -> &mut UVec2 {
+        match self {
+            OrnamentalMeshBuilder::Ground(m) => &mut m.coord,
+            OrnamentalMeshBuilder::Side(m) => &mut m.coord,
+            OrnamentalMeshBuilder::Top(m) => &mut m.coord,
+        }
+    }
+
+    pub fn mesh_path(&self) -> &str {
+        match self {
+            OrnamentalMeshBuilder::Ground(m) => &m.mesh_path,
+            OrnamentalMeshBuilder::Side(m) => &m.mesh_path,
+            OrnamentalMeshBuilder::Top(m) => &m.mesh_path,
+        }
+    }
+
+    pub fn rot_mut(&mut self) -> &mut Rot8 {
+        match self {
+            OrnamentalMeshBuilder::Ground(m) => &mut m.rot,
+            OrnamentalMeshBuilder::Side(m) => &mut m.rot,
+            OrnamentalMeshBuilder::Top(m) => &mut m.rot,
+        }
+    }
+
+    /// Whether both sit on the same spot, regardless of mesh and rotation
+    pub fn same_placement(&self, other: &Self) -> bool {
+        use OrnamentalMeshBuilder::*;
+        match (self, other) {
+            (Ground(a), Ground(b)) => a.coord == b.coord,
+            (Top(a), Top(b)) => a.coord == b.coord,
+            (Side(a), Side(b)) => a.coord == b.coord && a.side == b.side && a.height == b.height,
+            _ => false,
+        }
+    }
+
+    /// Resolves the placement to a world-space transform on `tilemap`. `None` if it lies
+    /// outside of the tilemap.
+    pub fn build(&self, tilemap: &Tilemap) -> Option<OrnamentalMesh> {
+        if self.coord().cmpge(tilemap.dims()).any() {
+            return None;
+        }
+        let fid = tilemap.face_grid().coord_to_id(self.coord());
+        let center = tilemap.face_id_to_center_pos_2d(fid).unwrap_or_default();
+
+        let (asset_path, position, yaw, roll, collider) = match self {
+            OrnamentalMeshBuilder::Ground(m) => {
+                let y = tilemap.face_center_height(fid);
+                let position = Vec3::new(center.x, y, center.y);
+                (&m.mesh_path, position, m.rot.into(), 0.0, m.collider)
+            }
+            OrnamentalMeshBuilder::Side(m) => {
+                let normal = m.side.to_vec3();
+                let y = tilemap.face_wall_base_height(fid) + (m.height as f32 + 0.5) * WALL_HEIGHT;
+                let position = Vec3::new(center.x, y, center.y) + normal * TILE_SIZE_X * 0.5;
+                let yaw = normal.x.atan2(normal.z);
+                (&m.mesh_path, position, yaw, m.rot.into(), m.collider)
+            }
+            OrnamentalMeshBuilder::Top(m) => {
+                let wall_height = tilemap
+                    .single_face_data(fid)
+                    .map_or(0, |face| face.wall_height);
+                let y = tilemap.face_wall_base_height(fid) + wall_height as f32 * WALL_HEIGHT;
+                let position = Vec3::new(center.x, y, center.y);
+                (&m.mesh_path, position, m.rot.into(), 0.0, m.collider)
+            }
+        };
+
+        Some(OrnamentalMesh {
+            asset_path: asset_path.clone(),
+            position,
+            rotation: Vec3::new(0.0, yaw, roll),
+            collider,
+        })
+    }
+}
+
+#[test]
+fn test_tilemap_ron_versions() {
+    let fixture = include_bytes!("../../tests/fixtures/tilemap_v3.ron");
+    let ron = TilemapRon::from_bytes(fixture).unwrap();
+    assert_eq!(ron.version, TilemapRon::CURRENT_VERSION);
+    assert_eq!(ron.metadata, LevelMetadata::default());
+    assert_eq!(ron.tilemap.dims(), UVec2::new(3, 2));
+    assert_eq!(ron.objects.len(), 1);
+
+    let current = ron::ser::to_string(&ron).unwrap();
+    let future = current.replacen(
+        &format!("version:{}", TilemapRon::CURRENT_VERSION),
+        &format!("version:{}", TilemapRon::CURRENT_VERSION + 1),
+        1,
+    );
+    assert_ne!(current, future);
+    assert!(TilemapRon::from_bytes(future.as_bytes()).is_err());
+}
+
+#[test]
+fn test_ornamental_mesh_build() {
+    let mut tilemap = Tilemap::new(UVec2::new(2, 2), 0).unwrap();
+    let wall = tilemap.face_grid().coord_to_id(UVec2::new(1, 1));
+    tilemap.single_face_data_mut(wall).unwrap().wall_height = 2;
+
+    let ground = OrnamentalMeshBuilder::Ground(GroundOrnamentalMesh {
+        mesh_path: "ground.glb#Scene0".to_owned(),
+        coord: UVec2::new(0, 0),
+        rot: Rot8::D90,
+        collider: true,
+    })
+    .build(&tilemap)
+    .unwrap();
+    assert_eq!(ground.position, Vec3::new(-0.5, 0.0, -0.5));
+    assert!(ground.collider);
+
+    let top = OrnamentalMeshBuilder::Top(WallTopOrnamentalMesh {
+        mesh_path: "top.glb#Scene0".to_owned(),
+        coord: UVec2::new(1, 1),
+        rot: Rot8::D0,
+        collider: false,
+    })
+    .build(&tilemap)
+    .unwrap();
+    assert_eq!(top.position, Vec3::new(0.5, 2.0, 0.5));
+
+    let side = OrnamentalMeshBuilder::Side(WallSideOrnamentalMesh {
+        mesh_path: "side.glb#Scene0".to_owned(),
+        coord: UVec2::new(1, 1),
+        side: Pnormal2::NO,
+        height: 1,
+        rot: Rot8::D0,
+        collider: false,
+    })
+    .build(&tilemap)
+    .unwrap();
+    assert_eq!(side.position, Vec3::new(0.0, 1.5, 0.5));
+    let facing = side.transform().rotation * Vec3::Z;
+    assert!(facing.abs_diff_eq(Vec3::NEG_X, 1e-5));
+
+    let outside = OrnamentalMeshBuilder::Ground(GroundOrnamentalMesh {
+        mesh_path: "ground.glb#Scene0".to_owned(),
+        coord: UVec2::new(2, 0),
+        rot: Rot8::D0,
+        collider: false,
+    });
+    assert!(outside.build(&tilemap).is_none());
+}
@@ -142,9 +142,7 @@ impl<'a> RawMeshBuilder<'a> {
             let x = coord.x as f32 + offset.x;
             let z = coord.y as f32 + offset.y;
 
-            let y_inc = ((self.tilemap.face_base_elevation(fid) as f32 * SLOPE_HEIGHT)
-                / WALL_HEIGHT) as u32;
-            let base_y = y_inc as f32 * WALL_HEIGHT;
+            let base_y = self.tilemap.face_wall_base_height(fid);
 
             for h in 0..height {
                 let y = base_y + h as f32 * WALL_HEIGHT;